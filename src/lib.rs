@@ -1,4 +1,6 @@
 mod modules;
 mod sys;
 
+#[cfg(feature = "modcoords")]
+pub use modules::{BackupManifest, CoordsArchive, CoordsBackup};
 pub use sys::*;
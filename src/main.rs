@@ -1,5 +1,16 @@
-use merlin::{Clearance, CommandHandler, MasterOptions, MasterSwitch, MASTER};
+use std::{
+    env,
+    io::{IsTerminal, Read},
+    sync::Arc,
+};
+
+use merlin::{
+    config_dir, Clearance, CommandHandler, ConfigFile, Console, GuildClearance, GuildSwitch,
+    LoggingConfig, MasterOptions, MasterSwitch, MatrixFormat, Migrations, PermCheck, PermMatrix,
+    Shutdown, Storage, MASTER,
+};
 use serenity::{all::*, async_trait, Client};
+use tracing::{error, info, warn};
 
 struct Handler;
 
@@ -8,10 +19,15 @@ impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
         let master = unsafe { MASTER.get() }.unwrap();
         if msg.content.starts_with(master.prefix.as_str()) {
+            let _in_flight = match Shutdown::enter() {
+                Some(in_flight) => in_flight,
+                None => return,
+            };
+
             if let Ok(args) = shell_words::split(&msg.content[master.prefix.len()..]) {
                 CommandHandler::run(
                     args.iter().map(String::as_str).collect::<Vec<_>>().as_ref(),
-                    &ctx,
+                    &merlin::Context::from(ctx),
                     &msg,
                 )
                 .await;
@@ -20,28 +36,40 @@ impl EventHandler for Handler {
     }
 
     async fn ready(&self, ctx: Context, ready: Ready) {
-        println!(
-            "{}#{} is connected! (shard #{})",
-            ready.user.name,
-            ready
-                .user
-                .discriminator
-                .map(|n| n.get())
-                .unwrap_or_default(),
-            ctx.shard_id
+        info!(
+            user = %ready.user.name,
+            discriminator = ready.user.discriminator.map(|n| n.get()).unwrap_or_default(),
+            shard = %ctx.shard_id,
+            "connected"
         );
     }
 }
 
 #[tokio::main]
 async fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    // these only read the files on disk and the store as they are, so nothing may be loaded before them
+    if let ["config" | "perms", ..] = args.as_slice() {
+        offline(&args).await;
+        return;
+    }
+
+    let _log_guard = LoggingConfig::setup();
     MasterOptions::setup();
     MasterSwitch::setup();
+    GuildSwitch::setup();
     Clearance::setup();
+    GuildClearance::setup();
     let intents = GatewayIntents::all();
 
-    #[cfg(feature = "mongo")]
-    merlin::Mongo::load().await;
+    Storage::load().await;
+
+    if !args.is_empty() {
+        cli(&args).await;
+        return;
+    }
 
     let client = Client::builder(unsafe { MASTER.get() }.unwrap().token.as_str(), intents)
         .event_handler(Handler)
@@ -51,7 +79,391 @@ async fn main() {
     CommandHandler::client_set(client);
     CommandHandler::load(false).await;
 
-    if let Err(e) = CommandHandler::client_mut().start().await {
-        println!("Client error: {e:?}");
+    tokio::spawn(signals(CommandHandler::client_mut().shard_manager.clone()));
+    tokio::spawn(Storage::monitor());
+
+    let data = CommandHandler::client_mut().data.clone();
+    let http = CommandHandler::client_mut().http.clone();
+    let console_socket = unsafe { MASTER.get() }.unwrap().console_socket.clone();
+
+    if std::io::stdin().is_terminal() {
+        tokio::spawn(Console::stdin(data.clone(), http.clone()));
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = console_socket.clone() {
+        tokio::spawn(Console::socket(path, data, http));
+    }
+
+    let res = tokio::select! {
+        res = CommandHandler::client_mut().start() => res,
+        _ = Shutdown::stopped() => Ok(()),
+    };
+
+    if let Err(e) = res {
+        error!(error = ?e, "client error");
+
+        // the console stays up so the bot can still be fixed without discord
+        if console_socket.is_some() || std::io::stdin().is_terminal() {
+            warn!("discord is unavailable, only the console is accepting commands");
+            Shutdown::stopped().await;
+        }
+    }
+
+    if Shutdown::restart_requested() {
+        info!("restarting");
+        restart();
+    }
+}
+
+// the first signal shuts down gracefully, a second one exits immediately
+async fn signals(shard_manager: Arc<ShardManager>) {
+    #[cfg(unix)]
+    let mut terminate =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()).unwrap();
+
+    loop {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+
+        if Shutdown::is_shutting_down() {
+            std::process::exit(130);
+        }
+
+        warn!("shutting down, send the signal again to exit immediately");
+        tokio::spawn(Shutdown::begin(false, Some(shard_manager.clone())));
+    }
+}
+
+fn restart() {
+    let exe = env::current_exe().unwrap();
+    let mut command = std::process::Command::new(exe);
+    command.args(env::args_os().skip(1));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        error!(error = %command.exec(), "restart failed");
+    }
+
+    #[cfg(not(unix))]
+    if let Err(e) = command.spawn() {
+        error!(error = %e, "restart failed");
+    }
+}
+
+async fn cli(args: &[&str]) {
+    match args {
+        ["permmatrix", rest @ ..] if rest.len() <= 1 => {
+            let format = match rest.first() {
+                Some(format) => match MatrixFormat::parse(format) {
+                    Some(format) => format,
+                    None => {
+                        eprintln!("Unknown format {format}, expected csv or md.");
+                        return;
+                    }
+                },
+                None => MatrixFormat::Markdown,
+            };
+
+            CommandHandler::load(false).await;
+            match PermMatrix::build().await {
+                Ok(matrix) => print!("{}", matrix.render(format)),
+                Err(e) => {
+                    eprintln!("Could not read the stored rules: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        ["console", rest @ ..] => console(rest).await,
+        ["migrate"] => migrate(false).await,
+        ["migrate", "--dry-run"] => migrate(true).await,
+        #[cfg(feature = "modcoords")]
+        ["coords", "export", rest @ ..] if rest.len() <= 1 => {
+            CommandHandler::load(false).await;
+            if !CommandHandler::get().modules.contains_key("coords") {
+                eprintln!("The coords module is disabled.");
+                std::process::exit(1);
+            }
+
+            let archive = match merlin::CoordsArchive::export().await {
+                Ok(archive) => archive,
+                Err(e) => {
+                    eprintln!("Could not read coords: {e}");
+                    std::process::exit(1);
+                }
+            };
+
+            let json = serde_json::to_string_pretty(&archive).unwrap();
+            match rest.first() {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(path, json) {
+                        eprintln!("Could not write {path}: {e}");
+                        std::process::exit(1);
+                    }
+                    println!(
+                        "Exported {} categories and {} coords to {path}.",
+                        archive.categories.len(),
+                        archive.coords.len()
+                    );
+                }
+                None => println!("{json}"),
+            }
+        }
+        #[cfg(feature = "modcoords")]
+        ["coords", "import", path] => {
+            let archive = match std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_json::from_str::<merlin::CoordsArchive>(&content)
+                        .map_err(|e| e.to_string())
+                }) {
+                Ok(archive) => archive,
+                Err(e) => {
+                    eprintln!("Could not read {path}: {e}");
+                    std::process::exit(1);
+                }
+            };
+
+            CommandHandler::load(false).await;
+            if !CommandHandler::get().modules.contains_key("coords") {
+                eprintln!("The coords module is disabled.");
+                std::process::exit(1);
+            }
+
+            if let Err(e) = archive.import().await {
+                eprintln!("Could not import coords: {e}");
+                std::process::exit(1);
+            }
+
+            println!(
+                "Imported {} categories and {} coords.",
+                archive.categories.len(),
+                archive.coords.len()
+            );
+        }
+        #[cfg(feature = "modcoords")]
+        ["coords", "backup", path, rest @ ..] if matches!(rest, [] | ["--attachments"]) => {
+            CommandHandler::load(false).await;
+            if !CommandHandler::get().modules.contains_key("coords") {
+                eprintln!("The coords module is disabled.");
+                std::process::exit(1);
+            }
+
+            match merlin::CoordsBackup::create(std::path::Path::new(path), !rest.is_empty()).await {
+                Ok(manifest) => println!("Backed up {} to {path}.", manifest.summary()),
+                Err(e) => {
+                    eprintln!("Could not back up coords: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "modcoords")]
+        ["coords", "restore", path] => {
+            CommandHandler::load(false).await;
+            if !CommandHandler::get().modules.contains_key("coords") {
+                eprintln!("The coords module is disabled.");
+                std::process::exit(1);
+            }
+
+            match merlin::CoordsBackup::restore(std::path::Path::new(path)).await {
+                Ok(manifest) => println!("Restored {} from {path}.", manifest.summary()),
+                Err(e) => {
+                    eprintln!("Could not restore {path}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
     }
 }
+
+// config files are checked as they are on disk, a file that is missing counts as its defaults
+async fn offline(args: &[&str]) {
+    match args {
+        ["config", "path"] => println!("{}", config_dir().display()),
+        ["config", "path", name] => match ConfigFile::all()
+            .into_iter()
+            .find(|file| file.name == *name)
+        {
+            Some(file) => println!("{}", file.path.display()),
+            None => {
+                eprintln!("Unknown config {name}.");
+                std::process::exit(1);
+            }
+        },
+        ["config", "init"] => {
+            for file in ConfigFile::all() {
+                if file.init() {
+                    println!("created  {}", file.path.display());
+                } else {
+                    println!("exists   {}", file.path.display());
+                }
+            }
+        }
+        ["config", "check"] => {
+            let mut failed = false;
+
+            for file in ConfigFile::all() {
+                match file.check() {
+                    Some(Ok(())) => println!("ok       {}", file.path.display()),
+                    Some(Err(e)) => {
+                        failed = true;
+                        println!("invalid  {}: {e}", file.path.display());
+                    }
+                    None => println!("missing  {} (defaults are used)", file.path.display()),
+                }
+            }
+
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        ["perms", "check"] => {
+            let problems = PermCheck::run().await;
+
+            if problems.is_empty() {
+                println!("All rules are valid.");
+                return;
+            }
+
+            for problem in problems.iter() {
+                println!("{problem}");
+            }
+            std::process::exit(1);
+        }
+        _ => usage(),
+    }
+}
+
+// the bot applies pending migrations on startup, this is for checking them beforehand
+async fn migrate(dry_run: bool) {
+    if Storage::try_get().is_none() {
+        eprintln!("Storage is not available.");
+        std::process::exit(1);
+    }
+
+    let mut modules = CommandHandler::available();
+    modules.sort_by_key(|module| module.name().to_string());
+    let mut failed = false;
+
+    for module in modules {
+        let migrations = module.migrations();
+        if migrations.is_empty() {
+            continue;
+        }
+
+        let name = module.name();
+        let current = Migrations::version(name).await.unwrap_or_default();
+
+        match Migrations::apply(name, &migrations, dry_run).await {
+            Ok(reports) if reports.is_empty() => {
+                println!("{name}: up to date at version {current}")
+            }
+            Ok(reports) => {
+                for report in reports {
+                    println!(
+                        "{name}: {} {} {}, {} documents changed",
+                        if dry_run { "would apply" } else { "applied" },
+                        report.version,
+                        report.description,
+                        report.changed
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("{name}: migration failed: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn usage() {
+    eprintln!(
+        "Usage:
+{0}                             start the bot
+{0} permmatrix (csv|md)         print the permission matrix
+{0} console (command)           run commands on a running bot
+{0} config init                 create missing config files with defaults
+{0} config check                check that every config file can be parsed
+{0} config path (config)        print the config directory, or the path of a config
+{0} perms check                 check every rule list and clearance preset
+{0} coords export (file)        write every category and coord as json
+{0} coords import [file]        add or replace categories and coords from json
+{0} coords backup [file] (--attachments)
+                                write the whole coords database to a .tar.gz
+{0} coords restore [file]       rebuild an empty coords database from a backup
+{0} migrate (--dry-run)         apply pending storage migrations, or list them",
+        env!("CARGO_PKG_NAME")
+    );
+}
+
+// sends commands to the console socket of a running bot, from the arguments or one per line from stdin
+#[cfg(unix)]
+async fn console(args: &[&str]) {
+    use merlin::END_OF_REPLY;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::UnixStream,
+    };
+
+    let path = match unsafe { MASTER.get() }.unwrap().console_socket.as_ref() {
+        Some(path) => path,
+        None => {
+            eprintln!("The console socket is disabled, set console-socket in master.jsonc.");
+            return;
+        }
+    };
+
+    let stream = match UnixStream::connect(path).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Could not connect to {path}: {e}");
+            return;
+        }
+    };
+
+    let commands = if args.is_empty() {
+        let mut input = String::new();
+        let _ = std::io::stdin().read_to_string(&mut input);
+        input.lines().map(str::to_string).collect::<Vec<_>>()
+    } else {
+        vec![shell_words::join(args)]
+    };
+
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    for command in commands.iter().filter(|command| !command.trim().is_empty()) {
+        if write
+            .write_all(format!("{command}\n").as_bytes())
+            .await
+            .is_err()
+        {
+            eprintln!("Connection closed.");
+            return;
+        }
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line == END_OF_REPLY {
+                break;
+            }
+            println!("{line}");
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn console(_: &[&str]) {
+    eprintln!("The console socket is only available on unix.");
+}
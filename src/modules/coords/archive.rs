@@ -0,0 +1,106 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{Counter, Filter, Sort};
+
+use super::{
+    category::Category,
+    collection::{CATEGORIES, COORDS},
+    coord::Coord,
+};
+
+// every category and coord entry, as written by `merlin coords export`
+#[derive(Serialize, Deserialize, Default)]
+pub struct CoordsArchive {
+    pub categories: Vec<Category>,
+    pub coords: Vec<Coord>,
+}
+
+impl CoordsArchive {
+    pub async fn export() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            categories: unsafe { CATEGORIES.get() }
+                .unwrap()
+                .find_sorted(&Filter::new(), Sort::Ascending("_id"))
+                .await?,
+            coords: unsafe { COORDS.get() }
+                .unwrap()
+                .find_sorted(&Filter::new(), Sort::Ascending("_id"))
+                .await?,
+        })
+    }
+
+    // entries with the same id are replaced, nothing is written if a name is taken by another entry
+    pub async fn import(&self) -> Result<(), Box<dyn Error>> {
+        let categories = unsafe { CATEGORIES.get() }.unwrap();
+        let coords = unsafe { COORDS.get() }.unwrap();
+
+        for cog in self.categories.iter() {
+            if let Some(existing) = categories
+                .find_one(
+                    Filter::new()
+                        .eq("world", cog.world.as_str())
+                        .eq("name", cog.name.as_str()),
+                )
+                .await?
+            {
+                if existing.id != cog.id {
+                    return Err(format!(
+                        "category {} already exists with id {}",
+                        cog.name, existing.id
+                    )
+                    .into());
+                }
+            }
+        }
+
+        for coord in self.coords.iter() {
+            if let Some(existing) = coords
+                .find_one(
+                    Filter::new()
+                        .eq("world", coord.world.as_str())
+                        .eq("name", coord.name.as_str()),
+                )
+                .await?
+            {
+                if existing.id != coord.id {
+                    return Err(format!(
+                        "coord {} already exists with id {}",
+                        coord.name, existing.id
+                    )
+                    .into());
+                }
+            }
+        }
+
+        for cog in self.categories.iter() {
+            categories.upsert_one(cog.id, cog).await?;
+        }
+
+        for coord in self.coords.iter() {
+            let coord = Coord {
+                pos: [coord.x, coord.z],
+                ..coord.clone()
+            };
+            coords.upsert_one(coord.id, &coord).await?;
+        }
+
+        if let Some(max) = self.categories.iter().map(|cog| cog.id).max() {
+            Counter::raise("coords-categories", max + 1).await?;
+        }
+
+        if let Some(max) = self.coords.iter().map(|coord| coord.id).max() {
+            Counter::raise("coords-coords", max + 1).await?;
+        }
+
+        info!(
+            categories = self.categories.len(),
+            coords = self.coords.len(),
+            "imported coords"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig};
+
+use super::backupfile::CoordsBackup;
+
+pub struct CmdBackup;
+
+#[async_trait]
+impl Command for CmdBackup {
+    fn name(&self) -> &str {
+        "backup"
+    }
+
+    fn description(&self) -> &str {
+        "Write the whole coords DB to an archive on the host."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["(attachments)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let attachments = match args {
+            [] => false,
+            ["attachments"] => true,
+            _ => return false,
+        };
+
+        let name = format!(
+            "coords-{}.tar.gz",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        );
+        let path = CoordsBackup::dir().join(&name);
+
+        match CoordsBackup::create(&path, attachments).await {
+            Ok(manifest) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        format!(
+                            "Backup `{name}` created with {}{}.",
+                            manifest.summary(),
+                            if attachments {
+                                ", including attachments"
+                            } else {
+                                ""
+                            }
+                        ),
+                    )
+                    .await;
+            }
+            Err(e) => {
+                let _ = ctx.reply(msg, format!("Backup failed: {e}")).await;
+            }
+        }
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
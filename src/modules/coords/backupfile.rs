@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs::File,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::fs;
+use tracing::info;
+
+use crate::{config_dir, Counter, Filter, Migrations, Sort, Storage, Transaction, Write};
+
+use super::{
+    category::Category,
+    collection::{CATEGORIES_NAME, COORDS, COORDS_NAME, REVISIONS_NAME, TRASH, TRASH_NAME},
+    config::COORDS_CONFIG,
+    deleted::Trashed,
+    migrations,
+};
+
+const FORMAT: u32 = 1;
+const COLLECTIONS: &[&str] = &[CATEGORIES_NAME, COORDS_NAME, TRASH_NAME, REVISIONS_NAME];
+// categories take their ids from a counter not named after their collection
+const COUNTERS: &[&str] = &["coords-categories", COORDS_NAME, TRASH_NAME, REVISIONS_NAME];
+
+// written first in every backup as manifest.json
+#[derive(Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub version: String,
+    pub created_at: i64,
+    // the coords migration the documents are at
+    pub migration: u32,
+    // number of documents in each collection
+    pub collections: BTreeMap<String, usize>,
+    // next id of each counter
+    pub counters: BTreeMap<String, i64>,
+    pub attachments: bool,
+}
+
+impl BackupManifest {
+    // for replies and the cli
+    pub fn summary(&self) -> String {
+        let count = |name: &str| self.collections.get(name).copied().unwrap_or_default();
+        format!(
+            "{} categories and {} coords",
+            count(CATEGORIES_NAME),
+            count(COORDS_NAME)
+        )
+    }
+}
+
+// a .tar.gz holding the whole coords module
+//   manifest.json
+//   data/<collection>.json     every document as stored
+//   attachments/<coord id>/    attachment directory of a coord
+//   trash/<trash id>/          attachment directory of a trashed coord
+pub struct CoordsBackup;
+
+impl CoordsBackup {
+    // relative paths in the config are inside the config directory
+    pub fn dir() -> PathBuf {
+        config_dir().join(&unsafe { COORDS_CONFIG.get() }.unwrap().backup_path)
+    }
+
+    // the archive is written next to the path and renamed once complete
+    pub async fn create(
+        path: &Path,
+        attachments: bool,
+    ) -> Result<BackupManifest, Box<dyn Error + Send + Sync>> {
+        let store = Storage::get();
+        let mut data = Vec::new();
+        let mut collections = BTreeMap::new();
+
+        for name in COLLECTIONS {
+            let docs = store
+                .find(name, &Filter::new(), Sort::Ascending("_id"))
+                .await?;
+            collections.insert(name.to_string(), docs.len());
+            data.push((
+                format!("data/{name}.json"),
+                serde_json::to_vec_pretty(&docs)?,
+            ));
+        }
+
+        let mut counters = BTreeMap::new();
+        for name in COUNTERS {
+            counters.insert(name.to_string(), Counter::peek(name).await?);
+        }
+
+        let mut dirs = Vec::new();
+        if attachments {
+            for coord in unsafe { COORDS.get() }
+                .unwrap()
+                .find(&Filter::new())
+                .await?
+            {
+                let dir = Category::path(coord.cog, coord.subcog)
+                    .await?
+                    .join(coord.id.to_string());
+                if fs::try_exists(&dir).await? {
+                    dirs.push((format!("attachments/{}", coord.id), dir));
+                }
+            }
+
+            for entry in unsafe { TRASH.get() }.unwrap().find(&Filter::new()).await? {
+                if let Some(dir) = entry.attachments.map(PathBuf::from) {
+                    if fs::try_exists(&dir).await? {
+                        dirs.push((format!("trash/{}", entry.id), dir));
+                    }
+                }
+            }
+        }
+
+        let manifest = BackupManifest {
+            format: FORMAT,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            migration: Migrations::version("coords").await?,
+            collections,
+            counters,
+            attachments,
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+
+        let archive = path.to_path_buf();
+        tokio::task::spawn_blocking(move || -> Result<(), std::io::Error> {
+            if let Some(parent) = archive.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let partial = PathBuf::from(format!("{}.part", archive.display()));
+            let mut tar = tar::Builder::new(GzEncoder::new(
+                File::create(&partial)?,
+                Compression::default(),
+            ));
+
+            append(&mut tar, "manifest.json", &manifest_json)?;
+            for (name, content) in data.iter() {
+                append(&mut tar, name, content)?;
+            }
+            for (name, dir) in dirs.iter() {
+                tar.append_dir_all(name, dir)?;
+            }
+
+            tar.into_inner()?.finish()?;
+            std::fs::rename(partial, archive)
+        })
+        .await??;
+
+        info!(
+            path = %path.display(),
+            attachments,
+            "coords backup created"
+        );
+
+        Ok(manifest)
+    }
+
+    // only into a database without any coords data, attachments are put where the restored entries expect them
+    pub async fn restore(path: &Path) -> Result<BackupManifest, Box<dyn Error + Send + Sync>> {
+        let archive = path.to_path_buf();
+        let (manifest, data) = tokio::task::spawn_blocking(move || read(&archive)).await??;
+
+        let manifest = manifest.ok_or("the archive has no manifest")?;
+        let manifest: BackupManifest = serde_json::from_slice(&manifest)?;
+
+        if manifest.format != FORMAT {
+            return Err(format!("unsupported backup format {}", manifest.format).into());
+        }
+
+        let steps = migrations::all();
+        let latest = steps.iter().map(|step| step.version).max().unwrap_or(0);
+        if manifest.migration > latest {
+            return Err(format!(
+                "the backup was made by a newer version ({})",
+                manifest.version
+            )
+            .into());
+        }
+
+        let store = Storage::get();
+        for name in COLLECTIONS {
+            if store.find_one(name, &Filter::new()).await?.is_some() {
+                return Err(format!("{name} is not empty, restore needs a fresh database").into());
+            }
+        }
+
+        let mut writes = Vec::new();
+        for name in COLLECTIONS {
+            let docs: Vec<Value> = match data.get(*name) {
+                Some(content) => serde_json::from_slice(content)?,
+                None => continue,
+            };
+
+            for doc in docs {
+                let id = doc
+                    .get("_id")
+                    .and_then(Value::as_i64)
+                    .ok_or_else(|| format!("a document in {name} has no id"))?;
+                writes.push(Write::Insert {
+                    collection: name,
+                    id,
+                    doc,
+                });
+            }
+        }
+
+        // counters first, a failed restore only leaves them raised, which skips ids instead of reusing them
+        for (name, next) in manifest.counters.iter() {
+            Counter::raise(name, *next).await?;
+        }
+
+        // every document or none, a failed restore leaves the collections empty for the next try
+        store.commit(writes).await?;
+
+        // documents from an older version are brought up to date
+        if manifest.migration < latest {
+            Migrations::set_version("coords", manifest.migration).await?;
+            Migrations::apply("coords", &steps, false).await?;
+        }
+
+        if manifest.attachments {
+            let mut targets = HashMap::new();
+
+            for coord in unsafe { COORDS.get() }
+                .unwrap()
+                .find(&Filter::new())
+                .await?
+            {
+                targets.insert(
+                    format!("attachments/{}", coord.id),
+                    Category::path(coord.cog, coord.subcog)
+                        .await?
+                        .join(coord.id.to_string()),
+                );
+            }
+
+            // the directory in the archive is not trusted, it is where the coord was when removed
+            let mut tx = Transaction::new();
+            for mut entry in unsafe { TRASH.get() }.unwrap().find(&Filter::new()).await? {
+                let item = match (&entry.trashed, &entry.attachments) {
+                    (Trashed::Coord { item }, Some(_)) => item,
+                    _ => continue,
+                };
+
+                let dir = Category::path(item.cog, item.subcog)
+                    .await?
+                    .join(item.id.to_string());
+                targets.insert(format!("trash/{}", entry.id), dir.clone());
+
+                let dir = dir.to_string_lossy().to_string();
+                if entry.attachments.as_ref() != Some(&dir) {
+                    entry.attachments = Some(dir);
+                    tx.replace(unsafe { TRASH.get() }.unwrap(), entry.id, &entry)?;
+                }
+            }
+            tx.commit().await?;
+
+            let archive = path.to_path_buf();
+            tokio::task::spawn_blocking(move || unpack(&archive, &targets)).await??;
+        }
+
+        info!(
+            path = %path.display(),
+            collections = ?manifest.collections,
+            "coords backup restored"
+        );
+
+        Ok(manifest)
+    }
+}
+
+fn append(
+    tar: &mut tar::Builder<GzEncoder<File>>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), std::io::Error> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, name, content)
+}
+
+// (manifest, collection name to its json)
+type Contents = (Option<Vec<u8>>, HashMap<String, Vec<u8>>);
+
+fn read(path: &Path) -> Result<Contents, std::io::Error> {
+    let mut tar = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut manifest = None;
+    let mut data = HashMap::new();
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+
+        let collection = match name.as_str() {
+            "manifest.json" => None,
+            _ => match name
+                .strip_prefix("data/")
+                .and_then(|name| name.strip_suffix(".json"))
+            {
+                Some(collection) => Some(collection.to_string()),
+                None => continue,
+            },
+        };
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+
+        match collection {
+            Some(collection) => {
+                data.insert(collection, content);
+            }
+            None => manifest = Some(content),
+        }
+    }
+
+    Ok((manifest, data))
+}
+
+// entries under "<prefix>/" are unpacked into the directory of the prefix, anything else is skipped
+fn unpack(path: &Path, targets: &HashMap<String, PathBuf>) -> Result<(), std::io::Error> {
+    let mut tar = tar::Archive::new(GzDecoder::new(File::open(path)?));
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+
+        let mut components = name.components();
+        let prefix = match (components.next(), components.next()) {
+            (Some(Component::Normal(first)), Some(Component::Normal(second))) => {
+                format!("{}/{}", first.to_string_lossy(), second.to_string_lossy())
+            }
+            _ => continue,
+        };
+
+        let rest = components.as_path();
+        let target = match targets.get(&prefix) {
+            Some(target)
+                if rest
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_))) =>
+            {
+                target.join(rest)
+            }
+            _ => continue,
+        };
+
+        if entry.header().entry_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+
+    Ok(())
+}
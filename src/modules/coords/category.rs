@@ -1,25 +1,27 @@
 use std::{collections::HashMap, path::PathBuf};
 
-use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
-use serenity::futures::StreamExt;
 use tokio::fs;
 
 use crate::{
     modules::coords::collection::{CATEGORIES, COORDS},
-    CollectionItem, Counter, Mongo,
+    CollectionItem, Counter, Filter, StoreError, Transaction,
 };
 
-use super::config::COORDS_CONFIG;
+use super::{config::COORDS_CONFIG, world::default_world};
 
 // special categories
 // X.0 - uncategorised
 // 0.1 - private: author only
+// the generic categories are shared by all worlds
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Category {
     #[serde(rename = "_id")]
     pub id: i64,
+    // categories from before worlds existed are in the default one
+    #[serde(default = "default_world")]
+    pub world: String,
     pub name: String,
     pub display_name: String,
     pub description: String,
@@ -68,16 +70,19 @@ impl Subcategory {
 
 impl Category {
     // category, category id, subcog id
-    pub async fn cogs_from_name(name: &str) -> Option<(Option<Category>, i64, Option<i64>)> {
+    pub async fn cogs_from_name(
+        world: &str,
+        name: &str,
+    ) -> Result<Option<(Option<Category>, i64, Option<i64>)>, StoreError> {
         match name {
-            "generic.unspecified" => return Some((None, 0, Some(0))),
-            "generic.private" => return Some((None, 0, Some(1))),
-            "generic" => return Some((None, 0, None)),
+            "generic.unspecified" => return Ok(Some((None, 0, Some(0)))),
+            "generic.private" => return Ok(Some((None, 0, Some(1)))),
+            "generic" => return Ok(Some((None, 0, None))),
             _ => {}
         }
 
-        if let Some((left, right)) = name.split_once('.') {
-            Self::get(left).await.and_then(|cog| {
+        Ok(if let Some((left, right)) = name.split_once('.') {
+            Self::get(world, left).await?.and_then(|cog| {
                 let id = cog.id;
                 let subcog = if right == "unspecified" {
                     0
@@ -87,11 +92,11 @@ impl Category {
                 Some((Some(cog), id, Some(subcog)))
             })
         } else {
-            Self::get(name).await.map(|cog| {
+            Self::get(world, name).await?.map(|cog| {
                 let id = cog.id;
                 (Some(cog), id, None)
             })
-        }
+        })
     }
 
     pub fn contains(&self, subcog: &str) -> bool {
@@ -108,40 +113,51 @@ impl Category {
             .find(|val| val.name == subcog)
     }
 
-    pub async fn get(display_name: &str) -> Option<Self> {
+    pub async fn get(world: &str, display_name: &str) -> Result<Option<Self>, StoreError> {
         let name = display_name.replace(' ', "-").to_lowercase();
 
         let categories = unsafe { CATEGORIES.get() }.unwrap();
 
-        categories.find_one(doc! {"name": &name}).await.unwrap()
+        categories
+            .find_one(Filter::new().eq("world", world).eq("name", name))
+            .await
     }
 
+    // the inner error is meant for the user
     pub async fn new(
+        world: String,
         display_name: String,
         description: String,
         attachment_path: Option<String>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Result<Self, &'static str>, StoreError> {
         let name = display_name.replace(' ', "-").to_lowercase();
 
         if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
-            return Err("name contains illegal characters");
+            return Ok(Err("name contains illegal characters"));
+        }
+
+        // coordadd would read it as a height
+        if name.parse::<i64>().is_ok() {
+            return Ok(Err("name cannot be an integer"));
         }
 
         let categories = unsafe { CATEGORIES.get() }.unwrap();
 
         if categories
-            .find_one(doc! {"name": &name})
-            .await
-            .unwrap()
+            .find_one(
+                Filter::new()
+                    .eq("world", world.as_str())
+                    .eq("name", name.as_str()),
+            )
+            .await?
             .is_some()
         {
-            return Err("a category with that name already exists");
+            return Ok(Err("a category with that name already exists"));
         }
 
         let out = Category {
-            id: Counter::bump_get("coords-categories", Mongo::database())
-                .await
-                .unwrap(),
+            id: Counter::bump_get("coords-categories").await?,
+            world,
             name,
             display_name,
             description,
@@ -151,26 +167,35 @@ impl Category {
             attachment_path,
         };
 
-        out.save_create(categories).await.unwrap();
-
-        Ok(out)
+        // the unique index catches names taken since the check above
+        match out.save_create(categories).await {
+            Ok(()) => Ok(Ok(out)),
+            Err(StoreError::Duplicate(_)) => Ok(Err("a category with that name already exists")),
+            Err(e) => Err(e),
+        }
     }
 
-    // returns false if the from dir contains folders, but a target dir is not specified
-    pub async fn move_all(cog: &Category, subcog: Option<i64>, from: &str, to: &str) {
+    // adds the moves to the transaction, nothing is moved until it is committed
+    pub async fn move_all(
+        cog: &Category,
+        subcog: Option<i64>,
+        from: &str,
+        to: &str,
+        tx: &mut Transaction,
+    ) -> Result<(), StoreError> {
         let from_dir = PathBuf::from(from);
         let to_dir = PathBuf::from(to);
 
-        let mut filter = doc! { "cog": cog.id };
+        let mut filter = Filter::new();
+        filter.eq("cog", cog.id);
         if let Some(subcog) = subcog {
-            filter.insert("subcog", subcog);
+            filter.eq("subcog", subcog);
         }
 
-        let mut queue = Vec::new();
-        let mut cursor = unsafe { COORDS.get() }.unwrap().find(filter).await.unwrap();
+        let mut moved = false;
+        let coords = unsafe { COORDS.get() }.unwrap().find(&filter).await?;
 
-        while let Some(coord) = cursor.next().await {
-            let coord = coord.unwrap();
+        for coord in coords {
             let from = from_dir.join(coord.id.to_string());
 
             if subcog.is_none()
@@ -182,70 +207,57 @@ impl Category {
                 continue;
             }
 
-            if fs::try_exists(&from).await.unwrap() {
+            if fs::try_exists(&from).await.unwrap_or_default() {
                 let to = to_dir.join(coord.id.to_string());
                 if to == from {
                     continue;
                 }
-                queue.push((from, to));
+                tx.rename(from, to);
+                moved = true;
             }
         }
 
-        if !queue.is_empty() {
-            if !fs::try_exists(&to_dir).await.unwrap() {
-                fs::create_dir_all(to_dir).await.unwrap();
-            }
-
-            for (from, to) in queue {
-                fs::rename(from, to).await.unwrap();
-            }
-
-            if fs::read_dir(&from_dir)
-                .await
-                .unwrap()
-                .next_entry()
-                .await
-                .unwrap()
-                .is_none()
-            {
-                fs::remove_dir(from_dir).await.unwrap();
-            }
+        if moved {
+            tx.remove_dir_if_empty(from_dir);
         }
+
+        Ok(())
     }
 
-    pub async fn path(cog: i64, subcog: i64) -> PathBuf {
-        let cog = if let Some(cog) = Category::find_by_id(cog, unsafe { CATEGORIES.get() }.unwrap())
-            .await
-            .unwrap()
+    pub async fn path(cog: i64, subcog: i64) -> Result<PathBuf, StoreError> {
+        let cog = if let Some(cog) =
+            Category::find_by_id(cog, unsafe { CATEGORIES.get() }.unwrap()).await?
         {
             cog
         } else {
-            return PathBuf::from(
+            return Ok(PathBuf::from(
                 unsafe { COORDS_CONFIG.get() }
                     .unwrap()
                     .default_attachment_path
                     .as_str(),
-            );
+            ));
         };
 
-        if let Some(subcog) = cog.subcategories.get(&subcog.to_string()) {
-            PathBuf::from(
-                subcog.attachment_path.as_ref().unwrap_or(
+        Ok(
+            if let Some(subcog) = cog.subcategories.get(&subcog.to_string()) {
+                PathBuf::from(
+                    subcog.attachment_path.as_ref().unwrap_or(
+                        cog.attachment_path.as_ref().unwrap_or(
+                            &unsafe { COORDS_CONFIG.get() }
+                                .unwrap()
+                                .default_attachment_path,
+                        ),
+                    ),
+                )
+            } else {
+                PathBuf::from(
                     cog.attachment_path.as_ref().unwrap_or(
                         &unsafe { COORDS_CONFIG.get() }
                             .unwrap()
                             .default_attachment_path,
                     ),
-                ),
-            )
-        } else {
-            PathBuf::from(
-                cog.attachment_path.as_ref().unwrap_or(
-                    &unsafe { COORDS_CONFIG.get() }
-                        .unwrap()
-                        .default_attachment_path,
-                ),
-            )
-        }
+                )
+            },
+        )
     }
 }
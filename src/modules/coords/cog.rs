@@ -1,15 +1,10 @@
 use std::fmt::Write;
 
-use mongodb::bson::doc;
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-    futures::StreamExt,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, Clearance, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, Filter, PerCommandConfig};
 
-use super::{category::Category, collection::CATEGORIES, config::COORDS_CONFIG};
+use super::{category::Category, collection::CATEGORIES, config::COORDS_CONFIG, world::take_world};
 
 pub struct CmdCog;
 
@@ -24,29 +19,45 @@ impl Command for CmdCog {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[category]", "[category].[subcategory]"]
+        &[
+            "(world=name)",
+            "[category] (world=name)",
+            "[category].[subcategory] (world=name)",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let (main, sub) = match args {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (main, sub) = match args.as_slice() {
             [name] if name.contains('.') => name
                 .split_once('.')
                 .map(|(left, right)| (left, Some(right)))
                 .unwrap(),
             [name] => (*name, None),
             _ => {
-                let _ = msg
+                let cogs = match unsafe { CATEGORIES.get() }
+                    .unwrap()
+                    .find(Filter::new().eq("world", world.as_str()))
+                    .await
+                {
+                    Ok(cogs) => cogs,
+                    Err(e) => {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
+                };
+
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "**Coords categories**\n\\- generic{}\n\nAttachment path: `{}`",
                             {
-                                unsafe { CATEGORIES.get() }
-                                    .unwrap()
-                                    .find(doc! {})
-                                    .await
-                                    .unwrap()
-                                    .map(|item| item.unwrap())
+                                cogs.into_iter()
                                     .map(|item| {
                                         format!(
                                             "\n\\- {}{}",
@@ -59,7 +70,6 @@ impl Command for CmdCog {
                                         )
                                     })
                                     .collect::<String>()
-                                    .await
                             },
                             unsafe { COORDS_CONFIG.get() }
                                 .unwrap()
@@ -75,19 +85,24 @@ impl Command for CmdCog {
 
         match (main.to_lowercase().as_str(), sublower.as_str()) {
             ("generic", "") => {
-                let _ = msg.reply(ctx, format!("**[Coords category] generic**\nSystem categories of special function.\n\n**Subcategories**\n\\- unspecified\n\\- private\n\nAttachment path: `{}` (inherited)", unsafe { COORDS_CONFIG.get() }.unwrap().default_attachment_path)).await;
+                let _ = ctx.reply(msg, format!("**[Coords category] generic**\nSystem categories of special function.\n\n**Subcategories**\n\\- unspecified\n\\- private\n\nAttachment path: `{}` (inherited)", unsafe { COORDS_CONFIG.get() }.unwrap().default_attachment_path)).await;
                 return true;
             }
             ("generic", "private") => {
-                let _ = msg.reply(ctx, "**[Coords category] generic.private**\nOnly the author can see entries in this category.").await;
+                let _ = ctx.reply(msg, "**[Coords category] generic.private**\nOnly the author can see entries in this category.").await;
                 return true;
             }
             (main, "unspecified") => {
-                let cog = if let Some(cog) = Category::get(main).await {
-                    cog
-                } else {
-                    let _ = msg.reply(ctx, "Category not found.").await;
-                    return true;
+                let cog = match Category::get(&world, main).await {
+                    Ok(Some(cog)) => cog,
+                    Ok(None) => {
+                        let _ = ctx.reply(msg, "Category not found.").await;
+                        return true;
+                    }
+                    Err(e) => {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
                 };
                 let path = cog.attachment_path.as_ref().unwrap_or(
                     &unsafe { COORDS_CONFIG.get() }
@@ -95,17 +110,22 @@ impl Command for CmdCog {
                         .default_attachment_path,
                 );
 
-                let _ = msg.reply(ctx, format!("**[Coords category] {main}.unspecified**\nThe default subcategory for {main}.\n\nAttachment path: `{path}` (inherited)")).await;
+                let _ = ctx.reply(msg, format!("**[Coords category] {main}.unspecified**\nThe default subcategory for {main}.\n\nAttachment path: `{path}` (inherited)")).await;
                 return true;
             }
             _ => {}
         }
 
-        let cog = if let Some(cog) = Category::get(main).await {
-            cog
-        } else {
-            let _ = msg.reply(ctx, "Category not found.").await;
-            return true;
+        let cog = match Category::get(&world, main).await {
+            Ok(Some(cog)) => cog,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "Category not found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
         };
 
         if let Some(sub) = sub {
@@ -119,9 +139,9 @@ impl Command for CmdCog {
                         .await
                         .unwrap_or(true)
                 {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "You don't have permission to view **{}.{}**{}.",
                                 cog.display_name,
@@ -145,9 +165,9 @@ impl Command for CmdCog {
                         .default_attachment_path,
                 );
 
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "**[Coords category] {}.{}**{}\n{}\n\nAttachment path: `{path}`{}",
                             cog.display_name,
@@ -178,9 +198,9 @@ impl Command for CmdCog {
             .await
             .unwrap_or(true)
         {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "You don't have permission to view **{}**{}.",
                         cog.display_name,
@@ -201,10 +221,7 @@ impl Command for CmdCog {
                 .default_attachment_path,
         );
 
-        let _ = msg
-            .reply(
-                ctx,
-                format!(
+        let _ = ctx.reply(msg, format!(
                     "**[Coords category] {}**{}\n{}\n\n**Subcategories**:\n\\- unspecified{}\n\nPath: `{path}`{}",
                     cog.display_name,
                     if cog.name == cog.display_name {
@@ -1,13 +1,12 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, Clearance, CollectionItem, PerCommandConfig};
+use crate::{sys::Command, Clearance, CollectionItem, Context, PerCommandConfig};
 
 use super::{
     category::{Category, Subcategory},
     collection::CATEGORIES,
+    world::take_world,
 };
 
 pub struct CmdCogAdd;
@@ -24,24 +23,29 @@ impl Command for CmdCogAdd {
 
     fn usage(&self) -> &[&str] {
         &[
-            "[category] (description) (attachment path)",
-            "[category].[subcategory] (description) (attachment path)",
+            "[category] (description) (attachment path) (world=name)",
+            "[category].[subcategory] (description) (attachment path) (world=name)",
         ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        match args {
-            [name] if !name.contains('.') => addmain(name, "", None, ctx, msg).await,
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        match args.as_slice() {
+            [name] if !name.contains('.') => addmain(world, name, "", None, ctx, msg).await,
             [name, description] if !name.contains('.') => {
-                addmain(name, description, None, ctx, msg).await
+                addmain(world, name, description, None, ctx, msg).await
             }
             [name, description, path] if !name.contains('.') => {
-                addmain(name, description, Some(path.to_string()), ctx, msg).await
+                addmain(world, name, description, Some(path.to_string()), ctx, msg).await
             }
-            [name] => addsub(name, "", None, ctx, msg).await,
-            [name, description] => addsub(name, description, None, ctx, msg).await,
+            [name] => addsub(&world, name, "", None, ctx, msg).await,
+            [name, description] => addsub(&world, name, description, None, ctx, msg).await,
             [name, description, path] => {
-                addsub(name, description, Some(path.to_string()), ctx, msg).await
+                addsub(&world, name, description, Some(path.to_string()), ctx, msg).await
             }
             _ => return false,
         }
@@ -56,29 +60,45 @@ impl Command for CmdCogAdd {
     }
 }
 
-async fn addmain(name: &str, desc: &str, path: Option<String>, ctx: &Context, msg: &Message) {
+async fn addmain(
+    world: String,
+    name: &str,
+    desc: &str,
+    path: Option<String>,
+    ctx: &Context,
+    msg: &Message,
+) {
     if name.is_empty() {
-        let _ = msg.reply(ctx, "Category name cannot be empty.").await;
+        let _ = ctx.reply(msg, "Category name cannot be empty.").await;
         return;
     }
 
-    if name == "generic" || Category::get(name).await.is_some() {
-        let _ = msg
+    let taken = match Category::get(&world, name).await {
+        Ok(cog) => cog.is_some(),
+        Err(e) => {
+            let _ = ctx.reply_store_error(msg, &e).await;
+            return;
+        }
+    };
+
+    if name == "generic" || taken {
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 "Category not created because a category with that name already exist.",
             )
             .await;
         return;
     }
 
-    let cog = Category::new(name.to_string(), desc.to_string(), path).await;
+    let cog = Category::new(world, name.to_string(), desc.to_string(), path).await;
 
     match cog {
-        Ok(cog) => {
-            let _ = msg
+        Ok(Ok(cog)) => {
+            info!(category = cog.name, user = %msg.author.id, "category created");
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "Category **{}**{} created!",
                         cog.display_name,
@@ -91,46 +111,62 @@ async fn addmain(name: &str, desc: &str, path: Option<String>, ctx: &Context, ms
                 )
                 .await;
         }
-        Err(reason) => {
-            let _ = msg
+        Ok(Err(reason)) => {
+            info!(name, error = %reason, "category not created");
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!("Could not create new category because {reason}."),
                 )
                 .await;
         }
+        Err(e) => {
+            let _ = ctx.reply_store_error(msg, &e).await;
+        }
     }
 }
 
-async fn addsub(name: &str, desc: &str, path: Option<String>, ctx: &Context, msg: &Message) {
+async fn addsub(
+    world: &str,
+    name: &str,
+    desc: &str,
+    path: Option<String>,
+    ctx: &Context,
+    msg: &Message,
+) {
     let (main, sub) = name.split_once('.').unwrap();
 
     if main.to_lowercase().as_str() == "generic" {
-        let _ = msg.reply(ctx, "You cannot edit a system category.").await;
+        let _ = ctx.reply(msg, "You cannot edit a system category.").await;
         return;
     }
 
     if sub.contains('.') {
-        let _ = msg
-            .reply(ctx, "The maximum depth for nested categories is 2.")
+        let _ = ctx
+            .reply(msg, "The maximum depth for nested categories is 2.")
             .await;
         return;
     }
 
-    let mut cog = if let Some(cog) = Category::get(main).await {
-        cog
-    } else {
-        let _ = msg.reply(ctx, "Parent category not found.").await;
-        return;
+    let mut cog = match Category::get(world, main).await {
+        Ok(Some(cog)) => cog,
+        Ok(None) => {
+            let _ = ctx.reply(msg, "Parent category not found.").await;
+            return;
+        }
+        Err(e) => {
+            let _ = ctx.reply_store_error(msg, &e).await;
+            return;
+        }
     };
 
     if !Clearance::is_allowed(&cog.allowed, ctx, msg)
         .await
         .unwrap_or(true)
     {
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
                     "You don't have permission to edit **{}**{}.",
                     cog.display_name,
@@ -148,9 +184,9 @@ async fn addsub(name: &str, desc: &str, path: Option<String>, ctx: &Context, msg
     let name = sub.replace(' ', "-").to_lowercase();
 
     if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 "Could not create new subcategory because name contains illegal characters.",
             )
             .await;
@@ -158,14 +194,14 @@ async fn addsub(name: &str, desc: &str, path: Option<String>, ctx: &Context, msg
     }
 
     if name.is_empty() {
-        let _ = msg.reply(ctx, "Category name cannot be empty.").await;
+        let _ = ctx.reply(msg, "Category name cannot be empty.").await;
         return;
     }
 
     if name == "unspecified" || cog.contains(&name) {
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 "Category not created because a category with that name already exist.",
             )
             .await;
@@ -183,13 +219,14 @@ async fn addsub(name: &str, desc: &str, path: Option<String>, ctx: &Context, msg
     cog.subcategories
         .insert(cog.subcogcounter.to_string(), subcog);
     cog.subcogcounter += 1;
-    cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-        .await
-        .unwrap();
+    if let Err(e) = cog.save_replace(unsafe { CATEGORIES.get() }.unwrap()).await {
+        let _ = ctx.reply_store_error(msg, &e).await;
+        return;
+    }
 
-    let _ = msg
+    let _ = ctx
         .reply(
-            ctx,
+            msg,
             format!(
                 "Subcategory **{}.{}**{} created!",
                 cog.display_name,
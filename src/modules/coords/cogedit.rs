@@ -1,11 +1,9 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, Clearance, CollectionItem, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, PerCommandConfig, StoreError, Transaction};
 
-use super::{category::Category, collection::CATEGORIES, config::COORDS_CONFIG};
+use super::{category::Category, collection::CATEGORIES, config::COORDS_CONFIG, world::take_world};
 
 pub struct CmdCogEdit;
 
@@ -20,11 +18,16 @@ impl Command for CmdCogEdit {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[category] [desc=value|name=value|path=value...]"]
+        &["[category] [desc=value|name=value|path=value...] (world=name)"]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let (main, sub) = match args {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (main, sub) = match args.as_slice() {
             [name, ..] if name.contains('.') => name
                 .split_once('.')
                 .map(|(left, right)| (left, Some(right)))
@@ -36,7 +39,7 @@ impl Command for CmdCogEdit {
         if main.to_lowercase().as_str() == "generic"
             || sub.is_some_and(|sub| sub.to_lowercase().as_str() == "unspecified")
         {
-            let _ = msg.reply(ctx, "You cannot edit a system category.").await;
+            let _ = ctx.reply(msg, "You cannot edit a system category.").await;
             return true;
         }
 
@@ -51,14 +54,19 @@ impl Command for CmdCogEdit {
                     "desc" => new_desc = Some(right),
                     "name" => {
                         if right.is_empty() {
-                            let _ = msg.reply(ctx, "Category name cannot be empty.").await;
+                            let _ = ctx.reply(msg, "Category name cannot be empty.").await;
                             return true;
                         }
 
                         let name = right.replace(' ', "-").to_lowercase();
 
                         if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
-                            let _ = msg.reply(ctx, "Category details not updated because name contains illegal characters.").await;
+                            let _ = ctx.reply(msg, "Category details not updated because name contains illegal characters.").await;
+                            return true;
+                        }
+
+                        if sub.is_none() && name.parse::<i64>().is_ok() {
+                            let _ = ctx.reply(msg, "Category details not updated because name cannot be an integer.").await;
                             return true;
                         }
 
@@ -74,20 +82,26 @@ impl Command for CmdCogEdit {
         }
 
         if new_path.is_none() && new_name.is_none() && new_desc.is_none() {
-            let _ = msg
-                .reply(ctx, "Update failed because no fields are changed.")
+            let _ = ctx
+                .reply(msg, "Update failed because no fields are changed.")
                 .await;
             return true;
         }
 
-        let mut cog = if let Some(cog) = Category::get(main).await {
-            cog
-        } else {
-            let _ = msg.reply(ctx, "Category not found.").await;
-            return true;
+        let mut cog = match Category::get(&world, main).await {
+            Ok(Some(cog)) => cog,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "Category not found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
         };
 
         let cog2 = cog.clone();
+        let mut tx = Transaction::new();
 
         if let Some(sub) = sub {
             let name = sub.replace(' ', "-").to_lowercase();
@@ -103,9 +117,9 @@ impl Command for CmdCogEdit {
                         .await
                         .unwrap_or(true)
                 {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "You don't have permission to edit **{}.{}**{}.",
                                 cog2.display_name,
@@ -124,9 +138,9 @@ impl Command for CmdCogEdit {
                 }
 
                 if is_duplicate {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             "Category not updated because a category with that name already exist.",
                         )
                         .await;
@@ -149,7 +163,7 @@ impl Command for CmdCogEdit {
                         Some(path.to_string())
                     };
 
-                    Category::move_all(
+                    if let Err(e) = Category::move_all(
                         &cog2,
                         Some(subcog.id),
                         subcog.attachment_path.as_ref().unwrap_or(
@@ -166,17 +180,31 @@ impl Command for CmdCogEdit {
                                     .default_attachment_path,
                             ),
                         ),
+                        &mut tx,
                     )
-                    .await;
+                    .await
+                    {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
 
                     subcog.attachment_path = to;
                 }
 
-                cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                    .await
-                    .unwrap();
+                // the category is saved together with the attachment moves
+                if let Err(e) = tx.replace(unsafe { CATEGORIES.get() }.unwrap(), cog.id, &cog) {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+                if let Err(e) = tx.commit().await {
+                    let _ = ctx
+                        .reply(msg, format!("Category not updated because {e}."))
+                        .await;
+                    return true;
+                }
 
-                let _ = msg.reply(ctx, "Category details updated.").await;
+                info!(category = cog.name, user = %msg.author.id, "category updated");
+                let _ = ctx.reply(msg, "Category details updated.").await;
 
                 return true;
             }
@@ -186,9 +214,9 @@ impl Command for CmdCogEdit {
             .await
             .unwrap_or(true)
         {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "You don't have permission to edit **{}**{}.",
                         cog2.display_name,
@@ -203,16 +231,21 @@ impl Command for CmdCogEdit {
             return true;
         }
 
-        let is_duplicate = if let Some(name) = &new_name {
-            Category::get(name).await.is_some()
-        } else {
-            false
+        let is_duplicate = match &new_name {
+            Some(name) => match Category::get(&world, name).await {
+                Ok(found) => found.is_some(),
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            },
+            None => false,
         };
 
         if is_duplicate {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     "Category not updated because a category with that name already exist.",
                 )
                 .await;
@@ -235,7 +268,7 @@ impl Command for CmdCogEdit {
                 Some(path.to_string())
             };
 
-            Category::move_all(
+            if let Err(e) = Category::move_all(
                 &cog2,
                 None,
                 cog.attachment_path.as_ref().unwrap_or(
@@ -248,17 +281,42 @@ impl Command for CmdCogEdit {
                         .unwrap()
                         .default_attachment_path,
                 ),
+                &mut tx,
             )
-            .await;
+            .await
+            {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
 
             cog.attachment_path = to;
         }
 
-        cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-            .await
-            .unwrap();
+        if let Err(e) = tx.replace(unsafe { CATEGORIES.get() }.unwrap(), cog.id, &cog) {
+            let _ = ctx.reply_store_error(msg, &e).await;
+            return true;
+        }
+        match tx.commit().await {
+            Ok(()) => {}
+            Err(StoreError::Duplicate(_)) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Category not updated because a category with that name already exist.",
+                    )
+                    .await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx
+                    .reply(msg, format!("Category not updated because {e}."))
+                    .await;
+                return true;
+            }
+        }
 
-        let _ = msg.reply(ctx, "Category details updated.").await;
+        info!(category = cog.name, user = %msg.author.id, "category updated");
+        let _ = ctx.reply(msg, "Category details updated.").await;
 
         true
     }
@@ -1,13 +1,11 @@
 use std::fmt::Write;
 
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, Clearance, CollectionItem, PerCommandConfig};
+use crate::{sys::Command, Clearance, CollectionItem, Context, PerCommandConfig};
 
-use super::{category::Category, collection::CATEGORIES};
+use super::{category::Category, collection::CATEGORIES, world::take_world};
 
 pub struct CmdCogPerms;
 
@@ -22,11 +20,19 @@ impl Command for CmdCogPerms {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[category] (rules...)", "[category] clear"]
+        &[
+            "[category] (rules...) (world=name)",
+            "[category] clear (world=name)",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let (main, sub) = match args {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (main, sub) = match args.as_slice() {
             [name, ..] if name.contains('.') => name
                 .split_once('.')
                 .map(|(left, right)| (left, Some(right)))
@@ -38,15 +44,20 @@ impl Command for CmdCogPerms {
         if main.to_lowercase().as_str() == "generic"
             || sub.is_some_and(|sub| sub.to_lowercase().as_str() == "unspecified")
         {
-            let _ = msg.reply(ctx, "You cannot edit a system category.").await;
+            let _ = ctx.reply(msg, "You cannot edit a system category.").await;
             return true;
         }
 
-        let mut cog = if let Some(cog) = Category::get(main).await {
-            cog
-        } else {
-            let _ = msg.reply(ctx, "Category not found.").await;
-            return true;
+        let mut cog = match Category::get(&world, main).await {
+            Ok(Some(cog)) => cog,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "Category not found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
         };
 
         let cog_display = cog.display_name.clone();
@@ -64,9 +75,9 @@ impl Command for CmdCogPerms {
                         .await
                         .unwrap_or(true)
                 {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "You don't have permission to edit **{}.{}**{}.",
                                 cog_display,
@@ -95,17 +106,16 @@ impl Command for CmdCogPerms {
                 } else if args[1..].as_ref() == ["clear"] {
                     subcog.allowed.clear();
 
-                    cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                        .await
-                        .unwrap();
+                    if let Err(e) = cog.save_replace(unsafe { CATEGORIES.get() }.unwrap()).await {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
 
-                    let _ = msg.reply(ctx, "Category permissions cleared.").await;
+                    info!(category = cog.name, user = %msg.author.id, "category permissions cleared");
+                    let _ = ctx.reply(msg, "Category permissions cleared.").await;
                 } else {
                     if !Clearance::validate(&args[1..], None) {
-                        let _ = msg
-                            .reply(
-                                ctx,
-                                "Failed to update category permission because it contains invalid rules.".to_string(),
+                        let _ = ctx.reply(msg, "Failed to update category permission because it contains invalid rules.".to_string(),
                             )
                             .await;
                         return true;
@@ -116,11 +126,13 @@ impl Command for CmdCogPerms {
                         return true;
                     }
 
-                    cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                        .await
-                        .unwrap();
+                    if let Err(e) = cog.save_replace(unsafe { CATEGORIES.get() }.unwrap()).await {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
 
-                    let _ = msg.reply(ctx, "Category permissions updated.").await;
+                    info!(category = cog.name, user = %msg.author.id, "category permissions updated");
+                    let _ = ctx.reply(msg, "Category permissions updated.").await;
                 }
 
                 return true;
@@ -131,9 +143,9 @@ impl Command for CmdCogPerms {
             .await
             .unwrap_or(true)
         {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "You don't have permission to edit **{}**{}.",
                         cog_display,
@@ -154,16 +166,18 @@ impl Command for CmdCogPerms {
         } else if args[1..].as_ref() == ["clear"] {
             cog.allowed.clear();
 
-            cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                .await
-                .unwrap();
+            if let Err(e) = cog.save_replace(unsafe { CATEGORIES.get() }.unwrap()).await {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
 
-            let _ = msg.reply(ctx, "Category permissions cleared.").await;
+            info!(category = cog.name, user = %msg.author.id, "category permissions cleared");
+            let _ = ctx.reply(msg, "Category permissions cleared.").await;
         } else {
             if !Clearance::validate(&args[1..], None) {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         "Failed to update category permission because it contains invalid rules."
                             .to_string(),
                     )
@@ -176,11 +190,13 @@ impl Command for CmdCogPerms {
                 return true;
             }
 
-            cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                .await
-                .unwrap();
+            if let Err(e) = cog.save_replace(unsafe { CATEGORIES.get() }.unwrap()).await {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
 
-            let _ = msg.reply(ctx, "Category details updated.").await;
+            info!(category = cog.name, user = %msg.author.id, "category permissions updated");
+            let _ = ctx.reply(msg, "Category details updated.").await;
         }
 
         true
@@ -202,9 +218,9 @@ async fn display_perms(
     msg: &Message,
 ) {
     if allowed.is_empty() {
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
                     "**[Category permission] {}{}**\nThis module has no permission rules.",
                     display,
@@ -215,9 +231,9 @@ async fn display_perms(
         return;
     }
 
-    let _ = msg
+    let _ = ctx
         .reply(
-            ctx,
+            msg,
             format!(
                 "**[Category permission] {}{}**{}",
                 display,
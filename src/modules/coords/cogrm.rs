@@ -1,14 +1,13 @@
-use mongodb::bson::doc;
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, Clearance, CollectionItem, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, Filter, PerCommandConfig, Transaction};
 
 use super::{
     category::Category,
     collection::{CATEGORIES, COORDS},
+    deleted::{TrashEntry, Trashed},
+    world::take_world,
 };
 
 pub struct CmdCogRm;
@@ -24,11 +23,16 @@ impl Command for CmdCogRm {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[category]"]
+        &["[category] (world=name)"]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let (main, sub) = match args {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (main, sub) = match args.as_slice() {
             [name, ..] if name.contains('.') => name
                 .split_once('.')
                 .map(|(left, right)| (left, Some(right)))
@@ -40,15 +44,20 @@ impl Command for CmdCogRm {
         if main.to_lowercase().as_str() == "generic"
             || sub.is_some_and(|sub| sub.to_lowercase().as_str() == "unspecified")
         {
-            let _ = msg.reply(ctx, "You cannot remove a system category.").await;
+            let _ = ctx.reply(msg, "You cannot remove a system category.").await;
             return true;
         }
 
-        let mut cog = if let Some(cog) = Category::get(main).await {
-            cog
-        } else {
-            let _ = msg.reply(ctx, "Category not found.").await;
-            return true;
+        let mut cog = match Category::get(&world, main).await {
+            Ok(Some(cog)) => cog,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "Category not found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
         };
 
         let cog_display = cog.display_name.clone();
@@ -69,9 +78,9 @@ impl Command for CmdCogRm {
                         .await
                         .unwrap_or(true)
                 {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "You don't have permission to remove **{}.{}**{}.",
                                 cog_display,
@@ -87,24 +96,57 @@ impl Command for CmdCogRm {
                     return true;
                 }
 
-                if unsafe { COORDS.get() }
+                let nonempty = match unsafe { COORDS.get() }
                     .unwrap()
-                    .find_one(doc! { "cog": cogid, "subcog": subcog.id})
+                    .find_one(Filter::new().eq("cog", cogid).eq("subcog", subcog.id))
                     .await
-                    .unwrap()
-                    .is_some()
                 {
-                    let _ = msg
-                        .reply(ctx, "You cannot delete a nonempty category.")
+                    Ok(found) => found.is_some(),
+                    Err(e) => {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
+                };
+
+                if nonempty {
+                    let _ = ctx
+                        .reply(msg, "You cannot delete a nonempty category.")
                         .await;
                     return true;
                 }
 
-                cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-                    .await
-                    .unwrap();
+                let key = subcog.id.to_string();
+                let subcog = cog.subcategories.remove(&key).unwrap();
+                let mut tx = Transaction::new();
+                if let Err(e) = TrashEntry::trash(
+                    Trashed::Subcategory {
+                        cog: cogid,
+                        item: subcog,
+                    },
+                    msg,
+                    &mut tx,
+                )
+                .await
+                {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+                if let Err(e) = tx.replace(unsafe { CATEGORIES.get() }.unwrap(), cog.id, &cog) {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
 
-                let _ = msg.reply(ctx, "Category deleted.").await;
+                if let Err(e) = tx.commit().await {
+                    let _ = ctx
+                        .reply(msg, format!("Category not deleted because {e}."))
+                        .await;
+                    return true;
+                }
+
+                info!(category = cog.name, subcategory = name, user = %msg.author.id, "category deleted");
+                let _ = ctx
+                    .reply(msg, "Category deleted. See `trash` to restore.")
+                    .await;
 
                 return true;
             }
@@ -114,9 +156,9 @@ impl Command for CmdCogRm {
             .await
             .unwrap_or(true)
         {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "You don't have permission to remove **{}**{}.",
                         cog_display,
@@ -131,30 +173,44 @@ impl Command for CmdCogRm {
             return true;
         }
 
-        if unsafe { COORDS.get() }
+        let nonempty = match unsafe { COORDS.get() }
             .unwrap()
-            .find_one(doc! { "cog": cog.id})
+            .find_one(Filter::new().eq("cog", cog.id))
             .await
-            .unwrap()
-            .is_some()
         {
-            let _ = msg
-                .reply(ctx, "You cannot delete a nonempty category.")
+            Ok(found) => found.is_some(),
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        if nonempty {
+            let _ = ctx
+                .reply(msg, "You cannot delete a nonempty category.")
                 .await;
             return true;
         }
 
-        cog.save_replace(unsafe { CATEGORIES.get() }.unwrap())
-            .await
-            .unwrap();
+        let id = cog.id;
+        let mut tx = Transaction::new();
+        if let Err(e) = TrashEntry::trash(Trashed::Category { item: cog }, msg, &mut tx).await {
+            let _ = ctx.reply_store_error(msg, &e).await;
+            return true;
+        }
+        tx.delete(unsafe { CATEGORIES.get() }.unwrap(), id);
 
-        let _ = msg.reply(ctx, "Category deleted.").await;
+        if let Err(e) = tx.commit().await {
+            let _ = ctx
+                .reply(msg, format!("Category not deleted because {e}."))
+                .await;
+            return true;
+        }
 
-        unsafe { CATEGORIES.get() }
-            .unwrap()
-            .delete_one(doc! { "_id": cog.id })
-            .await
-            .unwrap();
+        info!(category = cog_name, user = %msg.author.id, "category deleted");
+        let _ = ctx
+            .reply(msg, "Category deleted. See `trash` to restore.")
+            .await;
 
         true
     }
@@ -1,8 +1,35 @@
 use std::sync::OnceLock;
 
-use mongodb::Collection;
+use crate::{Collection, Index};
 
-use super::{category::Category, coord::Coord};
+use super::{
+    category::Category,
+    coord::{Coord, WORLD_BORDER},
+    deleted::TrashEntry,
+    revision::Revision,
+};
 
 pub static mut CATEGORIES: OnceLock<Collection<Category>> = OnceLock::new();
 pub static mut COORDS: OnceLock<Collection<Coord>> = OnceLock::new();
+pub static mut TRASH: OnceLock<Collection<TrashEntry>> = OnceLock::new();
+pub static mut REVISIONS: OnceLock<Collection<Revision>> = OnceLock::new();
+
+pub const CATEGORIES_NAME: &str = "coords-cogs";
+pub const COORDS_NAME: &str = "coords-coords";
+pub const TRASH_NAME: &str = "coords-trash";
+pub const REVISIONS_NAME: &str = "coords-revisions";
+
+// names are unique in each world
+pub const CATEGORY_INDEXES: &[Index] = &[Index::unique("world-name", &["world", "name"])];
+pub const COORD_INDEXES: &[Index] = &[
+    Index::unique("world-name", &["world", "name"]),
+    Index::new("cog", &["cog", "subcog"]),
+    Index::new("tags", &["tags"]),
+    Index::new("dim", &["dim"]),
+    // leaves room for near and box queries around entries at the border
+    Index::geo("pos", &["pos", "dim"], WORLD_BORDER * 2),
+];
+// replaced by world-name, unique over all worlds
+pub const DROPPED_INDEXES: &[&str] = &["name"];
+pub const TRASH_INDEXES: &[Index] = &[Index::new("kind", &["kind"])];
+pub const REVISION_INDEXES: &[Index] = &[Index::unique("rev", &["coord", "rev"])];
@@ -1,10 +1,14 @@
-use std::sync::OnceLock;
+use std::{collections::BTreeMap, sync::OnceLock};
 
 use serde::{Deserialize, Serialize};
 use serde_default::DefaultFromSerde;
 use serde_inline_default::serde_inline_default;
 
-use crate::Config;
+use tracing::warn;
+
+use crate::{Config, LiveConfig};
+
+use super::world::world_name;
 
 pub static mut COORDS_CONFIG: OnceLock<CoordsConfig> = OnceLock::new();
 
@@ -14,17 +18,68 @@ pub struct CoordsConfig {
     #[serde_inline_default(100)]
     #[serde(rename = "prevent-add-radius")]
     pub prevent_add_radius: u64,
+    // also prevent adding entries near the converted position in the linked dimension
+    #[serde_inline_default(false)]
+    #[serde(rename = "prevent-add-linked")]
+    pub prevent_add_linked: bool,
     #[serde_inline_default(5)]
     #[serde(rename = "page-size")]
     pub page_size: u32,
     #[serde_inline_default("/path/to/dir".to_string())]
     #[serde(rename = "default-attachment-path")]
     pub default_attachment_path: String,
+    // removed entries are kept this long before being purged, 0 keeps them forever
+    #[serde_inline_default(30)]
+    #[serde(rename = "trash-retention-days")]
+    pub trash_retention_days: u32,
+    // where `coords backup` writes archives, relative to the config directory
+    #[serde_inline_default("backups".to_string())]
+    #[serde(rename = "backup-path")]
+    pub backup_path: String,
+    // the world used where neither world= nor a channel or server default is given
+    #[serde_inline_default("default".to_string())]
+    #[serde(rename = "default-world")]
+    pub default_world: String,
+    // channel id to world, set with `coords worlds [world]`
+    #[serde_inline_default(BTreeMap::new())]
+    #[serde(rename = "channel-worlds")]
+    pub channel_worlds: BTreeMap<String, String>,
+    // server id to world, set with `coords worlds guild [world]`, used in channels without their own
+    #[serde_inline_default(BTreeMap::new())]
+    #[serde(rename = "guild-worlds")]
+    pub guild_worlds: BTreeMap<String, String>,
 }
 
 impl Config for CoordsConfig {
     const NAME: &'static str = "coords";
     const NOTE: &'static str = "Main config file for the coords module";
+    const HISTORY: bool = true;
+}
+
+impl LiveConfig for CoordsConfig {
+    fn current() -> &'static Self {
+        unsafe { COORDS_CONFIG.get() }.unwrap()
+    }
+
+    fn replace(new: Self) {
+        unsafe { COORDS_CONFIG = OnceLock::new() };
+        let _ = unsafe { COORDS_CONFIG.set(new) };
+    }
+
+    // worlds that world= could never select
+    fn verify(&self) -> Result<(), String> {
+        for world in [&self.default_world]
+            .into_iter()
+            .chain(self.channel_worlds.values())
+            .chain(self.guild_worlds.values())
+        {
+            if world_name(world).as_ref() != Ok(world) {
+                return Err(format!("World name {world} is not valid."));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl CoordsConfig {
@@ -33,11 +88,18 @@ impl CoordsConfig {
         Self::setup();
     }
 
-    // pub fn write_to_config() {
-    //     unsafe { COORDS_CONFIG.get() }.unwrap().smart_save();
-    // }
+    pub fn write_to_config() {
+        unsafe { COORDS_CONFIG.get() }.unwrap().smart_save();
+    }
 
     pub fn setup() {
-        let _ = unsafe { COORDS_CONFIG.set(CoordsConfig::load()) };
+        let config = CoordsConfig::load();
+
+        // an edited file is still loaded, as entries may already be in those worlds
+        if let Err(e) = config.verify() {
+            warn!(config = Self::NAME, error = e, "invalid coords config");
+        }
+
+        let _ = unsafe { COORDS_CONFIG.set(config) };
     }
 }
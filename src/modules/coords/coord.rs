@@ -1,20 +1,20 @@
 use std::{collections::HashMap, fmt::Display};
 
-use mongodb::bson::{doc, Document};
 use serde::{Deserialize, Serialize};
-use serenity::{
-    all::{Context, Message},
-    futures::StreamExt,
-};
+use serenity::all::Message;
 
 use crate::{
     modules::coords::{
         category::Category,
         collection::{CATEGORIES, COORDS},
+        world::default_world,
     },
-    Clearance, CollectionItem, Counter, Mongo,
+    Clearance, CollectionItem, Condition, Context, Counter, Filter, StoreError,
 };
 
+// the farthest a player can go from 0,0 on either axis
+pub const WORLD_BORDER: i64 = 30_000_000;
+
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Dimension {
     #[serde(rename = "ow")]
@@ -34,6 +34,33 @@ impl Dimension {
             _ => None,
         }
     }
+
+    // the overworld and the nether are linked by portals, 8 blocks to 1
+    pub fn linked(self) -> Option<Self> {
+        match self {
+            Self::Overworld => Some(Self::Nether),
+            Self::Nether => Some(Self::Overworld),
+            Self::End => None,
+        }
+    }
+
+    // where a position in this dimension ends up in the linked one
+    pub fn to_linked(self, x: i64, z: i64) -> Option<(i64, i64)> {
+        match self {
+            Self::Overworld => Some((x.div_euclid(8), z.div_euclid(8))),
+            Self::Nether => Some((x.saturating_mul(8), z.saturating_mul(8))),
+            Self::End => None,
+        }
+    }
+
+    // rounded up, so nothing in range is missed
+    pub fn radius_to_linked(self, r: u64) -> Option<u64> {
+        match self {
+            Self::Overworld => Some(r.div_ceil(8)),
+            Self::Nether => Some(r.saturating_mul(8)),
+            Self::End => None,
+        }
+    }
 }
 
 impl Display for Dimension {
@@ -50,17 +77,25 @@ impl Display for Dimension {
 pub struct Coord {
     #[serde(rename = "_id")]
     pub id: i64,
+    // entries from before worlds existed are in the default one
+    #[serde(default = "default_world")]
+    pub world: String,
     pub cog: i64,
     pub subcog: i64,
     pub name: String,
     pub display_name: String,
     pub description: String,
     pub x: i64,
+    // entries from before heights were recorded have none
+    #[serde(default)]
+    pub y: Option<i64>,
     pub z: i64,
+    // [x, z] for the geo index, exports from before it existed do not have it
+    #[serde(default)]
+    pub pos: [i64; 2],
     pub author_id: u64,
     pub dim: Dimension,
     pub added: i64,
-    #[serde(default)]
     pub tags: Vec<String>,
 }
 
@@ -71,51 +106,71 @@ impl CollectionItem<i64> for Coord {
 }
 
 impl Coord {
-    pub async fn find_by_name(name: &str) -> Option<Self> {
+    pub async fn find_by_name(world: &str, name: &str) -> Result<Option<Self>, StoreError> {
         unsafe { COORDS.get() }
             .unwrap()
-            .find_one(doc! { "name": &name })
+            .find_one(Filter::new().eq("world", world).eq("name", name))
             .await
-            .unwrap()
     }
 
+    // an entry in the world by id, or by name otherwise
+    pub async fn find_by_name_or_id(world: &str, entry: &str) -> Result<Option<Self>, StoreError> {
+        if let Ok(id) = entry.parse::<i64>() {
+            return Ok(Self::find_by_id(id, unsafe { COORDS.get() }.unwrap())
+                .await?
+                .filter(|found| found.world == world));
+        }
+
+        Self::find_by_name(world, &entry.replace(' ', "-").to_lowercase()).await
+    }
+
+    // the inner error is meant for the user
+
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
+        world: String,
         display_name: String,
         description: String,
         author_id: u64,
         cog: i64,
         subcog: i64,
         x: i64,
+        y: Option<i64>,
         z: i64,
         dim: Dimension,
         tags: Vec<String>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Result<Self, &'static str>, StoreError> {
         let name = display_name.replace(' ', "-").to_lowercase();
 
         if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
-            return Err("name contains illegal characters");
+            return Ok(Err("name contains illegal characters"));
         }
 
         if name.parse::<i64>().is_ok() {
-            return Err("name cannot be an integer");
+            return Ok(Err("name cannot be an integer"));
+        }
+
+        if !Self::within_border(x, z) {
+            return Ok(Err("coordinates are outside the world border"));
         }
 
         let coords = unsafe { COORDS.get() }.unwrap();
 
         if coords
-            .find_one(doc! {"name": &name})
-            .await
-            .unwrap()
+            .find_one(
+                Filter::new()
+                    .eq("world", world.as_str())
+                    .eq("name", name.as_str()),
+            )
+            .await?
             .is_some()
         {
-            return Err("a coord entry with that name already exists");
+            return Ok(Err("a coord entry with that name already exists"));
         }
 
         let new = Self {
-            id: Counter::bump_get("coords-coords", Mongo::database())
-                .await
-                .unwrap(),
+            id: Counter::bump_get("coords-coords").await?,
+            world,
             cog,
             subcog,
             name,
@@ -124,16 +179,19 @@ impl Coord {
             description,
             z,
             x,
+            y,
+            pos: [x, z],
             dim,
             added: chrono::Utc::now().timestamp(),
             tags,
         };
 
-        new.save_create(unsafe { COORDS.get() }.unwrap())
-            .await
-            .unwrap();
-
-        Ok(new)
+        // the unique index catches names taken since the check above
+        match new.save_create(unsafe { COORDS.get() }.unwrap()).await {
+            Ok(()) => Ok(Ok(new)),
+            Err(StoreError::Duplicate(_)) => Ok(Err("a coord entry with that name already exists")),
+            Err(e) => Err(e),
+        }
     }
 
     pub async fn is_allowed(
@@ -141,17 +199,17 @@ impl Coord {
         ctx: &Context,
         msg: &Message,
         lookup: &mut HashMap<(i64, i64), (bool, String, String)>,
-    ) -> bool {
+    ) -> Result<bool, StoreError> {
         if let Some((b, ..)) = lookup.get(&(self.cog, self.subcog)) {
-            return *b;
+            return Ok(*b);
         }
 
         async fn is_allowed_core(
             entry: &Coord,
             ctx: &Context,
             msg: &Message,
-        ) -> (bool, String, String) {
-            match (entry.cog, entry.subcog) {
+        ) -> Result<(bool, String, String), StoreError> {
+            Ok(match (entry.cog, entry.subcog) {
                 (0, 0) => (
                     true,
                     "generic.unspecified".to_string(),
@@ -164,13 +222,11 @@ impl Coord {
                 ),
                 (cog, subcog) => {
                     let cog = if let Some(cog) =
-                        Category::find_by_id(cog, unsafe { CATEGORIES.get() }.unwrap())
-                            .await
-                            .unwrap()
+                        Category::find_by_id(cog, unsafe { CATEGORIES.get() }.unwrap()).await?
                     {
                         cog
                     } else {
-                        return (false, String::new(), String::new());
+                        return Ok((false, String::new(), String::new()));
                     };
 
                     let mut allowed = Clearance::is_allowed(&cog.allowed, ctx, msg)
@@ -206,45 +262,99 @@ impl Coord {
                         },
                     )
                 }
-            }
+            })
         }
 
-        let (allowed, display, name) = is_allowed_core(self, ctx, msg).await;
+        let (allowed, display, name) = is_allowed_core(self, ctx, msg).await?;
         lookup.insert((self.cog, self.subcog), (allowed, display, name));
-        allowed
+        Ok(allowed)
+    }
+
+    // "x, z", or "x, y, z" if the height is known
+    pub fn position(&self) -> String {
+        match self.y {
+            Some(y) => format!("{}, {y}, {}", self.x, self.z),
+            None => format!("{}, {}", self.x, self.z),
+        }
     }
 
+    // within r blocks in 3d, entries without a height are compared by x and z only
+    pub fn is_near(&self, x: i64, y: i64, z: i64, r: u64) -> bool {
+        let dx = i128::from(self.x) - i128::from(x);
+        let dy = self.y.map_or(0, |own| i128::from(own) - i128::from(y));
+        let dz = i128::from(self.z) - i128::from(z);
+        dx * dx + dy * dy + dz * dz <= i128::from(r).saturating_pow(2)
+    }
+
+    // the same position in the linked dimension
+    pub fn linked_pos(&self) -> Option<(Dimension, i64, i64)> {
+        let (x, z) = self.dim.to_linked(self.x, self.z)?;
+        Some((self.dim.linked()?, x, z))
+    }
+
+    // a near search of the filter moved to the linked dimension, None if it has no near and dim conditions to move
+    pub fn linked_filter(filter: &Filter) -> Option<Filter> {
+        let dim = match filter.get("dim") {
+            Some(Condition::Eq(dim)) => serde_json::from_value::<Dimension>(dim.clone()).ok()?,
+            _ => return None,
+        };
+
+        let (center, radius) = match filter.get("pos") {
+            Some(Condition::Circle { center, radius }) => (*center, *radius),
+            _ => return None,
+        };
+
+        let mut linked = filter.clone();
+        linked
+            .eq("dim", serde_json::to_value(dim.linked()?).unwrap())
+            .within_radius(
+                "pos",
+                dim.to_linked(center.0, center.1)?,
+                dim.radius_to_linked(radius)?,
+            );
+        Some(linked)
+    }
+
+    pub fn within_border(x: i64, z: i64) -> bool {
+        x.abs() <= WORLD_BORDER && z.abs() <= WORLD_BORDER
+    }
+
+    // with linked, entries at the converted position in the linked dimension count as well
+    // an entry being moved does not count as near its new position
+    #[allow(clippy::too_many_arguments)]
     pub async fn find_near(
+        world: &str,
         x: i64,
         z: i64,
         r: u64,
         dim: Dimension,
+        linked: bool,
+        except: Option<i64>,
         ctx: &Context,
         msg: &Message,
-    ) -> Option<Coord> {
-        let r2 = r.pow(2) as i64;
+    ) -> Result<Option<Coord>, StoreError> {
+        let mut filter = Filter::new();
+        filter
+            .eq("world", world)
+            .within_radius("pos", (x, z), r)
+            .eq("dim", serde_json::to_value(dim).unwrap());
 
-        let mut cursor = unsafe { COORDS.get() }
-            .unwrap()
-            .find(Document::new())
-            .await
-            .unwrap();
+        let coords = unsafe { COORDS.get() }.unwrap();
+        let mut found = coords.find(&filter).await?;
+        if let Some(linked) = linked.then(|| Self::linked_filter(&filter)).flatten() {
+            found.extend(coords.find(&linked).await?);
+        }
 
         // allowed, display_name, name
         let mut clearance_lookup: HashMap<(i64, i64), (bool, String, String)> = HashMap::new();
 
-        while let Some(coord) = cursor.next().await {
-            let coord = coord.unwrap();
-
-            if !coord.is_allowed(ctx, msg, &mut clearance_lookup).await {
-                continue;
-            }
-
-            if (x - coord.x).pow(2) + (z - coord.z).pow(2) <= r2 && coord.dim == dim {
-                return Some(coord);
+        for coord in found {
+            if Some(coord.id) != except && coord.is_allowed(ctx, msg, &mut clearance_lookup).await?
+            {
+                return Ok(Some(coord));
             }
         }
 
-        None
+        Ok(None)
     }
 }
@@ -1,14 +1,13 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, Clearance, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, PerCommandConfig};
 
 use super::{
     category::Category,
     config::COORDS_CONFIG,
     coord::{Coord, Dimension},
+    world::take_world,
 };
 
 pub struct CmdCoordAdd;
@@ -24,11 +23,29 @@ impl Command for CmdCoordAdd {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[name] [ow|nether|end] [x] [z] (category) (description) (tag1,tag2...)"]
+        &[
+            "[name] [ow|nether|end] [x] [z] (category) (description) (tag1,tag2...) (world=name)",
+            "[name] [ow|nether|end] [x] [y] [z] (category) (description) (tag1,tag2...) (world=name)",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let (name, dim, x, z, cog, desc, tags) = match args {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        // three numbers after the dimension are x, y and z
+        let (args, y) = match args.as_slice() {
+            [name, dim, x, y, z, rest @ ..]
+                if [x, y, z].iter().all(|n| n.parse::<i64>().is_ok()) =>
+            {
+                ([&[*name, *dim, *x, *z], rest].concat(), y.parse().ok())
+            }
+            _ => (args, None),
+        };
+
+        let (name, dim, x, z, cog, desc, tags) = match args.as_slice() {
             [name, dim, x, z, cog, desc, tags] if matches!(*dim, "ow" | "nether" | "end") => {
                 (name, dim, *x, *z, *cog, *desc, *tags)
             }
@@ -50,15 +67,19 @@ impl Command for CmdCoordAdd {
             return false;
         };
 
-        let (category, cog_id, subcog_id) =
-            if let Some((category, cog, subcog)) = Category::cogs_from_name(cog).await {
-                (category, cog, subcog)
-            } else {
-                let _ = msg
-                    .reply(ctx, "Entry not added because the category does not exist.")
+        let (category, cog_id, subcog_id) = match Category::cogs_from_name(&world, cog).await {
+            Ok(Some(cogs)) => cogs,
+            Ok(None) => {
+                let _ = ctx
+                    .reply(msg, "Entry not added because the category does not exist.")
                     .await;
                 return true;
-            };
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
 
         if let Some(cog) = category {
             let subcog = cog.subcategories.get(&subcog_id.unwrap_or(0).to_string());
@@ -70,9 +91,9 @@ impl Command for CmdCoordAdd {
                         .await
                         .unwrap_or(true))
             {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         "You don't have permission to add entry to this category.",
                     )
                     .await;
@@ -84,26 +105,38 @@ impl Command for CmdCoordAdd {
         let z = z.parse();
 
         if x.is_err() || z.is_err() {
-            let _ = msg.reply(ctx, "Could not parse coordinates.").await;
+            let _ = ctx.reply(msg, "Could not parse coordinates.").await;
             return true;
         }
 
         let x = x.unwrap();
         let z = z.unwrap();
 
-        if let Some(entry) = Coord::find_near(
+        let near = Coord::find_near(
+            &world,
             x,
             z,
             unsafe { COORDS_CONFIG.get() }.unwrap().prevent_add_radius,
             dim,
+            unsafe { COORDS_CONFIG.get() }.unwrap().prevent_add_linked,
+            None,
             ctx,
             msg,
         )
-        .await
-        {
-            let _ = msg
+        .await;
+
+        let near = match near {
+            Ok(near) => near,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        if let Some(entry) = near {
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     format!(
                         "There is another entry nearby, consider updating **{}**{} instead.",
                         entry.display_name,
@@ -119,12 +152,14 @@ impl Command for CmdCoordAdd {
         }
 
         let entry = Coord::new(
+            world,
             name.to_string(),
             desc.to_string(),
             msg.author.id.get(),
             cog_id,
             subcog_id.unwrap_or(0),
             x,
+            y,
             z,
             dim,
             tags.split(',')
@@ -135,14 +170,19 @@ impl Command for CmdCoordAdd {
         .await;
 
         match entry {
-            Ok(_) => {
-                let _ = msg.reply(ctx, "Entry added successfully.").await;
+            Ok(Ok(entry)) => {
+                info!(id = entry.id, name = entry.name, user = %msg.author.id, "coord added");
+                let _ = ctx.reply(msg, "Entry added successfully.").await;
             }
-            Err(e) => {
-                let _ = msg
-                    .reply(ctx, format!("Entry was not added because {e}."))
+            Ok(Err(e)) => {
+                info!(name, error = e, "coord not added");
+                let _ = ctx
+                    .reply(msg, format!("Entry was not added because {e}."))
                     .await;
             }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+            }
         }
 
         true
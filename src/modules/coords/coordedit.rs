@@ -1,20 +1,18 @@
 use std::collections::HashMap;
 
-use mongodb::bson::{doc, Document};
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-    futures::StreamExt,
-};
+use serenity::{all::Message, async_trait};
 use tokio::fs;
+use tracing::info;
 
-use crate::{sys::Command, Clearance, CollectionItem, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, Filter, PerCommandConfig, StoreError, Transaction};
 
 use super::{
     category::Category,
     collection::COORDS,
     config::COORDS_CONFIG,
     coord::{Coord, Dimension},
+    revision::Revision,
+    world::take_world,
 };
 
 pub struct CmdCoordEdit;
@@ -31,27 +29,42 @@ impl Command for CmdCoordEdit {
 
     fn usage(&self) -> &[&str] {
         &[
-            "(name|regex|id|*) (cog=value|desc=regex|near=x,z,radius|dim=ow/nether/end...|tags=tag1,tag2...) (newname=value|newdesc=value|newcog=value|newpos=x,z|newdim=ow/nether/end...|newtags=tag1,tag2...)",
-            "(category) (desc=regex|near=x,z,radius|dim=ow/nether/end...|tags=tag1,tag2...) (newname=value|newdesc=value|newcog=value|newpos=x,z|newdim=ow/nether/end...|newtags=tag1,tag2...)",
+            "(name|regex|id|*) (cog=value|desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end...|tags=tag1,tag2...|world=name) (newname=value|newdesc=value|newcog=value|newpos=x,z/x,y,z|newdim=ow/nether/end...|newtags=tag1,tag2...)",
+            "(category) (desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end...|tags=tag1,tag2...|world=name) (newname=value|newdesc=value|newcog=value|newpos=x,z/x,y,z|newdim=ow/nether/end...|newtags=tag1,tag2...)",
         ]
     }
 
-    async fn run(&self, mut args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let mut filter = Document::new();
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+        let mut args = args.as_slice();
+
+        let mut filter = Filter::new();
+        filter.eq("world", world.as_str());
         let mut name = None;
 
         if let Some(first) = args.first() {
-            if let Some((_cog, cog_id, subcog_id)) = Category::cogs_from_name(first).await {
-                filter.insert("cog", cog_id);
+            let cogs = match Category::cogs_from_name(&world, first).await {
+                Ok(cogs) => cogs,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            };
+
+            if let Some((_cog, cog_id, subcog_id)) = cogs {
+                filter.eq("cog", cog_id);
                 if let Some(subcog) = subcog_id {
-                    filter.insert("subcog", subcog);
+                    filter.eq("subcog", subcog);
                 }
             } else if *first != "*" && !first.contains('=') {
                 if let Ok(id) = first.parse::<i64>() {
-                    filter.insert("_id", id);
+                    filter.eq("_id", id);
                 } else {
                     let formatted_name = first.replace(' ', "-").to_lowercase();
-                    filter.insert("name", doc! { "$regex": &formatted_name });
+                    filter.regex("name", formatted_name.as_str());
                     name = Some(formatted_name);
                 }
             }
@@ -63,8 +76,6 @@ impl Command for CmdCoordEdit {
             return false;
         }
 
-        let mut near: Option<(i64, i64, i64)> = None;
-
         let mut newdisplay = None;
         let mut newdesc = None;
         let mut newcog = None;
@@ -72,49 +83,99 @@ impl Command for CmdCoordEdit {
         let mut newdim = None;
         let mut newtags = None;
 
+        // the near search in 3d, done after the query
+        let mut sphere = None;
+
         for arg in args.iter() {
             if let Some((left, right)) = arg.split_once('=') {
                 match left {
                     "cog" => {
                         let (_cog, cog_id, subcog_id) =
-                            if let Some(res) = Category::cogs_from_name(right).await {
-                                res
-                            } else {
-                                let _ = msg.reply(ctx, "No maching results found.").await;
-                                return true;
+                            match Category::cogs_from_name(&world, right).await {
+                                Ok(Some(res)) => res,
+                                Ok(None) => {
+                                    let _ = ctx.reply(msg, "No maching results found.").await;
+                                    return true;
+                                }
+                                Err(e) => {
+                                    let _ = ctx.reply_store_error(msg, &e).await;
+                                    return true;
+                                }
                             };
 
-                        filter.insert("cog", cog_id);
+                        filter.eq("cog", cog_id);
                         if let Some(subcog) = subcog_id {
-                            filter.insert("subcog", subcog);
+                            filter.eq("subcog", subcog);
                         }
                     }
                     "desc" => {
-                        filter.insert("description", doc! { "$regex": right });
+                        filter.regex("description", right);
                     }
                     "near" => {
-                        let args = right.splitn(3, ',').collect::<Vec<_>>();
+                        let args = right.splitn(4, ',').collect::<Vec<_>>();
 
-                        if args.len() != 3 {
-                            return false;
-                        }
+                        let (x, y, z, r) = match args.as_slice() {
+                            [x, z, r] => (x, None, z, r),
+                            [x, y, z, r] => (x, Some(y), z, r),
+                            _ => return false,
+                        };
 
-                        let x = args[0].parse::<i64>();
-                        let z = args[1].parse::<i64>();
-                        let r = args[2].parse::<u64>();
+                        let x = x.parse::<i64>();
+                        let y = y.map(|y| y.parse::<i64>()).transpose();
+                        let z = z.parse::<i64>();
+                        let r = r.parse::<u64>();
 
-                        if x.is_err() || z.is_err() || r.is_err() {
-                            let _ = msg.reply(ctx, "Could not parse nearby arguments.").await;
+                        if x.is_err() || y.is_err() || z.is_err() || r.is_err() {
+                            let _ = ctx.reply(msg, "Could not parse nearby arguments.").await;
                             return true;
                         }
 
-                        near = Some((x.unwrap(), z.unwrap(), r.unwrap().pow(2) as i64))
+                        let (x, z, r) = (x.unwrap(), z.unwrap(), r.unwrap());
+                        filter.within_radius("pos", (x, z), r);
+                        sphere = y.unwrap().map(|y| (x, y, z, r));
+                    }
+                    "y" => match right
+                        .split_once(',')
+                        .map(|(min, max)| (min.parse::<i64>(), max.parse::<i64>()))
+                    {
+                        Some((Ok(min), Ok(max))) => {
+                            filter.between("y", min.min(max), min.max(max));
+                        }
+                        _ => {
+                            let _ = ctx.reply(msg, "Could not parse Y range.").await;
+                            return true;
+                        }
+                    },
+                    "box" => {
+                        let args = right
+                            .splitn(4, ',')
+                            .map(|arg| arg.parse::<i64>())
+                            .collect::<Result<Vec<_>, _>>();
+
+                        match args.as_deref() {
+                            Ok([x1, z1, x2, z2]) => {
+                                filter.within_box("pos", (*x1, *z1), (*x2, *z2));
+                            }
+                            Ok(_) => return false,
+                            Err(_) => {
+                                let _ = ctx.reply(msg, "Could not parse box arguments.").await;
+                                return true;
+                            }
+                        }
                     }
                     "dim" if matches!(right, "ow" | "nether" | "end") => {
-                        filter.insert("dim", right).unwrap();
+                        filter.eq("dim", right);
                     }
                     "tags" => {
-                        filter.insert("tags", doc! { "$all": right.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect::<Vec<_>>()});
+                        filter.all(
+                            "tags",
+                            right
+                                .split(',')
+                                .map(|s| s.trim())
+                                .filter(|s| !s.is_empty())
+                                .map(str::to_string)
+                                .collect(),
+                        );
                     }
                     "newname" => newdisplay = Some(right),
                     "newdesc" => newdesc = Some(right),
@@ -140,24 +201,33 @@ impl Command for CmdCoordEdit {
             }
         }
 
-        if filter.contains_key("near") && !filter.contains_key("dim") {
-            let _ = msg
-                .reply(ctx, "Nearby search requires dimension to be specified.")
+        if filter.get("near").is_some() && filter.get("dim").is_none() {
+            let _ = ctx
+                .reply(msg, "Nearby search requires dimension to be specified.")
                 .await;
             return true;
         }
 
         if newpos.is_some() && newdim.is_none() {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     "Updating position requires new dimension to be specified.",
                 )
                 .await;
             return true;
         }
 
-        let mut cursor = unsafe { COORDS.get() }.unwrap().find(filter).await.unwrap();
+        let mut found = match unsafe { COORDS.get() }.unwrap().find(&filter).await {
+            Ok(found) => found,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+        if let Some((x, y, z, r)) = sphere {
+            found.retain(|entry| entry.is_near(x, y, z, r));
+        }
 
         // allowed, display_name, name
         let mut clearance_lookup: HashMap<(i64, i64), (bool, String, String)> = HashMap::new();
@@ -166,27 +236,27 @@ impl Command for CmdCoordEdit {
 
         let mut entries = Vec::new();
 
-        while let Some(entry) = cursor.next().await {
-            let entry = entry.unwrap();
-
-            if !entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
-                continue;
-            }
-
-            if let Some((x, z, r2)) = near {
-                if (x - entry.x).pow(2) + (z - entry.z).pow(2) > r2 {
-                    continue;
+        for entry in found {
+            match entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
                 }
             }
 
             let path = if let Some(cog) = &newcog_lower {
-                let path = Category::path(entry.cog, entry.subcog)
-                    .await
-                    .join(entry.id.to_string());
-                if fs::try_exists(&path).await.unwrap() {
+                let path = match Category::path(entry.cog, entry.subcog).await {
+                    Ok(path) => path.join(entry.id.to_string()),
+                    Err(e) => {
+                        let _ = ctx.reply_store_error(msg, &e).await;
+                        return true;
+                    }
+                };
+                if fs::try_exists(&path).await.unwrap_or_default() {
                     if cog == "generic.private" {
-                        let _ = msg
-                    .reply(ctx, "Update failed because cannot move entry into generic.private when it contains attachments.")
+                        let _ = ctx.reply(msg, "Update failed because cannot move entry into generic.private when it contains attachments.")
                     .await;
                         return true;
                     }
@@ -211,16 +281,16 @@ impl Command for CmdCoordEdit {
             let name = display.replace(" ", "-").to_lowercase();
 
             if name.parse::<i64>().is_ok() {
-                let _ = msg
-                    .reply(ctx, "Update failed because name cannot be an integer.")
+                let _ = ctx
+                    .reply(msg, "Update failed because name cannot be an integer.")
                     .await;
                 return true;
             }
 
             if name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         "Update failed because name contains illegal characters.",
                     )
                     .await;
@@ -228,17 +298,25 @@ impl Command for CmdCoordEdit {
             }
 
             if entries.len() > 1 {
-                let _ = msg
-                    .reply(ctx, "Update failed because cannot batch rename entries.")
+                let _ = ctx
+                    .reply(msg, "Update failed because cannot batch rename entries.")
                     .await;
                 return true;
             }
 
-            if let Some(found) = Coord::find_by_name(&name).await {
+            let found = match Coord::find_by_name(&world, &name).await {
+                Ok(found) => found,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            };
+
+            if let Some(found) = found {
                 if Some(found.id) != entries.first().map(|entry| entry.0.id) {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             "Update failed because a coord entry with that name already exists.",
                         )
                         .await;
@@ -252,16 +330,21 @@ impl Command for CmdCoordEdit {
         };
 
         let newcog = if let Some(cog) = newcog {
-            let (cog, cogid, subcogid) = if let Some(res) = Category::cogs_from_name(cog).await {
-                res
-            } else {
-                let _ = msg
-                    .reply(
-                        ctx,
-                        "Update failed because destination category does not exist.",
-                    )
-                    .await;
-                return true;
+            let (cog, cogid, subcogid) = match Category::cogs_from_name(&world, cog).await {
+                Ok(Some(res)) => res,
+                Ok(None) => {
+                    let _ = ctx
+                        .reply(
+                            msg,
+                            "Update failed because destination category does not exist.",
+                        )
+                        .await;
+                    return true;
+                }
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
             };
 
             let mut allowed = true;
@@ -279,10 +362,7 @@ impl Command for CmdCoordEdit {
             }
 
             if !allowed {
-                let _ = msg
-                    .reply(
-                        ctx,
-                        "Update failed because you don't have permission to write to the destination category.",
+                let _ = ctx.reply(msg, "Update failed because you don't have permission to write to the destination category.",
                     )
                     .await;
                 return true;
@@ -294,29 +374,38 @@ impl Command for CmdCoordEdit {
         };
 
         let newpos = if let Some(newpos) = newpos {
-            let res = if let Some((x, z)) = newpos.split_once(',') {
-                let x = x.parse::<i64>();
-                let z = z.parse::<i64>();
-
-                if x.is_err() || z.is_err() {
-                    None
-                } else {
-                    Some((x.unwrap(), z.unwrap()))
-                }
-            } else {
-                None
+            // a position without y clears the height
+            let res = match newpos
+                .split(',')
+                .map(|n| n.parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .as_deref()
+            {
+                Ok([x, z]) => Some((*x, None, *z)),
+                Ok([x, y, z]) => Some((*x, Some(*y), *z)),
+                _ => None,
             };
 
             if res.is_none() {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         "Update failed because could not parse new coordinates.",
                     )
                     .await;
                 return true;
             }
 
+            if res.is_some_and(|(x, _, z)| !Coord::within_border(x, z)) {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Update failed because coordinates are outside the world border.",
+                    )
+                    .await;
+                return true;
+            }
+
             res
         } else {
             None
@@ -329,33 +418,44 @@ impl Command for CmdCoordEdit {
             && newpos.is_none()
             && newtags.is_none()
         {
-            let _ = msg
-                .reply(ctx, "Update failed because no fields are changed.")
+            let _ = ctx
+                .reply(msg, "Update failed because no fields are changed.")
                 .await;
             return true;
         }
 
         if entries.len() > 1 && newpos.is_some() {
-            let _ = msg
-                .reply(ctx, "Bulk editing location is not supported.")
+            let _ = ctx
+                .reply(msg, "Bulk editing location is not supported.")
                 .await;
             return true;
         }
 
-        if let Some((x, z)) = newpos {
-            if let Some(entry) = Coord::find_near(
+        if let Some((x, _, z)) = newpos {
+            let near = match Coord::find_near(
+                &world,
                 x,
                 z,
                 unsafe { COORDS_CONFIG.get() }.unwrap().prevent_add_radius,
                 newdim.unwrap(),
+                unsafe { COORDS_CONFIG.get() }.unwrap().prevent_add_linked,
+                entries.first().map(|(entry, _)| entry.id),
                 ctx,
                 msg,
             )
             .await
             {
-                let _ = msg
+                Ok(near) => near,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            };
+
+            if let Some(entry) = near {
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "There is another entry nearby, consider updating **{}**{} instead.",
                             entry.display_name,
@@ -376,9 +476,9 @@ impl Command for CmdCoordEdit {
                 .iter()
                 .all(|(entry, _)| entry.author_id == msg.author.id.get())
         {
-            let _ = msg
+            let _ = ctx
                 .reply(
-                    ctx,
+                    msg,
                     "Entries not moved to generic.private because you don't own all the entries.",
                 )
                 .await;
@@ -386,11 +486,23 @@ impl Command for CmdCoordEdit {
         }
 
         let to_dir = if let Some(newcog) = newcog {
-            Some(Category::path(newcog.0, newcog.1.unwrap_or_default()).await)
+            match Category::path(newcog.0, newcog.1.unwrap_or_default()).await {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
         } else {
             None
         };
 
+        let mut tx = Transaction::new();
+        let before = entries
+            .iter()
+            .map(|(entry, _)| entry.clone())
+            .collect::<Vec<_>>();
+
         for (entry, path) in entries.iter_mut() {
             if let Some(name) = &newname {
                 entry.name = name.clone();
@@ -401,9 +513,11 @@ impl Command for CmdCoordEdit {
                 entry.description = desc.to_string();
             }
 
-            if let Some((x, z)) = newpos {
+            if let Some((x, y, z)) = newpos {
                 entry.x = x;
+                entry.y = y;
                 entry.z = z;
+                entry.pos = [x, z];
             }
 
             if let Some((cog, sub)) = newcog {
@@ -420,22 +534,63 @@ impl Command for CmdCoordEdit {
             }
 
             if let Some(path) = path {
-                fs::rename(path, to_dir.as_ref().unwrap().join(entry.id.to_string()))
-                    .await
-                    .unwrap();
+                tx.rename(
+                    path.clone(),
+                    to_dir.as_ref().unwrap().join(entry.id.to_string()),
+                );
+            }
+        }
+
+        // every entry is updated along with its revision and attachments, or none of them
+        let mut revisions = Vec::new();
+        for ((entry, _), before) in entries.iter().zip(before.iter()) {
+            let recorded = match tx.replace(unsafe { COORDS.get() }.unwrap(), entry.id, entry) {
+                Ok(_) => Revision::record(before, entry, msg, &mut tx).await,
+                Err(e) => Err(e),
+            };
+
+            match recorded {
+                Ok(revision) => revisions.push(revision),
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
+        }
+
+        match tx.commit().await {
+            Ok(()) => {}
+            Err(e) if Revision::is_conflict(&e) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Update failed because an entry was edited at the same time, try again.",
+                    )
+                    .await;
+                return true;
+            }
+            Err(StoreError::Duplicate(_)) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Update failed because a coord entry with that name already exists.",
+                    )
+                    .await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply(msg, format!("Update failed because {e}.")).await;
+                return true;
             }
         }
 
-        for (entry, _) in entries.iter() {
-            entry
-                .save_replace(unsafe { COORDS.get() }.unwrap())
-                .await
-                .unwrap();
+        for ((entry, _), revision) in entries.iter().zip(revisions) {
+            info!(id = entry.id, name = entry.name, rev = revision.map(|revision| revision.rev), user = %msg.author.id, "coord updated");
         }
 
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
                     "{} {} updated.",
                     entries.len(),
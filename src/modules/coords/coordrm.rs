@@ -1,15 +1,16 @@
 use std::collections::HashMap;
 
-use mongodb::bson::{doc, Document};
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-    futures::StreamExt,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use crate::{sys::Command, PerCommandConfig};
+use crate::{sys::Command, Context, Filter, PerCommandConfig, Transaction};
 
-use super::{category::Category, collection::COORDS};
+use super::{
+    category::Category,
+    collection::COORDS,
+    deleted::{TrashEntry, Trashed},
+    world::take_world,
+};
 
 pub struct CmdCoordRm;
 
@@ -25,27 +26,42 @@ impl Command for CmdCoordRm {
 
     fn usage(&self) -> &[&str] {
         &[
-            "(name|regex|id|*) (cog=value|page=value|desc=regex|near=x,z,radius|dim=ow/nether/end|tags=tag1,tag2..)",
-            "(category) (page=value|desc=regex|near=x,z,radius|dim=ow/nether/end|tags=tag1,tag2..)"
+            "(name|regex|id|*) (cog=value|page=value|desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end|tags=tag1,tag2..|world=name)",
+            "(category) (page=value|desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end|tags=tag1,tag2..|world=name)"
         ]
     }
 
-    async fn run(&self, mut args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let mut filter = Document::new();
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+        let mut args = args.as_slice();
+
+        let mut filter = Filter::new();
+        filter.eq("world", world.as_str());
         let mut name = None;
 
         if let Some(first) = args.first() {
-            if let Some((_cog, cog_id, subcog_id)) = Category::cogs_from_name(first).await {
-                filter.insert("cog", cog_id);
+            let cogs = match Category::cogs_from_name(&world, first).await {
+                Ok(cogs) => cogs,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            };
+
+            if let Some((_cog, cog_id, subcog_id)) = cogs {
+                filter.eq("cog", cog_id);
                 if let Some(subcog) = subcog_id {
-                    filter.insert("subcog", subcog);
+                    filter.eq("subcog", subcog);
                 }
             } else if *first != "*" && !first.contains('=') {
                 if let Ok(id) = first.parse::<i64>() {
-                    filter.insert("_id", id);
+                    filter.eq("_id", id);
                 } else {
                     let formatted_name = first.replace(' ', "-").to_lowercase();
-                    filter.insert("name", doc! { "$regex": &formatted_name });
+                    filter.regex("name", formatted_name.as_str());
                     name = Some(formatted_name);
                 }
             }
@@ -57,51 +73,99 @@ impl Command for CmdCoordRm {
             return false;
         }
 
-        let mut near: Option<(i64, i64, i64)> = None;
+        // the near search in 3d, done after the query
+        let mut sphere = None;
 
         for arg in args.iter() {
             if let Some((left, right)) = arg.split_once('=') {
                 match left {
                     "cog" => {
                         let (_cog, cog_id, subcog_id) =
-                            if let Some(res) = Category::cogs_from_name(right).await {
-                                res
-                            } else {
-                                let _ = msg.reply(ctx, "No maching results found.").await;
-                                return true;
+                            match Category::cogs_from_name(&world, right).await {
+                                Ok(Some(res)) => res,
+                                Ok(None) => {
+                                    let _ = ctx.reply(msg, "No maching results found.").await;
+                                    return true;
+                                }
+                                Err(e) => {
+                                    let _ = ctx.reply_store_error(msg, &e).await;
+                                    return true;
+                                }
                             };
 
-                        filter.insert("cog", cog_id);
+                        filter.eq("cog", cog_id);
                         if let Some(subcog) = subcog_id {
-                            filter.insert("subcog", subcog);
+                            filter.eq("subcog", subcog);
                         }
                     }
                     "desc" => {
-                        filter.insert("description", doc! { "$regex": right });
+                        filter.regex("description", right);
                     }
                     "near" => {
-                        let args = right.splitn(3, ',').collect::<Vec<_>>();
+                        let args = right.splitn(4, ',').collect::<Vec<_>>();
 
-                        if args.len() != 3 {
-                            return false;
-                        }
+                        let (x, y, z, r) = match args.as_slice() {
+                            [x, z, r] => (x, None, z, r),
+                            [x, y, z, r] => (x, Some(y), z, r),
+                            _ => return false,
+                        };
 
-                        let x = args[0].parse::<i64>();
-                        let z = args[1].parse::<i64>();
-                        let r = args[2].parse::<u64>();
+                        let x = x.parse::<i64>();
+                        let y = y.map(|y| y.parse::<i64>()).transpose();
+                        let z = z.parse::<i64>();
+                        let r = r.parse::<u64>();
 
-                        if x.is_err() || z.is_err() || r.is_err() {
-                            let _ = msg.reply(ctx, "Could not parse nearby arguments.").await;
+                        if x.is_err() || y.is_err() || z.is_err() || r.is_err() {
+                            let _ = ctx.reply(msg, "Could not parse nearby arguments.").await;
                             return true;
                         }
 
-                        near = Some((x.unwrap(), z.unwrap(), r.unwrap().pow(2) as i64))
+                        let (x, z, r) = (x.unwrap(), z.unwrap(), r.unwrap());
+                        filter.within_radius("pos", (x, z), r);
+                        sphere = y.unwrap().map(|y| (x, y, z, r));
+                    }
+                    "y" => match right
+                        .split_once(',')
+                        .map(|(min, max)| (min.parse::<i64>(), max.parse::<i64>()))
+                    {
+                        Some((Ok(min), Ok(max))) => {
+                            filter.between("y", min.min(max), min.max(max));
+                        }
+                        _ => {
+                            let _ = ctx.reply(msg, "Could not parse Y range.").await;
+                            return true;
+                        }
+                    },
+                    "box" => {
+                        let args = right
+                            .splitn(4, ',')
+                            .map(|arg| arg.parse::<i64>())
+                            .collect::<Result<Vec<_>, _>>();
+
+                        match args.as_deref() {
+                            Ok([x1, z1, x2, z2]) => {
+                                filter.within_box("pos", (*x1, *z1), (*x2, *z2));
+                            }
+                            Ok(_) => return false,
+                            Err(_) => {
+                                let _ = ctx.reply(msg, "Could not parse box arguments.").await;
+                                return true;
+                            }
+                        }
                     }
                     "dim" if matches!(right, "ow" | "nether" | "end") => {
-                        filter.insert("dim", right).unwrap();
+                        filter.eq("dim", right);
                     }
                     "tags" => {
-                        filter.insert("tags", doc! { "$all": right.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect::<Vec<_>>()});
+                        filter.all(
+                            "tags",
+                            right
+                                .split(',')
+                                .map(|s| s.trim())
+                                .filter(|s| !s.is_empty())
+                                .map(str::to_string)
+                                .collect(),
+                        );
                     }
                     _ => return false,
                 }
@@ -110,30 +174,36 @@ impl Command for CmdCoordRm {
             }
         }
 
-        if filter.contains_key("near") && !filter.contains_key("dim") {
-            let _ = msg
-                .reply(ctx, "Nearby search requires dimension to be specified.")
+        if filter.get("near").is_some() && filter.get("dim").is_none() {
+            let _ = ctx
+                .reply(msg, "Nearby search requires dimension to be specified.")
                 .await;
             return true;
         }
 
-        let mut cursor = unsafe { COORDS.get() }.unwrap().find(filter).await.unwrap();
+        let mut found = match unsafe { COORDS.get() }.unwrap().find(&filter).await {
+            Ok(found) => found,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+        if let Some((x, y, z, r)) = sphere {
+            found.retain(|entry| entry.is_near(x, y, z, r));
+        }
 
         // allowed, display_name, name
         let mut clearance_lookup: HashMap<(i64, i64), (bool, String, String)> = HashMap::new();
 
         let mut entries = Vec::new();
 
-        while let Some(entry) = cursor.next().await {
-            let entry = entry.unwrap();
-
-            if !entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
-                continue;
-            }
-
-            if let Some((x, z, r2)) = near {
-                if (x - entry.x).pow(2) + (z - entry.z).pow(2) > r2 {
-                    continue;
+        for entry in found {
+            match entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
                 }
             }
 
@@ -145,24 +215,53 @@ impl Command for CmdCoordRm {
             entries.push(entry);
         }
 
+        let mut tx = Transaction::new();
+        let mut trashed = Vec::new();
         for entry in entries.iter() {
-            let _ = unsafe { COORDS.get() }
-                .unwrap()
-                .delete_one(doc! { "_id": entry.id })
-                .await
-                .unwrap();
+            match TrashEntry::trash(
+                Trashed::Coord {
+                    item: entry.clone(),
+                },
+                msg,
+                &mut tx,
+            )
+            .await
+            {
+                Ok(entry) => trashed.push(entry),
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
+            tx.delete(unsafe { COORDS.get() }.unwrap(), entry.id);
         }
 
-        let _ = msg
+        if let Err(e) = tx.commit().await {
+            let _ = ctx
+                .reply(msg, format!("Entries not removed because {e}."))
+                .await;
+            return true;
+        }
+
+        for (entry, trashed) in entries.iter().zip(trashed) {
+            info!(id = entry.id, name = entry.name, trash = trashed.id, user = %msg.author.id, "coord removed");
+        }
+
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
-                    "{} {} removed.",
+                    "{} {} removed.{}",
                     entries.len(),
                     if entries.len() > 1 {
                         "entries"
                     } else {
                         "entry"
+                    },
+                    if entries.is_empty() {
+                        ""
+                    } else {
+                        " See `trash` to restore."
                     }
                 ),
             )
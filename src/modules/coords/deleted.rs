@@ -0,0 +1,262 @@
+use std::{path::PathBuf, sync::OnceLock, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::Message;
+use tokio::{fs, task::JoinHandle};
+use tracing::{info, warn};
+
+use crate::{
+    Clearance, CollectionItem, Context, Counter, Filter, Storage, StoreError, Transaction,
+};
+
+use super::{
+    category::{Category, Subcategory},
+    collection::{CATEGORIES, COORDS, TRASH, TRASH_NAME},
+    config::COORDS_CONFIG,
+    coord::Coord,
+    revision::Revision,
+};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static PURGE_TASK: OnceLock<JoinHandle<()>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Trashed {
+    Coord { item: Coord },
+    Category { item: Category },
+    Subcategory { cog: i64, item: Subcategory },
+}
+
+// an entry removed by coordrm or cogrm, kept until restored or purged
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    #[serde(rename = "_id")]
+    pub id: i64,
+    #[serde(flatten)]
+    pub trashed: Trashed,
+    pub deleted_by: u64,
+    pub deleted_at: i64,
+    // attachment directory of a coord at the time it was removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<String>,
+}
+
+impl CollectionItem<i64> for TrashEntry {
+    fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+impl TrashEntry {
+    // added to the transaction that removes the original, so neither is written without the other
+    pub async fn trash(
+        trashed: Trashed,
+        msg: &Message,
+        tx: &mut Transaction,
+    ) -> Result<Self, StoreError> {
+        let attachments = match &trashed {
+            Trashed::Coord { item } => {
+                let path = Category::path(item.cog, item.subcog)
+                    .await?
+                    .join(item.id.to_string());
+                fs::try_exists(&path)
+                    .await
+                    .unwrap_or_default()
+                    .then(|| path.to_string_lossy().to_string())
+            }
+            _ => None,
+        };
+
+        let entry = Self {
+            id: Counter::bump_get(TRASH_NAME).await?,
+            trashed,
+            deleted_by: msg.author.id.get(),
+            deleted_at: chrono::Utc::now().timestamp(),
+            attachments,
+        };
+
+        tx.insert(unsafe { TRASH.get() }.unwrap(), entry.id, &entry)?;
+        Ok(entry)
+    }
+
+    pub fn name(&self) -> String {
+        match &self.trashed {
+            Trashed::Coord { item } => item.name.clone(),
+            Trashed::Category { item } => item.name.clone(),
+            Trashed::Subcategory { item, .. } => item.name.clone(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match &self.trashed {
+            Trashed::Coord { .. } => "coord",
+            Trashed::Category { .. } => "category",
+            Trashed::Subcategory { .. } => "subcategory",
+        }
+    }
+
+    // the deleter can always see their own entries
+    pub async fn is_allowed(&self, ctx: &Context, msg: &Message) -> Result<bool, StoreError> {
+        if self.deleted_by == msg.author.id.get() {
+            return Ok(true);
+        }
+
+        Ok(match &self.trashed {
+            Trashed::Coord { item } => item.is_allowed(ctx, msg, &mut Default::default()).await?,
+            Trashed::Category { item } => Clearance::is_allowed(&item.allowed, ctx, msg)
+                .await
+                .unwrap_or(true),
+            Trashed::Subcategory { cog, item } => {
+                let cog_allowed =
+                    match Category::find_by_id(*cog, unsafe { CATEGORIES.get() }.unwrap()).await? {
+                        Some(cog) => Clearance::is_allowed(&cog.allowed, ctx, msg)
+                            .await
+                            .unwrap_or(true),
+                        None => false,
+                    };
+
+                cog_allowed
+                    && Clearance::is_allowed(&item.allowed, ctx, msg)
+                        .await
+                        .unwrap_or(true)
+            }
+        })
+    }
+
+    // puts the item back and removes the trash entry, the inner error is meant for the user
+    pub async fn restore(&self) -> Result<Result<(), String>, StoreError> {
+        let mut tx = Transaction::new();
+
+        match &self.trashed {
+            Trashed::Coord { item } => {
+                if !matches!((item.cog, item.subcog), (0, 0) | (0, 1)) {
+                    let cog = Category::find_by_id(item.cog, unsafe { CATEGORIES.get() }.unwrap())
+                        .await?;
+                    if !cog.is_some_and(|cog| {
+                        item.subcog == 0 || cog.subcategories.contains_key(&item.subcog.to_string())
+                    }) {
+                        return Ok(Err(
+                            "The category of this entry no longer exists, restore it first."
+                                .to_string(),
+                        ));
+                    }
+                }
+
+                tx.insert(unsafe { COORDS.get() }.unwrap(), item.id, item)?;
+
+                if let Some(from) = &self.attachments {
+                    let from = PathBuf::from(from);
+                    let to = Category::path(item.cog, item.subcog)
+                        .await?
+                        .join(item.id.to_string());
+
+                    if from != to && fs::try_exists(&from).await.unwrap_or_default() {
+                        tx.rename(from, to);
+                    }
+                }
+            }
+            Trashed::Category { item } => {
+                tx.insert(unsafe { CATEGORIES.get() }.unwrap(), item.id, item)?;
+            }
+            Trashed::Subcategory { cog, item } => {
+                let categories = unsafe { CATEGORIES.get() }.unwrap();
+                let mut cog = match Category::find_by_id(*cog, categories).await? {
+                    Some(cog) => cog,
+                    None => {
+                        return Ok(Err(
+                            "The parent category no longer exists, restore it first.".to_string(),
+                        ))
+                    }
+                };
+
+                if cog.contains(&item.name) {
+                    return Ok(Err(format!(
+                        "A subcategory named **{}.{}** already exists.",
+                        cog.name, item.name
+                    )));
+                }
+
+                cog.subcategories.insert(item.id.to_string(), item.clone());
+                tx.replace(categories, cog.id, &cog)?;
+            }
+        }
+
+        tx.delete(unsafe { TRASH.get() }.unwrap(), self.id);
+
+        Ok(match tx.commit().await {
+            Ok(()) => Ok(()),
+            Err(StoreError::Duplicate(_)) => Err(match &self.trashed {
+                Trashed::Coord { item } => {
+                    format!("A coord entry named **{}** already exists.", item.name)
+                }
+                Trashed::Category { item } => {
+                    format!("A category named **{}** already exists.", item.name)
+                }
+                Trashed::Subcategory { item, .. } => {
+                    format!("A subcategory named **{}** already exists.", item.name)
+                }
+            }),
+            Err(e) => Err(format!("Restore failed because {e}.")),
+        })
+    }
+
+    // removes entries older than the retention, along with the attachments of trashed coords
+    pub async fn purge() -> Result<usize, StoreError> {
+        let (trash, config) = match unsafe { (TRASH.get(), COORDS_CONFIG.get()) } {
+            (Some(trash), Some(config)) if config.trash_retention_days != 0 => (trash, config),
+            _ => return Ok(0),
+        };
+
+        let cutoff =
+            chrono::Utc::now().timestamp() - i64::from(config.trash_retention_days) * 86400;
+        let mut purged = 0;
+
+        for entry in trash.find(&Filter::new()).await? {
+            if entry.deleted_at > cutoff {
+                continue;
+            }
+
+            if let Some(path) = &entry.attachments {
+                if let Err(e) = fs::remove_dir_all(path).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        warn!(id = entry.id, path, error = %e, "could not remove trashed attachments");
+                        continue;
+                    }
+                }
+            }
+
+            if let Trashed::Coord { item } = &entry.trashed {
+                Revision::delete_all(item.id).await?;
+            }
+
+            entry.delete(trash).await?;
+            info!(
+                id = entry.id,
+                kind = entry.kind(),
+                name = entry.name(),
+                "trash entry purged"
+            );
+            purged += 1;
+        }
+
+        Ok(purged)
+    }
+
+    // started once, the purge is skipped while storage is down
+    pub fn spawn_purge() {
+        PURGE_TASK.get_or_init(|| {
+            tokio::spawn(async {
+                loop {
+                    if Storage::is_healthy() {
+                        if let Err(e) = Self::purge().await {
+                            warn!(error = %e, "trash purge failed");
+                        }
+                    }
+                    tokio::time::sleep(PURGE_INTERVAL).await;
+                }
+            })
+        });
+    }
+}
@@ -1,16 +1,16 @@
 use std::collections::HashMap;
 use std::fmt::Write;
 
-use mongodb::bson::{doc, Document};
 use serenity::{
-    all::{Context, Message, UserId},
+    all::{Message, UserId},
     async_trait,
-    futures::StreamExt,
 };
 
-use crate::{sys::Command, PerCommandConfig};
+use crate::{sys::Command, Context, Filter, PerCommandConfig, Sort};
 
-use super::{category::Category, collection::COORDS, config::COORDS_CONFIG};
+use super::{
+    category::Category, collection::COORDS, config::COORDS_CONFIG, coord::Coord, world::take_world,
+};
 
 pub struct CmdFind;
 
@@ -26,27 +26,42 @@ impl Command for CmdFind {
 
     fn usage(&self) -> &[&str] {
         &[
-            "(name|regex|id|*) (cog=value|page=value|desc=regex|near=x,z,radius|dim=ow/nether/end|tags=tag1,tag2..)",
-            "(category) (page=value|desc=regex|near=x,z,radius|dim=ow/nether/end|tags=tag1,tag2..)"
+            "(name|regex|id|*) (cog=value|page=value|desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end|tags=tag1,tag2..|world=name) (linked)",
+            "(category) (page=value|desc=regex|near=x,(y,)z,radius|y=min,max|box=x1,z1,x2,z2|dim=ow/nether/end|tags=tag1,tag2..|world=name) (linked)"
         ]
     }
 
-    async fn run(&self, mut args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let mut filter = Document::new();
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+        let mut args = args.as_slice();
+
+        let mut filter = Filter::new();
+        filter.eq("world", world.as_str());
         let mut name = None;
 
         if let Some(first) = args.first() {
-            if let Some((_cog, cog_id, subcog_id)) = Category::cogs_from_name(first).await {
-                filter.insert("cog", cog_id);
+            let cogs = match Category::cogs_from_name(&world, first).await {
+                Ok(cogs) => cogs,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            };
+
+            if let Some((_cog, cog_id, subcog_id)) = cogs {
+                filter.eq("cog", cog_id);
                 if let Some(subcog) = subcog_id {
-                    filter.insert("subcog", subcog);
+                    filter.eq("subcog", subcog);
                 }
             } else if *first != "*" && !first.contains('=') {
                 if let Ok(id) = first.parse::<i64>() {
-                    filter.insert("_id", id);
+                    filter.eq("_id", id);
                 } else {
                     let formatted_name = first.replace(' ', "-").to_lowercase();
-                    filter.insert("name", doc! { "$regex": &formatted_name });
+                    filter.regex("name", formatted_name.as_str());
                     name = Some(formatted_name);
                 }
             }
@@ -59,80 +74,165 @@ impl Command for CmdFind {
         }
 
         let mut page: Option<u32> = None;
-        let mut near: Option<(i64, i64, i64)> = None;
+        let mut linked = false;
+
+        // the near search in 3d, done after the query
+        let mut sphere = None;
 
         for arg in args.iter() {
             if let Some((left, right)) = arg.split_once('=') {
                 match left {
                     "cog" => {
                         let (_cog, cog_id, subcog_id) =
-                            if let Some(res) = Category::cogs_from_name(right).await {
-                                res
-                            } else {
-                                let _ = msg.reply(ctx, "No maching results found.").await;
-                                return true;
+                            match Category::cogs_from_name(&world, right).await {
+                                Ok(Some(res)) => res,
+                                Ok(None) => {
+                                    let _ = ctx.reply(msg, "No maching results found.").await;
+                                    return true;
+                                }
+                                Err(e) => {
+                                    let _ = ctx.reply_store_error(msg, &e).await;
+                                    return true;
+                                }
                             };
 
-                        filter.insert("cog", cog_id);
+                        filter.eq("cog", cog_id);
                         if let Some(subcog) = subcog_id {
-                            filter.insert("subcog", subcog);
+                            filter.eq("subcog", subcog);
                         }
                     }
                     "desc" => {
-                        filter.insert("description", doc! { "$regex": right });
+                        filter.regex("description", right);
                     }
                     "page" => {
                         if let Ok(parsed) = right.parse() {
                             page = Some(parsed);
                         } else {
-                            let _ = msg.reply(ctx, "Could not parse page number.").await;
+                            let _ = ctx.reply(msg, "Could not parse page number.").await;
                             return true;
                         }
                     }
                     "near" => {
-                        let args = right.splitn(3, ',').collect::<Vec<_>>();
+                        let args = right.splitn(4, ',').collect::<Vec<_>>();
 
-                        if args.len() != 3 {
-                            return false;
-                        }
+                        let (x, y, z, r) = match args.as_slice() {
+                            [x, z, r] => (x, None, z, r),
+                            [x, y, z, r] => (x, Some(y), z, r),
+                            _ => return false,
+                        };
 
-                        let x = args[0].parse::<i64>();
-                        let z = args[1].parse::<i64>();
-                        let r = args[2].parse::<u64>();
+                        let x = x.parse::<i64>();
+                        let y = y.map(|y| y.parse::<i64>()).transpose();
+                        let z = z.parse::<i64>();
+                        let r = r.parse::<u64>();
 
-                        if x.is_err() || z.is_err() || r.is_err() {
-                            let _ = msg.reply(ctx, "Could not parse nearby arguments.").await;
+                        if x.is_err() || y.is_err() || z.is_err() || r.is_err() {
+                            let _ = ctx.reply(msg, "Could not parse nearby arguments.").await;
                             return true;
                         }
 
-                        near = Some((x.unwrap(), z.unwrap(), r.unwrap().pow(2) as i64))
+                        let (x, z, r) = (x.unwrap(), z.unwrap(), r.unwrap());
+                        filter.within_radius("pos", (x, z), r);
+                        sphere = y.unwrap().map(|y| (x, y, z, r));
+                    }
+                    "y" => match right
+                        .split_once(',')
+                        .map(|(min, max)| (min.parse::<i64>(), max.parse::<i64>()))
+                    {
+                        Some((Ok(min), Ok(max))) => {
+                            filter.between("y", min.min(max), min.max(max));
+                        }
+                        _ => {
+                            let _ = ctx.reply(msg, "Could not parse Y range.").await;
+                            return true;
+                        }
+                    },
+                    "box" => {
+                        let args = right
+                            .splitn(4, ',')
+                            .map(|arg| arg.parse::<i64>())
+                            .collect::<Result<Vec<_>, _>>();
+
+                        match args.as_deref() {
+                            Ok([x1, z1, x2, z2]) => {
+                                filter.within_box("pos", (*x1, *z1), (*x2, *z2));
+                            }
+                            Ok(_) => return false,
+                            Err(_) => {
+                                let _ = ctx.reply(msg, "Could not parse box arguments.").await;
+                                return true;
+                            }
+                        }
                     }
                     "dim" if matches!(right, "ow" | "nether" | "end") => {
-                        filter.insert("dim", right).unwrap();
+                        filter.eq("dim", right);
                     }
                     "tags" => {
-                        filter.insert("tags", doc! { "$all": right.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect::<Vec<_>>()});
+                        filter.all(
+                            "tags",
+                            right
+                                .split(',')
+                                .map(|s| s.trim())
+                                .filter(|s| !s.is_empty())
+                                .map(str::to_string)
+                                .collect(),
+                        );
                     }
                     _ => return false,
                 }
+            } else if *arg == "linked" {
+                linked = true;
             } else {
                 return false;
             }
         }
 
-        if filter.contains_key("near") && !filter.contains_key("dim") {
-            let _ = msg
-                .reply(ctx, "Nearby search requires dimension to be specified.")
+        if filter.get("near").is_some() && filter.get("dim").is_none() {
+            let _ = ctx
+                .reply(msg, "Nearby search requires dimension to be specified.")
                 .await;
             return true;
         }
 
-        let mut cursor = unsafe { COORDS.get() }
-            .unwrap()
-            .find(filter)
-            .sort(doc! {"$natural": -1})
-            .await
-            .unwrap();
+        let linked = if linked {
+            match Coord::linked_filter(&filter) {
+                Some(linked) => Some(linked),
+                None => {
+                    let _ = ctx
+                        .reply(
+                            msg,
+                            "Linked search requires near= and dim= to be ow or nether.",
+                        )
+                        .await;
+                    return true;
+                }
+            }
+        } else {
+            None
+        };
+
+        let coords = unsafe { COORDS.get() }.unwrap();
+        let mut found = match coords.find_sorted(&filter, Sort::Reverse).await {
+            Ok(found) => found,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+        // before the linked entries are added, a height does not carry over to the linked dimension
+        if let Some((x, y, z, r)) = sphere {
+            found.retain(|entry| entry.is_near(x, y, z, r));
+        }
+        if let Some(linked) = &linked {
+            match coords.find_sorted(linked, Sort::Reverse).await {
+                Ok(linked) => found.extend(linked),
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
+            found.sort_by_key(|entry| -entry.id);
+        }
 
         let page_size = unsafe { COORDS_CONFIG.get() }.unwrap().page_size;
         let to_skip = page.unwrap_or(0).saturating_sub(1) * page_size;
@@ -143,16 +243,13 @@ impl Command for CmdFind {
         let mut entries_owned =
             Vec::with_capacity(unsafe { COORDS_CONFIG.get() }.unwrap().page_size as usize);
 
-        while let Some(entry) = cursor.next().await {
-            let entry = entry.unwrap();
-
-            if !entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
-                continue;
-            }
-
-            if let Some((x, z, r2)) = near {
-                if (x - entry.x).pow(2) + (z - entry.z).pow(2) > r2 {
-                    continue;
+        for entry in found {
+            match entry.is_allowed(ctx, msg, &mut clearance_lookup).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
                 }
             }
 
@@ -184,16 +281,16 @@ impl Command for CmdFind {
 
         match entries.len() {
             0 => {
-                let _ = msg.reply(ctx, "No maching results found.").await;
+                let _ = ctx.reply(msg, "No maching results found.").await;
             }
             1 => {
                 let (_, entry) = &entries[0];
                 let (_, display, name) = clearance_lookup.get(&(entry.cog, entry.subcog)).unwrap();
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
-                            "**[{}{}] {}: {}**\n{}\n{}\n\nx=||{}|| z=||{}|| in the {}{}",
+                            "**[{}{}] {}: {}**\n{}\n{}\n\nx=||{}||{} z=||{}|| in the {}{}{}",
                             display,
                             if display != name {
                                 format!(" **({name})**")
@@ -213,8 +310,15 @@ impl Command for CmdFind {
                                 format!("Tags: {}", entry.tags.join(", "))
                             },
                             entry.x,
+                            entry.y.map(|y| format!(" y=||{y}||")).unwrap_or_default(),
                             entry.z,
                             entry.dim,
+                            match entry.linked_pos().filter(|_| linked.is_some()) {
+                                Some((dim, x, z)) => {
+                                    format!(", x=||{x}|| z=||{z}|| in the {dim}")
+                                }
+                                None => String::new(),
+                            },
                             if let Ok(user) = UserId::new(entry.author_id).to_user(ctx).await {
                                 format!("\n\n*Entry added by {}.*", user.name)
                             } else {
@@ -225,9 +329,9 @@ impl Command for CmdFind {
                     .await;
             }
             _ => {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "Showing {} results.{}{}",
                             entries.len(),
@@ -238,7 +342,7 @@ impl Command for CmdFind {
                                         clearance_lookup.get(&(entry.cog, entry.subcog)).unwrap();
                                     write!(
                                         current,
-                                        "\n{}. **{}**{} in {}{}{}",
+                                        "\n{}. **{}**{} in {}{}{}{}",
                                         no + 1,
                                         entry.display_name,
                                         if entry.display_name != entry.name {
@@ -269,6 +373,15 @@ impl Command for CmdFind {
                                                     String::new()
                                                 }
                                             )
+                                        },
+                                        // both positions, so the results can be told apart
+                                        match entry.linked_pos().filter(|_| linked.is_some()) {
+                                            Some((dim, x, z)) => format!(
+                                                " at ||{}|| in the {}, ||{x}, {z}|| in the {dim}",
+                                                entry.position(),
+                                                entry.dim
+                                            ),
+                                            None => String::new(),
                                         }
                                     )
                                     .unwrap();
@@ -0,0 +1,162 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+use serenity::{
+    all::{Message, UserId},
+    async_trait,
+};
+
+use crate::{sys::Command, Context, PerCommandConfig};
+
+use super::{config::COORDS_CONFIG, coord::Coord, revision::Revision, world::take_world};
+
+pub struct CmdHistory;
+
+#[async_trait]
+impl Command for CmdHistory {
+    fn name(&self) -> &str {
+        "history"
+    }
+
+    fn description(&self) -> &str {
+        "Show the edit history of a coord DB entry."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["[name|id] (page=value) (world=name)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (entry, page) = match args.as_slice() {
+            [entry] => (entry, None),
+            [entry, page] => match page.strip_prefix("page=").map(str::parse::<u32>) {
+                Some(Ok(page)) => (entry, Some(page)),
+                Some(Err(_)) => {
+                    let _ = ctx.reply(msg, "Could not parse page number.").await;
+                    return true;
+                }
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        let entry = match Coord::find_by_name_or_id(&world, entry).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "No maching results found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        match entry.is_allowed(ctx, msg, &mut Default::default()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = ctx.reply(msg, "No maching results found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        }
+
+        let revisions = match Revision::of(entry.id).await {
+            Ok(revisions) => revisions,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        if revisions.is_empty() {
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!("**{}** has never been edited.", entry.display_name),
+                )
+                .await;
+            return true;
+        }
+
+        let page_size = unsafe { COORDS_CONFIG.get() }.unwrap().page_size as usize;
+        let to_skip = page.unwrap_or(0).saturating_sub(1) as usize * page_size;
+
+        if to_skip >= revisions.len() {
+            let _ = ctx.reply(msg, "There is no such page.").await;
+            return true;
+        }
+
+        let mut out = format!(
+            "**{}** has {} {}.",
+            entry.display_name,
+            revisions.len(),
+            if revisions.len() > 1 {
+                "revisions"
+            } else {
+                "revision"
+            }
+        );
+
+        for revision in revisions.iter().skip(to_skip).take(page_size) {
+            write!(
+                out,
+                "\n\n**Revision {}** <t:{}:R>{}",
+                revision.rev,
+                revision.edited_at,
+                if let Ok(user) = UserId::new(revision.edited_by).to_user(ctx).await {
+                    format!(" by {}", user.name)
+                } else {
+                    String::new()
+                }
+            )
+            .unwrap();
+
+            for change in revision.changes.iter() {
+                write!(
+                    out,
+                    "\n\\- {}: {} → {}",
+                    change.field,
+                    show(&change.old),
+                    show(&change.new)
+                )
+                .unwrap();
+            }
+        }
+
+        out.push_str(if revisions.len() > to_skip + page_size {
+            "\n*(continued next page)*"
+        } else {
+            "\n*(there are no more results)*"
+        });
+
+        let _ = ctx.reply(msg, out).await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["-everyone".to_string(), "?coorduser".to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.is_empty() => "*(empty)*".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) if values.is_empty() => "*(empty)*".to_string(),
+        Value::Array(values) => values.iter().map(show).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
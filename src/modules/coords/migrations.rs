@@ -0,0 +1,106 @@
+use serde_json::{json, Value};
+
+use crate::Migration;
+
+use super::{
+    collection::{CATEGORIES_NAME, COORDS_NAME, TRASH_NAME},
+    world::DEFAULT_WORLD,
+};
+
+// append new steps with the next version, released steps must never change
+pub fn all() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "add empty tags to entries from before tags existed",
+            collection: COORDS_NAME,
+            migrate: add_tags,
+        },
+        Migration {
+            version: 2,
+            description: "add [x, z] positions for the geo index",
+            collection: COORDS_NAME,
+            migrate: add_pos,
+        },
+        Migration {
+            version: 3,
+            description: "add [x, z] positions to trashed entries",
+            collection: TRASH_NAME,
+            migrate: add_trashed_pos,
+        },
+        Migration {
+            version: 4,
+            description: "put entries in the default world",
+            collection: COORDS_NAME,
+            migrate: add_world,
+        },
+        Migration {
+            version: 5,
+            description: "put categories in the default world",
+            collection: CATEGORIES_NAME,
+            migrate: add_world,
+        },
+        Migration {
+            version: 6,
+            description: "put trashed entries and categories in the default world",
+            collection: TRASH_NAME,
+            migrate: add_trashed_world,
+        },
+    ]
+}
+
+fn add_tags(doc: &Value) -> Option<Value> {
+    if doc.get("tags").is_some() {
+        return None;
+    }
+
+    let mut doc = doc.clone();
+    doc.as_object_mut()?.insert("tags".to_string(), json!([]));
+    Some(doc)
+}
+
+fn add_pos(doc: &Value) -> Option<Value> {
+    let pos = json!([doc.get("x")?, doc.get("z")?]);
+    if doc.get("pos") == Some(&pos) {
+        return None;
+    }
+
+    let mut doc = doc.clone();
+    doc.as_object_mut()?.insert("pos".to_string(), pos);
+    Some(doc)
+}
+
+// only coords have a position, categories are left as they are
+fn add_trashed_pos(doc: &Value) -> Option<Value> {
+    if doc.get("kind")?.as_str()? != "coord" {
+        return None;
+    }
+
+    let item = add_pos(doc.get("item")?)?;
+    let mut doc = doc.clone();
+    doc.as_object_mut()?.insert("item".to_string(), item);
+    Some(doc)
+}
+
+fn add_world(doc: &Value) -> Option<Value> {
+    if doc.get("world").is_some() {
+        return None;
+    }
+
+    let mut doc = doc.clone();
+    doc.as_object_mut()?
+        .insert("world".to_string(), json!(DEFAULT_WORLD));
+    Some(doc)
+}
+
+// subcategories belong to the world of their category
+fn add_trashed_world(doc: &Value) -> Option<Value> {
+    if !matches!(doc.get("kind")?.as_str()?, "coord" | "category") {
+        return None;
+    }
+
+    let item = add_world(doc.get("item")?)?;
+    let mut doc = doc.clone();
+    doc.as_object_mut()?.insert("item".to_string(), item);
+    Some(doc)
+}
@@ -1,9 +1,16 @@
+mod archive;
+mod backupfile;
 mod category;
 mod collection;
 mod coord;
+mod deleted;
+mod migrations;
 mod module;
+mod revision;
+mod world;
 
 mod attach;
+mod backup;
 mod cog;
 mod cogadd;
 mod cogedit;
@@ -14,5 +21,12 @@ mod coordadd;
 mod coordedit;
 mod coordrm;
 mod find;
+mod history;
+mod restore;
+mod revert;
+mod trash;
+mod worlds;
 
+pub use archive::CoordsArchive;
+pub use backupfile::{BackupManifest, CoordsBackup};
 pub use module::ModCoords;
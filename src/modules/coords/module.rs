@@ -5,21 +5,38 @@ use std::{
 
 use serenity::async_trait;
 
-use crate::{Command, Module, Mongo};
+use crate::{
+    Collection, Command, ConfigHandle, Filter, Index, LiveHandle, Migration, Module, Sort,
+    StoreError,
+};
 
 use super::{
     attach::CmdAttach,
+    backup::CmdBackup,
+    category::Category,
     cog::CmdCog,
     cogadd::CmdCogAdd,
     cogedit::CmdCogEdit,
     cogperms::CmdCogPerms,
     cogrm::CmdCogRm,
-    collection::{CATEGORIES, COORDS},
+    collection::{
+        CATEGORIES, CATEGORIES_NAME, CATEGORY_INDEXES, COORDS, COORDS_NAME, COORD_INDEXES,
+        DROPPED_INDEXES, REVISIONS, REVISIONS_NAME, REVISION_INDEXES, TRASH, TRASH_INDEXES,
+        TRASH_NAME,
+    },
     config::CoordsConfig,
     coordadd::CmdCoordAdd,
     coordedit::CmdCoordEdit,
     coordrm::CmdCoordRm,
+    deleted::TrashEntry,
     find::CmdFind,
+    history::CmdHistory,
+    migrations,
+    restore::CmdRestore,
+    revert::CmdRevert,
+    trash::CmdTrash,
+    world::DEFAULT_WORLD,
+    worlds::CmdWorlds,
 };
 
 pub struct ModCoords(Arc<HashMap<String, Box<dyn Command>>>);
@@ -84,6 +101,36 @@ impl ModCoords {
             map.insert(cmd.name().to_string(), cmd);
         }
 
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdTrash);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdRestore);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdHistory);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdRevert);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdBackup);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdWorlds);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
         Self(Arc::new(map))
     }
 }
@@ -104,17 +151,78 @@ impl Module for ModCoords {
 
     async fn setup(&mut self) {
         CoordsConfig::setup();
-        let _ = unsafe { CATEGORIES.set(Mongo::database().collection("coords-cogs")) };
-        let _ = unsafe { COORDS.set(Mongo::database().collection("coords-coords")) };
+        setup_collections();
+        TrashEntry::spawn_purge();
     }
 
     async fn reload(&mut self) {
         CoordsConfig::reload();
         unsafe { CATEGORIES = OnceLock::new() };
         unsafe { COORDS = OnceLock::new() };
+        unsafe { TRASH = OnceLock::new() };
+        unsafe { REVISIONS = OnceLock::new() };
 
-        let _ = unsafe { CATEGORIES.set(Mongo::database().collection("coords-cogs")) };
-        let _ = unsafe { COORDS.set(Mongo::database().collection("coords-coords")) };
+        setup_collections();
+    }
+
+    fn configs(&self) -> Vec<Box<dyn ConfigHandle>> {
+        vec![LiveHandle::<CoordsConfig>::boxed()]
+    }
+
+    fn needs_storage(&self) -> bool {
+        true
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        migrations::all()
+    }
+
+    fn indexes(&self) -> Vec<(&'static str, &'static [Index])> {
+        vec![
+            (CATEGORIES_NAME, CATEGORY_INDEXES),
+            (COORDS_NAME, COORD_INDEXES),
+            (TRASH_NAME, TRASH_INDEXES),
+            (REVISIONS_NAME, REVISION_INDEXES),
+        ]
+    }
+
+    fn dropped_indexes(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        vec![
+            (CATEGORIES_NAME, DROPPED_INDEXES),
+            (COORDS_NAME, DROPPED_INDEXES),
+        ]
+    }
+
+    // also read by perms check, which does not set up the module
+    async fn permission_rows(&self) -> Result<Vec<(String, Vec<Vec<String>>)>, StoreError> {
+        let mut rows = Vec::new();
+
+        let cogs = Collection::<Category>::new(CATEGORIES_NAME)
+            .find_sorted(&Filter::new(), Sort::Ascending("name"))
+            .await?;
+
+        for cog in cogs {
+            // names are only unique in their world
+            let name = if cog.world == DEFAULT_WORLD {
+                cog.name.clone()
+            } else {
+                format!("{}:{}", cog.world, cog.name)
+            };
+
+            rows.push((name.clone(), vec![cog.allowed.clone()]));
+
+            let mut subcogs = cog.subcategories.values().collect::<Vec<_>>();
+            subcogs.sort_by_key(|subcog| &subcog.name);
+
+            for subcog in subcogs {
+                rows.push((
+                    format!("{name}.{}", subcog.name),
+                    vec![cog.allowed.clone(), subcog.allowed.clone()],
+                ));
+            }
+        }
+
+        Ok(rows)
     }
 
     fn aliases(&self) -> &[(&str, &str)] {
@@ -129,6 +237,18 @@ impl Module for ModCoords {
             ("coordrm", "coords coordrm"),
             ("cogrm", "coords cogrm"),
             ("attach", "coords attach"),
+            ("trash", "coords trash"),
+            ("restore", "coords restore"),
+            ("coordhistory", "coords history"),
+            ("coordrevert", "coords revert"),
+            ("worlds", "coords worlds"),
         ]
     }
 }
+
+fn setup_collections() {
+    let _ = unsafe { CATEGORIES.set(Collection::new(CATEGORIES_NAME)) };
+    let _ = unsafe { COORDS.set(Collection::new(COORDS_NAME)) };
+    let _ = unsafe { TRASH.set(Collection::new(TRASH_NAME)) };
+    let _ = unsafe { REVISIONS.set(Collection::new(REVISIONS_NAME)) };
+}
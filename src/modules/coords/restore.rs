@@ -0,0 +1,117 @@
+use serenity::{all::Message, async_trait};
+use tracing::info;
+
+use crate::{sys::Command, CollectionItem, Context, MasterSwitch, PerCommandConfig};
+
+use super::{backupfile::CoordsBackup, collection::TRASH, deleted::TrashEntry};
+
+pub struct CmdRestore;
+
+#[async_trait]
+impl Command for CmdRestore {
+    fn name(&self) -> &str {
+        "restore"
+    }
+
+    fn description(&self) -> &str {
+        "Restore a removed coord DB entry or category from the trash, or the whole DB from a backup."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["[trash id]", "backup [file name]"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let id = match args {
+            [id] => match id.parse::<i64>() {
+                Ok(id) => id,
+                Err(_) => return false,
+            },
+            ["backup", name] => return restore_backup(name, ctx, msg).await,
+            _ => return false,
+        };
+
+        let entry = match TrashEntry::find_by_id(id, unsafe { TRASH.get() }.unwrap()).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "No such entry in the trash.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        match entry.is_allowed(ctx, msg).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = ctx.reply(msg, "No such entry in the trash.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        }
+
+        match entry.restore().await {
+            Ok(Ok(())) => {
+                info!(id, kind = entry.kind(), name = entry.name(), user = %msg.author.id, "trash entry restored");
+                let _ = ctx
+                    .reply(
+                        msg,
+                        format!("Restored {} **{}**.", entry.kind(), entry.name()),
+                    )
+                    .await;
+            }
+            Ok(Err(e)) => {
+                let _ = ctx.reply(msg, e).await;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+            }
+        }
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["-everyone".to_string(), "?coorduser".to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+// the whole DB is replaced, so this takes the same permission as making a backup
+async fn restore_backup(name: &str, ctx: &Context, msg: &Message) -> bool {
+    if !MasterSwitch::is_allowed("coords", Some("backup"), ctx, msg).await {
+        let _ = ctx
+            .reply(msg, "You don't have permission to restore a backup.")
+            .await;
+        return true;
+    }
+
+    // only archives in the backup directory
+    if std::path::Path::new(name).file_name() != Some(name.as_ref()) {
+        let _ = ctx.reply(msg, "Expected the file name of a backup.").await;
+        return true;
+    }
+
+    match CoordsBackup::restore(&CoordsBackup::dir().join(name)).await {
+        Ok(manifest) => {
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!("Restored {} from `{name}`.", manifest.summary()),
+                )
+                .await;
+        }
+        Err(e) => {
+            let _ = ctx.reply(msg, format!("Restore failed: {e}")).await;
+        }
+    }
+
+    true
+}
@@ -0,0 +1,185 @@
+use serenity::{all::Message, async_trait};
+use tokio::fs;
+use tracing::info;
+
+use crate::{sys::Command, Context, PerCommandConfig, StoreError, Transaction};
+
+use super::{
+    category::Category, collection::COORDS, coord::Coord, revision::Revision, world::take_world,
+};
+
+pub struct CmdRevert;
+
+#[async_trait]
+impl Command for CmdRevert {
+    fn name(&self) -> &str {
+        "revert"
+    }
+
+    fn description(&self) -> &str {
+        "Undo a revision of a coord DB entry and every revision after it."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["[name|id] [revision] (world=name)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (world, args) = match take_world(args, ctx, msg).await {
+            Some(res) => res,
+            None => return true,
+        };
+
+        let (entry, rev) = match args.as_slice() {
+            [entry, rev] => match rev.parse::<i64>() {
+                Ok(rev) => (entry, rev),
+                Err(_) => return false,
+            },
+            _ => return false,
+        };
+
+        let entry = match Coord::find_by_name_or_id(&world, entry).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "No maching results found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        match entry.is_allowed(ctx, msg, &mut Default::default()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = ctx.reply(msg, "No maching results found.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        }
+
+        let target = match Revision::before(&entry, rev).await {
+            Ok(Some(target)) => target,
+            Ok(None) => {
+                let _ = ctx.reply(msg, "No such revision.").await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply(msg, format!("Revert failed because {e}.")).await;
+                return true;
+            }
+        };
+
+        // the old category must still exist and be writable, like a newcog= edit
+        let moved = (target.cog, target.subcog) != (entry.cog, entry.subcog);
+        let allowed = if moved {
+            match target.is_allowed(ctx, msg, &mut Default::default()).await {
+                Ok(allowed) => allowed,
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
+        } else {
+            true
+        };
+        if !allowed {
+            let _ = ctx
+                .reply(
+                    msg,
+                    "Revert failed because you don't have permission to write to the previous category.",
+                )
+                .await;
+            return true;
+        }
+
+        let (from, to) = match (
+            Category::path(entry.cog, entry.subcog).await,
+            Category::path(target.cog, target.subcog).await,
+        ) {
+            (Ok(from), Ok(to)) => (
+                from.join(entry.id.to_string()),
+                to.join(target.id.to_string()),
+            ),
+            (Err(e), _) | (_, Err(e)) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+        let move_attachments =
+            moved && from != to && fs::try_exists(&from).await.unwrap_or_default();
+
+        if move_attachments && (target.cog, target.subcog) == (0, 1) {
+            let _ = ctx.reply(msg, "Revert failed because cannot move entry into generic.private when it contains attachments.").await;
+            return true;
+        }
+
+        let mut tx = Transaction::new();
+        if move_attachments {
+            tx.rename(from, to);
+        }
+        // the revert is a revision of its own, so it can be undone as well
+        let recorded = match tx.replace(unsafe { COORDS.get() }.unwrap(), target.id, &target) {
+            Ok(_) => Revision::record(&entry, &target, msg, &mut tx).await,
+            Err(e) => Err(e),
+        };
+        let revision = match recorded {
+            Ok(revision) => revision,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        match tx.commit().await {
+            Ok(()) => {}
+            Err(e) if Revision::is_conflict(&e) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Revert failed because the entry was edited at the same time, try again.",
+                    )
+                    .await;
+                return true;
+            }
+            Err(StoreError::Duplicate(_)) => {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "Revert failed because a coord entry with that name already exists.",
+                    )
+                    .await;
+                return true;
+            }
+            Err(e) => {
+                let _ = ctx.reply(msg, format!("Revert failed because {e}.")).await;
+                return true;
+            }
+        }
+
+        info!(id = entry.id, name = target.name, reverted = rev, rev = revision.map(|revision| revision.rev), user = %msg.author.id, "coord reverted");
+
+        let _ = ctx
+            .reply(
+                msg,
+                format!(
+                    "**{}** reverted to before revision {rev}.",
+                    target.display_name
+                ),
+            )
+            .await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["-everyone".to_string(), "?coorduser".to_string()],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::all::Message;
+
+use crate::{CollectionItem, Counter, Filter, StoreError, Transaction};
+
+use super::{
+    collection::{REVISIONS, REVISIONS_NAME},
+    coord::Coord,
+};
+
+// fields that are set when an entry is added and never edited, or follow other fields
+const IGNORED: &[&str] = &["_id", "author_id", "added", "pos"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Change {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+// a single edit of a coord entry, numbered from 1 for each entry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Revision {
+    #[serde(rename = "_id")]
+    pub id: i64,
+    pub coord: i64,
+    pub rev: i64,
+    pub changes: Vec<Change>,
+    pub edited_by: u64,
+    pub edited_at: i64,
+}
+
+impl CollectionItem<i64> for Revision {
+    fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+impl Revision {
+    // edits of the same entry at once number their revisions after the same last one,
+    // the unique (coord, rev) index turns away whichever is committed second
+    pub fn is_conflict(e: &StoreError) -> bool {
+        matches!(e, StoreError::Duplicate(index) if index == "rev")
+    }
+
+    pub fn diff(before: &Coord, after: &Coord) -> Vec<Change> {
+        let before = serde_json::to_value(before).unwrap();
+        let after = serde_json::to_value(after).unwrap();
+
+        after
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(field, _)| !IGNORED.contains(&field.as_str()))
+            .filter_map(|(field, new)| {
+                let old = before.get(field).cloned().unwrap_or_default();
+                (old != *new).then(|| Change {
+                    field: field.clone(),
+                    old,
+                    new: new.clone(),
+                })
+            })
+            .collect()
+    }
+
+    // added to the transaction of the edit, nothing is written if the entry did not change
+    pub async fn record(
+        before: &Coord,
+        after: &Coord,
+        msg: &Message,
+        tx: &mut Transaction,
+    ) -> Result<Option<Self>, StoreError> {
+        let changes = Self::diff(before, after);
+
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let last = Self::of(after.id)
+            .await?
+            .first()
+            .map(|revision| revision.rev)
+            .unwrap_or_default();
+
+        let revision = Self {
+            id: Counter::bump_get(REVISIONS_NAME).await?,
+            coord: after.id,
+            rev: last + 1,
+            changes,
+            edited_by: msg.author.id.get(),
+            edited_at: chrono::Utc::now().timestamp(),
+        };
+
+        tx.insert(unsafe { REVISIONS.get() }.unwrap(), revision.id, &revision)?;
+        Ok(Some(revision))
+    }
+
+    // newest first
+    pub async fn of(coord: i64) -> Result<Vec<Self>, StoreError> {
+        let mut revisions = unsafe { REVISIONS.get() }
+            .unwrap()
+            .find(Filter::new().eq("coord", coord))
+            .await?;
+        revisions.sort_by_key(|rev| -rev.rev);
+        Ok(revisions)
+    }
+
+    // the entry as it was before the revision, by undoing it and every later revision
+    pub async fn before(coord: &Coord, rev: i64) -> Result<Option<Coord>, StoreError> {
+        let revisions = Self::of(coord.id).await?;
+
+        if !revisions.iter().any(|revision| revision.rev == rev) {
+            return Ok(None);
+        }
+
+        let mut doc = serde_json::to_value(coord).unwrap();
+
+        for revision in revisions.iter().take_while(|revision| revision.rev >= rev) {
+            for change in revision.changes.iter() {
+                doc[change.field.as_str()] = change.old.clone();
+            }
+        }
+
+        let mut coord: Coord =
+            serde_json::from_value(doc).map_err(|e| StoreError::Other(e.to_string()))?;
+        coord.pos = [coord.x, coord.z];
+        Ok(Some(coord))
+    }
+
+    pub async fn delete_all(coord: i64) -> Result<(), StoreError> {
+        let revisions = unsafe { REVISIONS.get() }.unwrap();
+
+        for revision in Self::of(coord).await? {
+            revision.delete(revisions).await?;
+        }
+
+        Ok(())
+    }
+}
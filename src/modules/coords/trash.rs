@@ -0,0 +1,138 @@
+use std::fmt::Write;
+
+use serenity::{
+    all::{Message, UserId},
+    async_trait,
+};
+
+use crate::{sys::Command, Context, Filter, PerCommandConfig, Sort};
+
+use super::{collection::TRASH, config::COORDS_CONFIG};
+
+pub struct CmdTrash;
+
+#[async_trait]
+impl Command for CmdTrash {
+    fn name(&self) -> &str {
+        "trash"
+    }
+
+    fn description(&self) -> &str {
+        "List removed coord DB entries and categories."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["(page=value|kind=coord/category/subcategory)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let mut filter = Filter::new();
+        let mut page: Option<u32> = None;
+
+        for arg in args.iter() {
+            match arg.split_once('=') {
+                Some(("page", right)) => {
+                    if let Ok(parsed) = right.parse() {
+                        page = Some(parsed);
+                    } else {
+                        let _ = ctx.reply(msg, "Could not parse page number.").await;
+                        return true;
+                    }
+                }
+                Some(("kind", right)) if matches!(right, "coord" | "category" | "subcategory") => {
+                    filter.eq("kind", right);
+                }
+                _ => return false,
+            }
+        }
+
+        let found = match unsafe { TRASH.get() }
+            .unwrap()
+            .find_sorted(&filter, Sort::Reverse)
+            .await
+        {
+            Ok(found) => found,
+            Err(e) => {
+                let _ = ctx.reply_store_error(msg, &e).await;
+                return true;
+            }
+        };
+
+        let mut entries = Vec::new();
+
+        for entry in found {
+            match entry.is_allowed(ctx, msg).await {
+                Ok(true) => entries.push(entry),
+                Ok(false) => {}
+                Err(e) => {
+                    let _ = ctx.reply_store_error(msg, &e).await;
+                    return true;
+                }
+            }
+        }
+
+        let config = unsafe { COORDS_CONFIG.get() }.unwrap();
+        let page_size = config.page_size as usize;
+        let to_skip = page.unwrap_or(0).saturating_sub(1) as usize * page_size;
+
+        if entries.is_empty() {
+            let _ = ctx.reply(msg, "The trash is empty.").await;
+            return true;
+        }
+
+        if to_skip >= entries.len() {
+            let _ = ctx.reply(msg, "There is no such page.").await;
+            return true;
+        }
+
+        let has_next_page = entries.len() > to_skip + page_size;
+        let mut out = format!(
+            "Showing {} of {} trashed entries.",
+            entries.len().min(to_skip + page_size) - to_skip,
+            entries.len()
+        );
+
+        for entry in entries.iter().skip(to_skip).take(page_size) {
+            write!(
+                out,
+                "\n{}. {} **{}** removed <t:{}:R>{}",
+                entry.id,
+                entry.kind(),
+                entry.name(),
+                entry.deleted_at,
+                if let Ok(user) = UserId::new(entry.deleted_by).to_user(ctx).await {
+                    format!(" by {}", user.name)
+                } else {
+                    String::new()
+                }
+            )
+            .unwrap();
+        }
+
+        out.push_str(if has_next_page {
+            "\n*(continued next page)*"
+        } else {
+            "\n*(there are no more results)*"
+        });
+
+        if config.trash_retention_days != 0 {
+            write!(
+                out,
+                "\nEntries are purged after {} days.",
+                config.trash_retention_days
+            )
+            .unwrap();
+        }
+
+        let _ = ctx.reply(msg, out).await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["-everyone".to_string(), "?coorduser".to_string()],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serenity::all::Message;
+
+use crate::Context;
+
+use super::config::COORDS_CONFIG;
+
+// entries and categories from before worlds existed belong to this one
+pub const DEFAULT_WORLD: &str = "default";
+
+pub fn default_world() -> String {
+    DEFAULT_WORLD.to_string()
+}
+
+// same rules as entry and category names, the keywords of `worlds` cannot be used
+pub fn world_name(display_name: &str) -> Result<String, &'static str> {
+    let name = display_name.replace(' ', "-").to_lowercase();
+
+    if name.is_empty() || name.chars().any(|c| !c.is_alphanumeric() && c != '-') {
+        return Err("contains illegal characters");
+    }
+
+    if matches!(name.as_str(), "clear" | "guild") {
+        return Err("is reserved");
+    }
+
+    Ok(name)
+}
+
+// the world of the channel, then of the server, then the configured default
+pub fn world_of(msg: &Message) -> String {
+    let config = unsafe { COORDS_CONFIG.get() }.unwrap();
+
+    config
+        .channel_worlds
+        .get(&msg.channel_id.to_string())
+        .or_else(|| {
+            msg.guild_id
+                .and_then(|guild| config.guild_worlds.get(&guild.to_string()))
+        })
+        .unwrap_or(&config.default_world)
+        .clone()
+}
+
+// takes world= out of the arguments, None after replying if the name is not valid
+pub async fn take_world<'a>(
+    args: &[&'a str],
+    ctx: &Context,
+    msg: &Message,
+) -> Option<(String, Vec<&'a str>)> {
+    let mut world = None;
+    let mut rest = Vec::with_capacity(args.len());
+
+    for arg in args {
+        match arg.strip_prefix("world=") {
+            Some(name) => world = Some(name),
+            None => rest.push(*arg),
+        }
+    }
+
+    match world {
+        Some(name) => match world_name(name) {
+            Ok(world) => Some((world, rest)),
+            Err(e) => {
+                let _ = ctx.reply(msg, format!("World name {e}.")).await;
+                None
+            }
+        },
+        None => Some((world_of(msg), rest)),
+    }
+}
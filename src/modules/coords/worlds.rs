@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use serenity::{all::Message, async_trait};
+use tracing::info;
+
+use crate::{sys::Command, Context, PerCommandConfig};
+
+use super::{
+    collection::{CATEGORIES, COORDS},
+    config::{CoordsConfig, COORDS_CONFIG},
+    world::{world_name, world_of},
+};
+
+pub struct CmdWorlds;
+
+#[async_trait]
+impl Command for CmdWorlds {
+    fn name(&self) -> &str {
+        "worlds"
+    }
+
+    fn description(&self) -> &str {
+        "List coords worlds and set the default world of a channel or server."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["", "[world]", "clear", "guild [world]", "guild clear"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (guild, world) = match args {
+            [] => {
+                list(ctx, msg).await;
+                return true;
+            }
+            ["guild", world] => (true, *world),
+            [world] => (false, *world),
+            _ => return false,
+        };
+
+        let key = if guild {
+            if let Some(guild) = msg.guild_id {
+                guild.to_string()
+            } else {
+                let _ = ctx
+                    .reply(msg, "Server worlds can only be set in a server.")
+                    .await;
+                return true;
+            }
+        } else {
+            msg.channel_id.to_string()
+        };
+
+        // no world can be named clear
+        let world = if world == "clear" {
+            None
+        } else {
+            match world_name(world) {
+                Ok(world) => Some(world),
+                Err(e) => {
+                    let _ = ctx.reply(msg, format!("World name {e}.")).await;
+                    return true;
+                }
+            }
+        };
+
+        let config = unsafe { COORDS_CONFIG.get_mut() }.unwrap();
+        let worlds = if guild {
+            &mut config.guild_worlds
+        } else {
+            &mut config.channel_worlds
+        };
+
+        match &world {
+            Some(world) => worlds.insert(key.clone(), world.clone()),
+            None => worlds.remove(&key),
+        };
+        CoordsConfig::write_to_config();
+
+        info!(key, guild, world = ?world, user = %msg.author.id, "default world changed");
+
+        let place = if guild { "server" } else { "channel" };
+        let _ = ctx
+            .reply(
+                msg,
+                match world {
+                    Some(world) => format!("Entries in this {place} are now in **{world}**."),
+                    None => format!(
+                        "This {place} no longer has its own world, entries here are in **{}**.",
+                        world_of(msg)
+                    ),
+                },
+            )
+            .await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["-everyone".to_string(), "?coordmod".to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+async fn list(ctx: &Context, msg: &Message) {
+    let config = unsafe { COORDS_CONFIG.get() }.unwrap();
+
+    // (entries, categories), worlds that are only configured have neither
+    let mut worlds: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for world in [&config.default_world]
+        .into_iter()
+        .chain(config.channel_worlds.values())
+        .chain(config.guild_worlds.values())
+    {
+        worlds.entry(world.clone()).or_default();
+    }
+
+    let (coords, cogs) = match (
+        unsafe { COORDS.get() }.unwrap().count_by("world").await,
+        unsafe { CATEGORIES.get() }.unwrap().count_by("world").await,
+    ) {
+        (Ok(coords), Ok(cogs)) => (coords, cogs),
+        (Err(e), _) | (_, Err(e)) => {
+            let _ = ctx.reply_store_error(msg, &e).await;
+            return;
+        }
+    };
+
+    for (world, count) in coords {
+        if let Some(world) = world.as_str() {
+            worlds.entry(world.to_string()).or_default().0 += count as usize;
+        }
+    }
+
+    for (world, count) in cogs {
+        if let Some(world) = world.as_str() {
+            worlds.entry(world.to_string()).or_default().1 += count as usize;
+        }
+    }
+
+    let here = world_of(msg);
+
+    let _ = ctx
+        .reply(
+            msg,
+            format!(
+                "**Coords worlds**{}\n\nEntries here are in **{here}**.",
+                worlds
+                    .iter()
+                    .fold(String::new(), |mut current, (world, (coords, cogs))| {
+                        write!(
+                            current,
+                            "\n\\- {world}: {coords} entries, {cogs} categories{}",
+                            if *world == config.default_world {
+                                " (default)"
+                            } else {
+                                ""
+                            }
+                        )
+                        .unwrap();
+                        current
+                    })
+            ),
+        )
+        .await;
+}
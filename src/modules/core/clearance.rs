@@ -1,11 +1,8 @@
 use std::fmt::Write;
 
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, Clearance, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, GuildClearance, MasterSwitch, PerCommandConfig};
 
 pub struct CmdClearance;
 
@@ -20,28 +17,44 @@ impl Command for CmdClearance {
     }
 
     fn usage(&self) -> &[&str] {
-        &["(preset)", "[preset] (rules...)", "[preset] clear"]
+        &[
+            "(preset)",
+            "[preset] (rules...)",
+            "[preset] clear",
+            "guild (preset)",
+            "guild [preset] (rules...)",
+            "guild [preset] clear",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
         match args {
+            ["guild", args @ ..] => return guild_clearance(args, ctx, msg).await,
+            [_, ..]
+                if args.len() > 1
+                    && !MasterSwitch::is_allowed_globally("core", "clearance", ctx, msg).await =>
+            {
+                let _ = ctx.reply(msg, "You can only change clearance presets for this server, use `clearance guild` instead.",
+                    )
+                    .await;
+            }
             [] => {
                 let mut presets = Clearance::list_all();
                 presets.sort();
 
                 if presets.is_empty() {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             "**Clearance presets**\nThere are no clearance presets.",
                         )
                         .await;
                     return true;
                 }
 
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "**Clearance presets**{}",
                             presets.iter().fold(String::new(), |mut current, label| {
@@ -56,16 +69,13 @@ impl Command for CmdClearance {
                 let rules = Clearance::get(preset);
 
                 if rules.is_empty() {
-                    let _ = msg
-                        .reply(
-                            ctx,
-                            format!("**[Clearance preset] {preset}**\nClearance preset *{preset}* has no rules.",),
+                    let _ = ctx.reply(msg, format!("**[Clearance preset] {preset}**\nClearance preset *{preset}* has no rules.",),
                         )
                         .await;
                 } else {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "**[Clearance preset] {preset}**{}",
                                 rules.iter().enumerate().fold(
@@ -82,19 +92,16 @@ impl Command for CmdClearance {
             }
             [preset, "clear"] => {
                 if Clearance::remove(preset) {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
-                                "Clearance preset **{preset}** has been clearned. *(not saved)*",
+                                "Clearance preset **{preset}** has been clearned. *(uncommitted)*",
                             ),
                         )
                         .await;
                 } else {
-                    let _ = msg
-                        .reply(
-                            ctx,
-                            format!("Clearance preset **{preset}** has been clearned, but is was originally empty.",),
+                    let _ = ctx.reply(msg, format!("Clearance preset **{preset}** has been clearned, but is was originally empty.",),
                         )
                         .await;
                 }
@@ -113,17 +120,14 @@ impl Command for CmdClearance {
                     preset.to_string(),
                     &args.iter().map(String::as_str).collect::<Vec<_>>(),
                 ) {
-                    let _ = msg
-                        .reply(
-                            ctx,
-                            format!("Failed to update clearance preset **{preset}** because it contains invalid rules.",),
+                    let _ = ctx.reply(msg, format!("Failed to update clearance preset **{preset}** because it contains invalid rules.",),
                         )
                         .await;
                 } else {
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
-                            format!("Clearance preset **{preset}** updated. *(not saved)*",),
+                            msg,
+                            format!("Clearance preset **{preset}** updated. *(uncommitted)*",),
                         )
                         .await;
                 }
@@ -140,3 +144,133 @@ impl Command for CmdClearance {
         }
     }
 }
+
+async fn guild_clearance(args: &[&str], ctx: &Context, msg: &Message) -> bool {
+    let guild = if let Some(guild) = msg.guild_id {
+        guild
+    } else {
+        let _ = ctx
+            .reply(
+                msg,
+                "Server clearance presets can only be used in a server.",
+            )
+            .await;
+        return true;
+    };
+
+    // clearing stays possible for presets defined before this was refused
+    if let [preset, rest @ ..] = args {
+        if !rest.is_empty() && rest != ["clear"] && MasterSwitch::global_presets().contains(*preset)
+        {
+            let _ = ctx.reply(msg, format!("**{preset}** is used by commands that affect the whole bot and cannot be changed for a server."),
+                )
+                .await;
+            return true;
+        }
+    }
+
+    match args {
+        [] => {
+            let mut presets = GuildClearance::list_all(guild);
+            presets.sort();
+
+            if presets.is_empty() {
+                let _ = ctx.reply(msg, "**Server clearance presets**\nThis server has no clearance presets of its own.",
+                    )
+                    .await;
+                return true;
+            }
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "**Server clearance presets**{}",
+                        presets.iter().fold(String::new(), |mut current, label| {
+                            write!(current, "\n\\- {}", label).unwrap();
+                            current
+                        })
+                    ),
+                )
+                .await;
+        }
+        [preset] => {
+            let rules = match GuildClearance::get(guild, preset) {
+                Some(rules) => rules,
+                None => {
+                    let _ = ctx.reply(msg, format!("**[Server clearance preset] {preset}**\nThis server does not define *{preset}*, the global preset is used instead."),
+                        )
+                        .await;
+                    return true;
+                }
+            };
+
+            if rules.is_empty() {
+                let _ = ctx.reply(msg, format!("**[Server clearance preset] {preset}**\nClearance preset *{preset}* has no rules."),
+                    )
+                    .await;
+                return true;
+            }
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "**[Server clearance preset] {preset}**{}",
+                        rules.iter().enumerate().fold(
+                            String::new(),
+                            |mut current, (index, rule)| {
+                                write!(current, "\n{}\\. {}", index + 1, rule).unwrap();
+                                current
+                            }
+                        )
+                    ),
+                )
+                .await;
+        }
+        [preset, "clear"] => {
+            if GuildClearance::remove(guild, preset) {
+                GuildClearance::write_to_config();
+
+                let _ = ctx.reply(msg, format!("Server clearance preset **{preset}** has been cleared, the global preset is used instead."),
+                    )
+                    .await;
+            } else {
+                let _ = ctx.reply(msg, format!("Server clearance preset **{preset}** has been cleared, but it was not defined for this server."),
+                    )
+                    .await;
+            }
+        }
+        [preset, ..] => {
+            let mut rules = args
+                .iter()
+                .skip(1)
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>();
+            if !Clearance::map_rules(&mut rules, msg, ctx).await {
+                return true;
+            }
+
+            if !GuildClearance::set(
+                guild,
+                preset.to_string(),
+                &rules.iter().map(String::as_str).collect::<Vec<_>>(),
+            ) {
+                let _ = ctx.reply(msg, format!("Failed to update server clearance preset **{preset}** because it contains invalid rules."),
+                    )
+                    .await;
+            } else {
+                GuildClearance::write_to_config();
+
+                let _ = ctx
+                    .reply(
+                        msg,
+                        format!("Server clearance preset **{preset}** updated."),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    true
+}
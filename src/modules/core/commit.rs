@@ -0,0 +1,58 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig, Staging};
+
+pub struct CmdCommit;
+
+#[async_trait]
+impl Command for CmdCommit {
+    fn name(&self) -> &str {
+        "commit"
+    }
+
+    fn description(&self) -> &str {
+        "Write uncommitted config changes to file."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["(message)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let committed = Staging::commit(
+            &format!("{} ({})", msg.author.name, msg.author.id),
+            &args.join(" "),
+        );
+
+        if committed.is_empty() {
+            let _ = ctx.reply(msg, "Nothing to commit.").await;
+        } else {
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "Committed changes to {}.",
+                        committed
+                            .iter()
+                            .map(|name| format!("**{name}**"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+                .await;
+        }
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashMap, fmt::Write, sync::OnceLock};
+
+use serenity::{
+    all::{Message, UserId},
+    async_trait,
+};
+
+use crate::{sys::Command, CommandHandler, Context, PerCommandConfig};
+
+// imports waiting for confirmation, keyed by the user who sent them
+static mut PENDING: OnceLock<HashMap<UserId, (String, String)>> = OnceLock::new();
+
+const MAX_IMPORT_SIZE: u32 = 1024 * 1024;
+
+pub struct CmdConfig;
+
+#[async_trait]
+impl Command for CmdConfig {
+    fn name(&self) -> &str {
+        "config"
+    }
+
+    fn description(&self) -> &str {
+        "Export config files, or import them from an attached jsonc file."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[
+            "",
+            "export [config]",
+            "import [config]",
+            "confirm",
+            "cancel",
+        ]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let _ = unsafe { PENDING.set(HashMap::new()) };
+        let pending = unsafe { PENDING.get_mut() }.unwrap();
+
+        match args {
+            [] => {
+                let mut out = "Available configs:".to_string();
+                for config in CommandHandler::configs() {
+                    write!(out, "\n- `{}`", config.name()).unwrap();
+                }
+
+                let _ = ctx.reply(msg, out).await;
+            }
+            ["export", name] => {
+                let config = match CommandHandler::config(name) {
+                    Some(config) => config,
+                    None => {
+                        let _ = ctx.reply(msg, "No such config.").await;
+                        return true;
+                    }
+                };
+
+                let _ = ctx
+                    .reply_file(
+                        msg,
+                        format!("Current value of **{name}**."),
+                        config.export().into_bytes(),
+                        format!("{name}.jsonc"),
+                    )
+                    .await;
+            }
+            ["import", name] => {
+                let config = match CommandHandler::config(name) {
+                    Some(config) => config,
+                    None => {
+                        let _ = ctx.reply(msg, "No such config.").await;
+                        return true;
+                    }
+                };
+
+                let attachment = match msg.attachments.as_slice() {
+                    [attachment] => attachment,
+                    _ => {
+                        let _ = ctx
+                            .reply(msg, "Attach exactly one jsonc file to import.")
+                            .await;
+                        return true;
+                    }
+                };
+
+                if attachment.size > MAX_IMPORT_SIZE {
+                    let _ = ctx.reply(msg, "Attachment is too large.").await;
+                    return true;
+                }
+
+                let content = match attachment.download().await.map(String::from_utf8) {
+                    Ok(Ok(content)) => content,
+                    _ => {
+                        let _ = ctx.reply(msg, "Could not read attachment.").await;
+                        return true;
+                    }
+                };
+
+                let diff = match config.check(&content) {
+                    Ok(Some(diff)) => diff,
+                    Ok(None) => {
+                        let _ = ctx
+                            .reply(
+                                msg,
+                                format!("Import is the same as the current **{name}**."),
+                            )
+                            .await;
+                        return true;
+                    }
+                    Err(e) => {
+                        let _ = ctx
+                            .reply(msg, format!("Invalid **{name}** config: {e}"))
+                            .await;
+                        return true;
+                    }
+                };
+
+                pending.insert(msg.author.id, (name.to_string(), content));
+
+                let content = format!(
+                    "Use `core config confirm` to apply the changes to **{name}**, or `core config cancel`."
+                );
+
+                // discord messages are limited to 2000 characters
+                if content.len() + diff.len() < 1900 {
+                    let _ = ctx
+                        .reply(msg, format!("```diff\n{diff}```\n{content}"))
+                        .await;
+                } else {
+                    let _ = ctx
+                        .reply_file(msg, content, diff.into_bytes(), format!("{name}.diff"))
+                        .await;
+                }
+            }
+            ["confirm"] => {
+                let (name, content) = match pending.remove(&msg.author.id) {
+                    Some(import) => import,
+                    None => {
+                        let _ = ctx.reply(msg, "No pending import.").await;
+                        return true;
+                    }
+                };
+
+                let config = match CommandHandler::config(&name) {
+                    Some(config) => config,
+                    None => {
+                        let _ = ctx.reply(msg, "No such config.").await;
+                        return true;
+                    }
+                };
+
+                // the config may have changed since the import was checked
+                match config.apply(&content) {
+                    Ok(()) if config.staged() => {
+                        let _ = ctx
+                            .reply(msg, format!("**{name}** imported. *(uncommitted)*"))
+                            .await;
+                    }
+                    Ok(()) => {
+                        let _ = ctx.reply(msg, format!("**{name}** imported.")).await;
+                    }
+                    Err(e) => {
+                        let _ = ctx
+                            .reply(msg, format!("Invalid **{name}** config: {e}"))
+                            .await;
+                    }
+                }
+            }
+            ["cancel"] => {
+                if pending.remove(&msg.author.id).is_some() {
+                    let _ = ctx.reply(msg, "Import cancelled.").await;
+                } else {
+                    let _ = ctx.reply(msg, "No pending import.").await;
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig, Staging};
+
+pub struct CmdDiff;
+
+#[async_trait]
+impl Command for CmdDiff {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    fn description(&self) -> &str {
+        "Show uncommitted config changes."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        if !args.is_empty() {
+            return false;
+        }
+
+        let diffs = Staging::diff();
+
+        if diffs.is_empty() {
+            let _ = ctx.reply(msg, "No uncommitted changes.").await;
+            return true;
+        }
+
+        let mut out = String::new();
+        for (_, diff) in diffs.iter() {
+            out.push_str(diff);
+        }
+
+        let mut content = format!(
+            "Uncommitted changes to {}.",
+            diffs
+                .iter()
+                .map(|(name, _)| format!("**{name}**"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        // discord messages are limited to 2000 characters
+        if content.len() + out.len() < 1900 {
+            write!(content, "\n```diff\n{out}```").unwrap();
+            let _ = ctx.reply(msg, content).await;
+        } else {
+            let _ = ctx
+                .reply_file(msg, content, out.into_bytes(), "changes.diff")
+                .await;
+        }
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,64 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig, Staging};
+
+use super::reload::reload;
+
+pub struct CmdDiscard;
+
+#[async_trait]
+impl Command for CmdDiscard {
+    fn name(&self) -> &str {
+        "discard"
+    }
+
+    fn description(&self) -> &str {
+        "Throw away uncommitted config changes."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        if !args.is_empty() {
+            return false;
+        }
+
+        let staged = Staging::staged();
+
+        if staged.is_empty() {
+            let _ = ctx.reply(msg, "No uncommitted changes.").await;
+            return true;
+        }
+
+        reload().await;
+
+        let _ = ctx
+            .reply(
+                msg,
+                format!(
+                    "Discarded changes to {}.",
+                    staged
+                        .iter()
+                        .map(|name| format!("**{name}**"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+            .await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
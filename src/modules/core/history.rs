@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, History, PerCommandConfig};
+
+pub struct CmdHistory;
+
+#[async_trait]
+impl Command for CmdHistory {
+    fn name(&self) -> &str {
+        "history"
+    }
+
+    fn description(&self) -> &str {
+        "List saved snapshots of a config file."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["[config]"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let name = match args {
+            [name] => *name,
+            _ => return false,
+        };
+
+        let snapshots = History::list(name);
+
+        if snapshots.is_empty() {
+            let _ = ctx
+                .reply(msg, format!("No snapshots found for **{name}**."))
+                .await;
+            return true;
+        }
+
+        let mut out = format!("Snapshots of **{name}**, newest first:");
+        for (i, (millis, _)) in snapshots.iter().enumerate() {
+            write!(out, "\n{}. <t:{}:f>", i + 1, millis / 1000).unwrap();
+        }
+        write!(out, "\nUse `core rollback {name} [n]` to restore one.").unwrap();
+
+        let _ = ctx.reply(msg, out).await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,172 @@
+use std::{fmt::Write, sync::Arc, time::Duration};
+
+use serenity::{gateway::ConnectionStage, prelude::TypeMap};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::RwLock,
+    time::timeout,
+};
+
+use tracing::{debug, error, info};
+
+use crate::Metrics;
+
+use super::keys::{ShardManagerContainer, StartInstanceContainer};
+
+// minimal http server for /healthz and /metrics, anything else is a 404
+pub async fn serve(address: String, data: Arc<RwLock<TypeMap>>) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(%address, error = %e, "could not bind metrics server");
+            return;
+        }
+    };
+
+    info!(%address, "metrics server started");
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(_) => continue,
+        };
+
+        tokio::spawn(handle(stream, data.clone()));
+    }
+}
+
+async fn handle(mut stream: TcpStream, data: Arc<RwLock<TypeMap>>) {
+    let mut buf = [0; 1024];
+    let len = match timeout(Duration::from_secs(5), stream.read(&mut buf)).await {
+        Ok(Ok(len)) => len,
+        _ => return,
+    };
+
+    let request = String::from_utf8_lossy(&buf[..len]);
+    debug!(
+        request = request.lines().next().unwrap_or_default(),
+        "metrics request"
+    );
+    let path = match request
+        .lines()
+        .next()
+        .map(|line| line.split(' ').collect::<Vec<_>>())
+    {
+        Some(line) if line.len() == 3 && line[0] == "GET" => line[1].to_string(),
+        _ => {
+            respond(&mut stream, "405 Method Not Allowed", "text/plain", "").await;
+            return;
+        }
+    };
+
+    match path.as_str() {
+        "/healthz" => {
+            let (healthy, body) = health(&data).await;
+            respond(
+                &mut stream,
+                if healthy {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                },
+                "application/json",
+                &body,
+            )
+            .await;
+        }
+        "/metrics" => {
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/plain; version=0.0.4",
+                &metrics(&data).await,
+            )
+            .await
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "").await,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let _ = stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await;
+    let _ = stream.shutdown().await;
+}
+
+async fn health(data: &Arc<RwLock<TypeMap>>) -> (bool, String) {
+    let gateway = match data.read().await.get::<ShardManagerContainer>() {
+        Some(shard_manager) => {
+            let runners = shard_manager.runners.lock().await;
+            !runners.is_empty()
+                && runners
+                    .values()
+                    .all(|runner| runner.stage == ConnectionStage::Connected)
+        }
+        None => false,
+    };
+
+    let storage = match crate::Storage::try_get() {
+        Some(store) => timeout(Duration::from_secs(2), store.ping())
+            .await
+            .is_ok_and(|info| info.is_some()),
+        None => true,
+    };
+
+    (
+        gateway && storage,
+        format!("{{\"gateway\":{gateway},\"storage\":{storage}}}"),
+    )
+}
+
+async fn metrics(data: &Arc<RwLock<TypeMap>>) -> String {
+    let mut out = String::new();
+    let data = data.read().await;
+
+    if let Some(start) = data.get::<StartInstanceContainer>() {
+        out.push_str("# HELP merlin_uptime_seconds Time since the bot started.\n");
+        out.push_str("# TYPE merlin_uptime_seconds gauge\n");
+        writeln!(
+            out,
+            "merlin_uptime_seconds {}",
+            start.get().elapsed().as_secs()
+        )
+        .unwrap();
+    }
+
+    if let Some(shard_manager) = data.get::<ShardManagerContainer>() {
+        out.push_str("# HELP merlin_shard_latency_seconds Heartbeat latency of each shard.\n");
+        out.push_str("# TYPE merlin_shard_latency_seconds gauge\n");
+        for (id, runner) in shard_manager.runners.lock().await.iter() {
+            if let Some(latency) = runner.latency {
+                writeln!(
+                    out,
+                    "merlin_shard_latency_seconds{{shard=\"{id}\"}} {}",
+                    latency.as_secs_f64()
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if crate::Storage::try_get().is_some() {
+        out.push_str("# HELP merlin_storage_up Whether the storage backend could be reached.\n");
+        out.push_str("# TYPE merlin_storage_up gauge\n");
+        writeln!(
+            out,
+            "merlin_storage_up {}",
+            crate::Storage::is_healthy() as u8
+        )
+        .unwrap();
+    }
+
+    Metrics::render(&mut out);
+    out
+}
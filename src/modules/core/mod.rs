@@ -1,11 +1,22 @@
+mod http;
 mod keys;
 mod module;
 
 mod clearance;
+mod commit;
+mod config;
+mod diff;
+mod discard;
+mod history;
+mod permmatrix;
 mod perms;
 mod ping;
 mod reload;
+mod restart;
+mod rollback;
 mod save;
+mod shutdown;
+mod status;
 mod switch;
 mod uptime;
 mod version;
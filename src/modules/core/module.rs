@@ -2,15 +2,29 @@ use std::{collections::HashMap, sync::Arc};
 
 use serenity::async_trait;
 
-use crate::{Command, CommandHandler, Module};
+use crate::{
+    Clearance, Command, CommandHandler, ConfigHandle, GuildClearance, GuildSwitch, LiveHandle,
+    MasterOptions, MasterSwitch, Module, MASTER,
+};
 
 use super::{
     clearance::CmdClearance,
+    commit::CmdCommit,
+    config::CmdConfig,
+    diff::CmdDiff,
+    discard::CmdDiscard,
+    history::CmdHistory,
+    http,
     keys::{ShardManagerContainer, StartInstanceContainer},
+    permmatrix::CmdPermMatrix,
     perms::CmdPerms,
     ping::CmdPing,
     reload::CmdReload,
+    restart::CmdRestart,
+    rollback::CmdRollback,
     save::CmdSave,
+    shutdown::CmdShutdown,
+    status::CmdStatus,
     switch::CmdSwitch,
     uptime::CmdUptime,
     version::CmdVersion,
@@ -68,6 +82,56 @@ impl ModCore {
             map.insert(cmd.name().to_string(), cmd);
         }
 
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdPermMatrix);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdDiff);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdCommit);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdDiscard);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdHistory);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdRollback);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdConfig);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdShutdown);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdRestart);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
+        {
+            let cmd: Box<dyn Command> = Box::new(CmdStatus);
+            map.insert(cmd.name().to_string(), cmd);
+        }
+
         Self(Arc::new(map))
     }
 }
@@ -87,10 +151,28 @@ impl Module for ModCore {
     }
 
     async fn setup(&mut self) {
+        if !CommandHandler::has_client() {
+            return;
+        }
+
         let client = CommandHandler::client_mut();
         let mut data = client.data.write().await;
         data.insert::<ShardManagerContainer>(client.shard_manager.clone());
         data.insert::<StartInstanceContainer>(StartInstanceContainer::new());
+
+        if let Some(address) = unsafe { MASTER.get() }.unwrap().metrics_address.clone() {
+            tokio::spawn(http::serve(address, client.data.clone()));
+        }
+    }
+
+    fn configs(&self) -> Vec<Box<dyn ConfigHandle>> {
+        vec![
+            LiveHandle::<MasterOptions>::boxed(),
+            LiveHandle::<MasterSwitch>::boxed(),
+            LiveHandle::<GuildSwitch>::boxed(),
+            LiveHandle::<Clearance>::boxed(),
+            LiveHandle::<GuildClearance>::boxed(),
+        ]
     }
 
     fn aliases(&self) -> &[(&str, &str)] {
@@ -104,6 +186,16 @@ impl Module for ModCore {
             ("clearance", "core clearance"),
             ("preset", "core clearance"),
             ("perms", "core perms"),
+            ("permmatrix", "core permmatrix"),
+            ("diff", "core diff"),
+            ("commit", "core commit"),
+            ("discard", "core discard"),
+            ("history", "core history"),
+            ("rollback", "core rollback"),
+            ("config", "core config"),
+            ("shutdown", "core shutdown"),
+            ("restart", "core restart"),
+            ("status", "core status"),
         ]
     }
 }
@@ -0,0 +1,67 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, MatrixFormat, PerCommandConfig, PermMatrix};
+
+pub struct CmdPermMatrix;
+
+#[async_trait]
+impl Command for CmdPermMatrix {
+    fn name(&self) -> &str {
+        "permmatrix"
+    }
+
+    fn description(&self) -> &str {
+        "Export the effective permission of every command and category."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["(csv|md)"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let format = match args {
+            [] => MatrixFormat::Markdown,
+            [format] => match MatrixFormat::parse(format) {
+                Some(format) => format,
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        let matrix = match PermMatrix::build().await {
+            Ok(matrix) => matrix,
+            Err(e) => {
+                let _ = ctx
+                    .reply(msg, format!("Could not read the stored rules: {e}."))
+                    .await;
+                return true;
+            }
+        };
+
+        let _ = ctx
+            .reply_file(
+                msg,
+                format!(
+                    "Permission matrix of {} scopes against {} subjects.",
+                    matrix.rows.len(),
+                    matrix.subjects.len()
+                ),
+                matrix.render(format).into_bytes(),
+                format!("permmatrix.{}", format.extension()),
+            )
+            .await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
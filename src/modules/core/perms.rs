@@ -1,11 +1,8 @@
 use std::fmt::Write;
 
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, Clearance, MasterSwitch, PerCommandConfig};
+use crate::{sys::Command, Clearance, Context, GuildSwitch, MasterSwitch, PerCommandConfig};
 
 pub struct CmdPerms;
 
@@ -20,12 +17,18 @@ impl Command for CmdPerms {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[module] (rules...)", "[module] clear"]
+        &[
+            "[module] (rules...)",
+            "[module] clear",
+            "guild [module] (rules...)",
+            "guild [module] clear",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
         match args {
             [] => return false,
+            ["guild", args @ ..] => return guild_perms(args, ctx, msg).await,
             [module] => {
                 let (module, command) = if let Some((module, command)) = module.split_once('.') {
                     (module, Some(command))
@@ -34,7 +37,7 @@ impl Command for CmdPerms {
                 };
 
                 if !MasterSwitch::has_module(module, command) {
-                    let _ = msg.reply(ctx, "No such module.").await;
+                    let _ = ctx.reply(msg, "No such module.").await;
                     return true;
                 }
 
@@ -47,10 +50,7 @@ impl Command for CmdPerms {
                             .unwrap();
 
                         if percmd.allowed.is_empty() {
-                            let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
+                            let _ = ctx.reply(msg, format!(
                                     "**[Permission] {module}.{cmd}**\nThis module has no permission rules.",
                                 ),
                             )
@@ -58,9 +58,9 @@ impl Command for CmdPerms {
                             return true;
                         }
 
-                        let _ = msg
+                        let _ = ctx
                             .reply(
-                                ctx,
+                                msg,
                                 format!(
                                     "**[Permission] {module}.{cmd}**{}",
                                     percmd.allowed.iter().enumerate().fold(
@@ -78,10 +78,7 @@ impl Command for CmdPerms {
                         let permod = MasterSwitch::get(module).unwrap();
 
                         if permod.allowed.is_empty() {
-                            let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
+                            let _ = ctx.reply(msg, format!(
                                     "**[Permission] {module}**\nThis module has no permission rules.",
                                 ),
                             )
@@ -89,9 +86,9 @@ impl Command for CmdPerms {
                             return true;
                         }
 
-                        let _ = msg
+                        let _ = ctx
                             .reply(
-                                ctx,
+                                msg,
                                 format!(
                                     "**[Permission] {module}**{}",
                                     permod.allowed.iter().enumerate().fold(
@@ -108,6 +105,13 @@ impl Command for CmdPerms {
                 }
             }
             [module, "clear"] => {
+                if !MasterSwitch::is_allowed_globally("core", "perms", ctx, msg).await {
+                    let _ = ctx.reply(msg, "You can only change permissions for this server, use `perms guild` instead.",
+                        )
+                        .await;
+                    return true;
+                }
+
                 let (module, command) = if let Some((module, command)) = module.split_once('.') {
                     (module, Some(command))
                 } else {
@@ -115,7 +119,7 @@ impl Command for CmdPerms {
                 };
 
                 if !MasterSwitch::has_module(module, command) {
-                    let _ = msg.reply(ctx, "No such module.").await;
+                    let _ = ctx.reply(msg, "No such module.").await;
                     return true;
                 }
 
@@ -128,10 +132,7 @@ impl Command for CmdPerms {
                             .unwrap();
 
                         if percmd.allowed.is_empty() {
-                            let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
+                            let _ = ctx.reply(msg, format!(
                                     "Module permissions for **{module}.{cmd}** has been cleared, but is was originally empty.",
                                 )
                             )
@@ -141,11 +142,8 @@ impl Command for CmdPerms {
 
                         percmd.allowed.clear();
 
-                        let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
-                                    "Module permissions for **{module}.{cmd}** has been cleared. *(not saved)*",
+                        let _ = ctx.reply(msg, format!(
+                                    "Module permissions for **{module}.{cmd}** has been cleared. *(uncommitted)*",
                                 )
                             )
                             .await;
@@ -154,10 +152,7 @@ impl Command for CmdPerms {
                         let permod = MasterSwitch::get_mut(module).unwrap();
 
                         if permod.allowed.is_empty() {
-                            let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
+                            let _ = ctx.reply(msg, format!(
                                     "Module permissions for **{module}** has been cleared, but is was originally empty.",
                                 )
                             )
@@ -167,11 +162,8 @@ impl Command for CmdPerms {
 
                         permod.allowed.clear();
 
-                        let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
-                                    "Module permissions for **{module}** has been cleared. *(not saved)*",
+                        let _ = ctx.reply(msg, format!(
+                                    "Module permissions for **{module}** has been cleared. *(uncommitted)*",
                                     )
                             )
                             .await;
@@ -179,6 +171,13 @@ impl Command for CmdPerms {
                 }
             }
             [module, ..] => {
+                if !MasterSwitch::is_allowed_globally("core", "perms", ctx, msg).await {
+                    let _ = ctx.reply(msg, "You can only change permissions for this server, use `perms guild` instead.",
+                        )
+                        .await;
+                    return true;
+                }
+
                 let (module, command) = if let Some((module, command)) = module.split_once('.') {
                     (module, Some(command))
                 } else {
@@ -186,15 +185,12 @@ impl Command for CmdPerms {
                 };
 
                 if !MasterSwitch::has_module(module, command) {
-                    let _ = msg.reply(ctx, "No such module.").await;
+                    let _ = ctx.reply(msg, "No such module.").await;
                     return true;
                 }
 
                 if !Clearance::validate(&args[1..], None) {
-                    let _ = msg
-                        .reply(
-                            ctx,
-                            format!("Failed to update module permission for {} because it contains invalid rules.",args[0]),
+                    let _ = ctx.reply(msg, format!("Failed to update module permission for {} because it contains invalid rules.",args[0]),
                         )
                         .await;
                     return true;
@@ -216,12 +212,9 @@ impl Command for CmdPerms {
 
                         percmd.allowed = allowed;
 
-                        let _ = msg
-                            .reply(
-                                ctx,
-                                format!(
+                        let _ = ctx.reply(msg, format!(
 
-                                "Module permissions for **{module}.{cmd}** updated. *(not saved)*",
+                                "Module permissions for **{module}.{cmd}** updated. *(uncommitted)*",
                                     ),
                             )
                             .await;
@@ -231,11 +224,11 @@ impl Command for CmdPerms {
 
                         permod.allowed = allowed;
 
-                        let _ = msg
+                        let _ = ctx
                             .reply(
-                                ctx,
+                                msg,
                                 format!(
-                                    "Module permissions for **{module}** updated. *(not saved)*",
+                                    "Module permissions for **{module}** updated. *(uncommitted)*",
                                 ),
                             )
                             .await;
@@ -254,3 +247,107 @@ impl Command for CmdPerms {
         }
     }
 }
+
+async fn guild_perms(args: &[&str], ctx: &Context, msg: &Message) -> bool {
+    let guild = if let Some(guild) = msg.guild_id {
+        guild
+    } else {
+        let _ = ctx
+            .reply(msg, "Server permissions can only be used in a server.")
+            .await;
+        return true;
+    };
+
+    let item = match args.first() {
+        Some(item) => *item,
+        None => return false,
+    };
+
+    let (module, command) = if let Some((module, command)) = item.split_once('.') {
+        (module, Some(command))
+    } else {
+        (item, None)
+    };
+
+    if !MasterSwitch::has_module(module, command) {
+        let _ = ctx.reply(msg, "No such module.").await;
+        return true;
+    }
+
+    if MasterSwitch::is_global(module, command) {
+        let _ = ctx
+            .reply(
+                msg,
+                format!("**{item}** affects the whole bot and has no server permissions."),
+            )
+            .await;
+        return true;
+    }
+
+    match &args[1..] {
+        [] => {
+            let rules = GuildSwitch::overlay(guild, module, command)
+                .map(|(_, rules)| rules)
+                .unwrap_or_default();
+
+            if rules.is_empty() {
+                let _ = ctx.reply(msg, format!(
+                            "**[Server permission] {item}**\nThis server has no permission rules for this module.",
+                        ),
+                    )
+                    .await;
+                return true;
+            }
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "**[Server permission] {item}**{}",
+                        rules.iter().enumerate().fold(
+                            String::new(),
+                            |mut current, (index, rule)| {
+                                write!(current, "\n{}\\. {}", index + 1, rule).unwrap();
+                                current
+                            }
+                        )
+                    ),
+                )
+                .await;
+        }
+        ["clear"] => {
+            GuildSwitch::set_rules(guild, module, command, Vec::new());
+            GuildSwitch::write_to_config();
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!("Server permissions for **{item}** has been cleared."),
+                )
+                .await;
+        }
+        rules => {
+            if !Clearance::validate(rules, None) {
+                let _ = ctx.reply(msg, format!("Failed to update server permission for {item} because it contains invalid rules."),
+                    )
+                    .await;
+                return true;
+            }
+
+            let mut allowed = rules.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+            if !Clearance::map_rules(&mut allowed, msg, ctx).await {
+                return true;
+            }
+
+            GuildSwitch::set_rules(guild, module, command, allowed);
+            GuildSwitch::write_to_config();
+
+            let _ = ctx
+                .reply(msg, format!("Server permissions for **{item}** updated."))
+                .await;
+        }
+    }
+
+    true
+}
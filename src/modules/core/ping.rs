@@ -1,9 +1,6 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::sys::Command;
+use crate::{sys::Command, Context};
 
 use super::keys::ShardManagerContainer;
 
@@ -29,8 +26,8 @@ impl Command for CmdPing {
         let shard_manager = match data.get::<ShardManagerContainer>() {
             Some(v) => v,
             None => {
-                let _ = msg
-                    .reply(ctx, "There was a problem getting the shard manager")
+                let _ = ctx
+                    .reply(msg, "There was a problem getting the shard manager")
                     .await;
 
                 return true;
@@ -39,18 +36,24 @@ impl Command for CmdPing {
 
         let runners = shard_manager.runners.lock().await;
 
-        let runner = match runners.get(&ctx.shard_id) {
+        // the local console is not on a shard, so use the first one
+        let runner = match ctx.discord() {
+            Some(discord) => runners.get(&discord.shard_id),
+            None => runners.values().next(),
+        };
+
+        let runner = match runner {
             Some(runner) => runner,
             None => {
-                let _ = msg.reply(ctx, "No shard found").await;
+                let _ = ctx.reply(msg, "No shard found").await;
 
                 return true;
             }
         };
 
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
                     "The shard latency is {}",
                     runner
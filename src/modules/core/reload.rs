@@ -1,9 +1,9 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, Clearance, CommandHandler, MasterSwitch, PerCommandConfig};
+use crate::{
+    sys::Command, Clearance, CommandHandler, Context, GuildClearance, GuildSwitch, MasterOptions,
+    MasterSwitch, PerCommandConfig, Staging,
+};
 
 pub struct CmdReload;
 
@@ -18,13 +18,35 @@ impl Command for CmdReload {
     }
 
     fn usage(&self) -> &[&str] {
-        &[]
+        &["(force)"]
     }
 
-    async fn run(&self, _args: &[&str], ctx: &Context, msg: &Message) -> bool {
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        match args {
+            [] => {
+                let staged = Staging::staged();
+
+                if !staged.is_empty() {
+                    let _ = ctx.reply(msg, format!(
+                                "There are uncommitted changes to {}, use `core diff` to view them, or `reload force` to discard them.",
+                                staged
+                                    .iter()
+                                    .map(|name| format!("**{name}**"))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                        )
+                        .await;
+                    return true;
+                }
+            }
+            ["force"] => {}
+            _ => return false,
+        }
+
         reload().await;
 
-        let _ = msg.reply(ctx, "Config reloaded.").await;
+        let _ = ctx.reply(msg, "Config reloaded.").await;
 
         true
     }
@@ -35,12 +57,18 @@ impl Command for CmdReload {
             ..Default::default()
         }
     }
+
+    fn global(&self) -> bool {
+        true
+    }
 }
 
-async fn reload() {
+pub(super) async fn reload() {
+    MasterOptions::reload();
     MasterSwitch::reload();
+    GuildSwitch::reload();
     Clearance::reload();
-    #[cfg(feature = "mongo")]
-    crate::Mongo::reload().await;
+    GuildClearance::reload();
+    crate::Storage::reload().await;
     CommandHandler::reload().await;
 }
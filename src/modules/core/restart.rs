@@ -0,0 +1,53 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig, Shutdown};
+
+use super::keys::ShardManagerContainer;
+
+pub struct CmdRestart;
+
+#[async_trait]
+impl Command for CmdRestart {
+    fn name(&self) -> &str {
+        "restart"
+    }
+
+    fn description(&self) -> &str {
+        "Restart the bot after running commands finish."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        if !args.is_empty() {
+            return false;
+        }
+
+        let shard_manager = ctx
+            .data
+            .read()
+            .await
+            .get::<ShardManagerContainer>()
+            .cloned();
+
+        let _ = ctx.reply(msg, "Restarting.").await;
+
+        // this command is waited for as well, so shutdown cannot run inside it
+        tokio::spawn(Shutdown::begin(true, shard_manager));
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,67 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{config_dir, sys::Command, Context, History, PerCommandConfig, Staging};
+
+use super::reload::reload;
+
+pub struct CmdRollback;
+
+#[async_trait]
+impl Command for CmdRollback {
+    fn name(&self) -> &str {
+        "rollback"
+    }
+
+    fn description(&self) -> &str {
+        "Restore a config file from a snapshot and reload."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &["[config] [n]"]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let (name, n) = match args {
+            [name, n] => match n.parse::<usize>() {
+                Ok(n) => (*name, n),
+                Err(_) => return false,
+            },
+            _ => return false,
+        };
+
+        // the reload afterwards would throw away anything not yet committed
+        if !Staging::staged().is_empty() {
+            let _ = ctx
+                .reply(
+                    msg,
+                    "There are uncommitted changes, commit or discard them before rolling back.",
+                )
+                .await;
+            return true;
+        }
+
+        if let Err(e) = History::rollback(name, n, config_dir().join(format!("{name}.jsonc"))) {
+            let _ = ctx.reply(msg, e).await;
+            return true;
+        }
+
+        reload().await;
+
+        let _ = ctx
+            .reply(msg, format!("Rolled back **{name}** to snapshot #{n}."))
+            .await;
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -1,9 +1,6 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, Clearance, MasterOptions, MasterSwitch, PerCommandConfig};
+use crate::{sys::Command, Context, PerCommandConfig, Staging};
 
 pub struct CmdSave;
 
@@ -14,7 +11,7 @@ impl Command for CmdSave {
     }
 
     fn description(&self) -> &str {
-        "Write configurated options to file."
+        "Write configurated options to file, same as commit without a message."
     }
 
     fn usage(&self) -> &[&str] {
@@ -22,9 +19,9 @@ impl Command for CmdSave {
     }
 
     async fn run(&self, _args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        save();
+        Staging::commit(&format!("{} ({})", msg.author.name, msg.author.id), "");
 
-        let _ = msg.reply(ctx, "Config saved.").await;
+        let _ = ctx.reply(msg, "Config saved.").await;
 
         true
     }
@@ -35,12 +32,8 @@ impl Command for CmdSave {
             ..Default::default()
         }
     }
-}
 
-fn save() {
-    MasterSwitch::write_to_config();
-    MasterOptions::write_to_config();
-    Clearance::write_to_config();
-    // #[cfg(feature = "mongo")]
-    // crate::Mongo::reload().await;
+    fn global(&self) -> bool {
+        true
+    }
 }
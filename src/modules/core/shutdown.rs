@@ -0,0 +1,53 @@
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, Context, PerCommandConfig, Shutdown};
+
+use super::keys::ShardManagerContainer;
+
+pub struct CmdShutdown;
+
+#[async_trait]
+impl Command for CmdShutdown {
+    fn name(&self) -> &str {
+        "shutdown"
+    }
+
+    fn description(&self) -> &str {
+        "Stop the bot after running commands finish."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        if !args.is_empty() {
+            return false;
+        }
+
+        let shard_manager = ctx
+            .data
+            .read()
+            .await
+            .get::<ShardManagerContainer>()
+            .cloned();
+
+        let _ = ctx.reply(msg, "Shutting down.").await;
+
+        // this command is waited for as well, so shutdown cannot run inside it
+        tokio::spawn(Shutdown::begin(false, shard_manager));
+
+        true
+    }
+
+    fn percmd(&self) -> PerCommandConfig {
+        PerCommandConfig {
+            allowed: vec!["?admin".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,209 @@
+use std::{fmt::Write, time::Duration};
+
+use serenity::{all::Message, async_trait};
+
+use crate::{sys::Command, CommandHandler, Context, MasterSwitch, Storage};
+
+use super::{
+    keys::{ShardManagerContainer, StartInstanceContainer},
+    uptime::duration_string,
+};
+
+pub struct CmdStatus;
+
+#[async_trait]
+impl Command for CmdStatus {
+    fn name(&self) -> &str {
+        "status"
+    }
+
+    fn description(&self) -> &str {
+        "Show shards, cache, resource usage, database and modules."
+    }
+
+    fn usage(&self) -> &[&str] {
+        &[]
+    }
+
+    async fn run(&self, _args: &[&str], ctx: &Context, msg: &Message) -> bool {
+        let mut out = format!(
+            "**Status**\nRunning {} {} (Git {})\nFeatures: {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            env!("GIT_HASH"),
+            features().join(", ")
+        );
+
+        let data = ctx.data.read().await;
+
+        let uptime = data
+            .get::<StartInstanceContainer>()
+            .map(|start| start.get().elapsed());
+
+        if let Some(uptime) = uptime {
+            write!(out, "\nUptime: {}", duration_string(uptime.as_secs(), 3)).unwrap();
+        }
+
+        out.push_str("\n\n**Shards**");
+        match data.get::<ShardManagerContainer>() {
+            Some(shard_manager) => {
+                let runners = shard_manager.runners.lock().await;
+                let mut runners = runners.iter().collect::<Vec<_>>();
+                runners.sort_by_key(|entry| entry.0);
+
+                if runners.is_empty() {
+                    out.push_str("\nNo shards running");
+                }
+
+                for (id, runner) in runners {
+                    write!(
+                        out,
+                        "\n\\- {id}: {}, latency {}",
+                        runner.stage,
+                        runner
+                            .latency
+                            .map(|dur| format!("{}ms", dur.as_millis()))
+                            .unwrap_or("not yet known".to_string())
+                    )
+                    .unwrap();
+                }
+            }
+            None => out.push_str("\nNo shard manager"),
+        }
+
+        drop(data);
+
+        if CommandHandler::has_client() {
+            let cache = &CommandHandler::client().cache;
+            write!(
+                out,
+                "\nServers: {}, cached users: {}",
+                cache.guild_count(),
+                cache.user_count()
+            )
+            .unwrap();
+        }
+
+        out.push_str("\n\n**Process**");
+        match process_usage() {
+            Some((memory, cpu)) => {
+                write!(
+                    out,
+                    "\nMemory: {:.1} MiB\nCPU time: {:.2}s",
+                    memory as f64 / 1024.0 / 1024.0,
+                    cpu.as_secs_f64()
+                )
+                .unwrap();
+
+                if let Some(uptime) = uptime.filter(|uptime| !uptime.is_zero()) {
+                    write!(
+                        out,
+                        " ({:.1}% average)",
+                        cpu.as_secs_f64() / uptime.as_secs_f64() * 100.0
+                    )
+                    .unwrap();
+                }
+            }
+            None => out.push_str("\nNot available on this platform"),
+        }
+
+        out.push_str("\n\n**Storage**");
+        match Storage::try_get() {
+            Some(store) => {
+                write!(out, "\nBackend: {}", store.name()).unwrap();
+                match tokio::time::timeout(Duration::from_secs(2), store.ping()).await {
+                    Ok(Some((round_trip, version))) => write!(
+                        out,
+                        "\nServer version {version}, round trip {}ms",
+                        round_trip.as_millis()
+                    )
+                    .unwrap(),
+                    _ => out.push_str("\nNot responding"),
+                }
+            }
+            None => out.push_str("\nNot loaded"),
+        }
+
+        let mut loaded = CommandHandler::get()
+            .modules
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        loaded.sort();
+
+        let mut disabled = MasterSwitch::get_self()
+            .0
+            .iter()
+            .filter(|(_, permod)| !permod.enabled)
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        disabled.sort();
+
+        write!(
+            out,
+            "\n\n**Modules**\nLoaded: {}\nDisabled: {}",
+            loaded.join(", "),
+            if disabled.is_empty() {
+                "none".to_string()
+            } else {
+                disabled.join(", ")
+            }
+        )
+        .unwrap();
+
+        let _ = ctx.reply(msg, out).await;
+
+        true
+    }
+
+    fn global(&self) -> bool {
+        true
+    }
+}
+
+fn features() -> Vec<&'static str> {
+    [
+        ("modcore", cfg!(feature = "modcore")),
+        ("modcoords", cfg!(feature = "modcoords")),
+        ("mongo", cfg!(feature = "mongo")),
+        ("sqlite", cfg!(feature = "sqlite")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
+
+// (resident memory in bytes, cpu time used)
+#[cfg(target_os = "linux")]
+fn process_usage() -> Option<(u64, Duration)> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let memory = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?
+        * 1024;
+
+    // the process name may contain spaces, so fields are counted from the closing parenthesis
+    // utime and stime are fields 14 and 15, in clock ticks
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    let fields = stat[stat.rfind(')')? + 2..].split(' ').collect::<Vec<_>>();
+    let ticks = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+
+    let per_second = u64::try_from(unsafe { libc::sysconf(libc::_SC_CLK_TCK) })
+        .ok()
+        .filter(|ticks| *ticks > 0)?;
+
+    Some((
+        memory,
+        Duration::from_micros(ticks.saturating_mul(1_000_000) / per_second),
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_usage() -> Option<(u64, Duration)> {
+    None
+}
@@ -1,11 +1,8 @@
 use std::fmt::Write;
 
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::{sys::Command, MasterSwitch, PerCommandConfig};
+use crate::{sys::Command, Context, GuildSwitch, MasterSwitch, PerCommandConfig};
 
 pub struct CmdSwitch;
 
@@ -20,22 +17,33 @@ impl Command for CmdSwitch {
     }
 
     fn usage(&self) -> &[&str] {
-        &["[module] (enable|disable)"]
+        &[
+            "[module] (enable|disable)",
+            "guild (module) (enable|disable|reset)",
+        ]
     }
 
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) -> bool {
         match args {
+            ["guild", args @ ..] => return guild_switch(args, ctx, msg).await,
             ["core.switch", val] if matches!(*val, "enable" | "disable") => {
-                let _ = msg.reply(ctx, "core.switch cannot be disabled.").await;
+                let _ = ctx.reply(msg, "core.switch cannot be disabled.").await;
             }
             ["core", val] if matches!(*val, "enable" | "disable") => {
-                let _ = msg.reply(ctx, "core cannot be disabled.").await;
+                let _ = ctx.reply(msg, "core cannot be disabled.").await;
             }
             [item, val] => {
                 if !matches!(*val, "enable" | "disable") {
                     return false;
                 }
 
+                if !MasterSwitch::is_allowed_globally("core", "switch", ctx, msg).await {
+                    let _ = ctx.reply(msg, "You can only change switches for this server, use `switch guild` instead.",
+                        )
+                        .await;
+                    return true;
+                }
+
                 let value = *val == "enable";
 
                 let success = match item.split_once('.') {
@@ -44,11 +52,11 @@ impl Command for CmdSwitch {
                 };
 
                 if success {
-                    let _ = msg
-                        .reply(ctx, format!("{item} has been {val}d. *(not saved)*"))
+                    let _ = ctx
+                        .reply(msg, format!("{item} has been {val}d. *(uncommitted)*"))
                         .await;
                 } else {
-                    let _ = msg.reply(ctx, "No such module.").await;
+                    let _ = ctx.reply(msg, "No such module.").await;
                 }
             }
             [item] => match item.split_once('.') {
@@ -56,7 +64,7 @@ impl Command for CmdSwitch {
                     let module = match MasterSwitch::get(module_str) {
                         Some(module) => module,
                         None => {
-                            let _ = msg.reply(ctx, "No such module.").await;
+                            let _ = ctx.reply(msg, "No such module.").await;
                             return true;
                         }
                     };
@@ -64,14 +72,14 @@ impl Command for CmdSwitch {
                     let cmd = match module.commands.get(cmd_str) {
                         Some(cmd) => cmd,
                         None => {
-                            let _ = msg.reply(ctx, "No such module.").await;
+                            let _ = ctx.reply(msg, "No such module.").await;
                             return true;
                         }
                     };
 
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "{item} is *{}*.",
                                 if cmd.enabled { "enabled" } else { "disabled" }
@@ -83,7 +91,7 @@ impl Command for CmdSwitch {
                     let module = match MasterSwitch::get(item) {
                         Some(module) => module,
                         None => {
-                            let _ = msg.reply(ctx, "No such module.").await;
+                            let _ = ctx.reply(msg, "No such module.").await;
                             return true;
                         }
                     };
@@ -110,9 +118,9 @@ impl Command for CmdSwitch {
                                 current
                             });
 
-                    let _ = msg
+                    let _ = ctx
                         .reply(
-                            ctx,
+                            msg,
                             format!(
                                 "**[Module] {item}** is *{}* with {} commands.\n{cmds}",
                                 if module.enabled {
@@ -139,3 +147,148 @@ impl Command for CmdSwitch {
         }
     }
 }
+
+async fn guild_switch(args: &[&str], ctx: &Context, msg: &Message) -> bool {
+    let guild = if let Some(guild) = msg.guild_id {
+        guild
+    } else {
+        let _ = ctx
+            .reply(msg, "Server switches can only be used in a server.")
+            .await;
+        return true;
+    };
+
+    match args {
+        [] => {
+            let mut overrides = Vec::new();
+
+            for (module, permod) in GuildSwitch::list(guild) {
+                if let Some(enabled) = permod.enabled {
+                    overrides.push((module.to_string(), enabled));
+                }
+
+                for (cmd, percmd) in permod.commands.iter() {
+                    if let Some(enabled) = percmd.enabled {
+                        overrides.push((format!("{module}.{cmd}"), enabled));
+                    }
+                }
+            }
+
+            overrides.sort();
+
+            if overrides.is_empty() {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        "**[Server switches]**\nThis server follows the master switch.",
+                    )
+                    .await;
+                return true;
+            }
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "**[Server switches]**{}",
+                        overrides
+                            .iter()
+                            .fold(String::new(), |mut current, (item, enabled)| {
+                                write!(
+                                    current,
+                                    "\n\\- {item} is *{}*",
+                                    if *enabled { "enabled" } else { "disabled" }
+                                )
+                                .unwrap();
+                                current
+                            })
+                    ),
+                )
+                .await;
+        }
+        ["core.switch", "disable"] => {
+            let _ = ctx.reply(msg, "core.switch cannot be disabled.").await;
+        }
+        ["core", "disable"] => {
+            let _ = ctx.reply(msg, "core cannot be disabled.").await;
+        }
+        [item, val] => {
+            let value = match *val {
+                "enable" => Some(true),
+                "disable" => Some(false),
+                "reset" => None,
+                _ => return false,
+            };
+
+            let (module, command) = match item.split_once('.') {
+                Some((module, cmd)) => (module, Some(cmd)),
+                None => (*item, None),
+            };
+
+            if MasterSwitch::is_global(module, command) {
+                let _ = ctx
+                    .reply(
+                        msg,
+                        format!(
+                            "{item} affects the whole bot and cannot be switched for a server."
+                        ),
+                    )
+                    .await;
+                return true;
+            }
+
+            if !GuildSwitch::switch(guild, module, command, value) {
+                let _ = ctx.reply(msg, "No such module.").await;
+                return true;
+            }
+
+            GuildSwitch::write_to_config();
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    if value.is_some() {
+                        format!("{item} has been {val}d for this server.")
+                    } else {
+                        format!("{item} now follows the master switch in this server.")
+                    },
+                )
+                .await;
+        }
+        [item] => {
+            let (module, command) = match item.split_once('.') {
+                Some((module, cmd)) => (module, Some(cmd)),
+                None => (*item, None),
+            };
+
+            if !MasterSwitch::has_module(module, command) {
+                let _ = ctx.reply(msg, "No such module.").await;
+                return true;
+            }
+
+            let _ = ctx
+                .reply(
+                    msg,
+                    format!(
+                        "{item} is *{}* in this server{}.",
+                        if MasterSwitch::is_enabled(module, command, Some(guild)) {
+                            "enabled"
+                        } else {
+                            "disabled"
+                        },
+                        if GuildSwitch::overlay(guild, module, command)
+                            .is_some_and(|(enabled, _)| enabled.is_some())
+                        {
+                            ""
+                        } else {
+                            " (inherited)"
+                        }
+                    ),
+                )
+                .await;
+        }
+        _ => return false,
+    }
+
+    true
+}
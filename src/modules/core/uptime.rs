@@ -1,9 +1,6 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::sys::Command;
+use crate::{sys::Command, Context};
 
 use super::keys::StartInstanceContainer;
 
@@ -32,9 +29,9 @@ impl Command for CmdUptime {
             .elapsed()
             .as_secs();
 
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!("Merlin has been online for {}", duration_string(elapsed, 3)),
             )
             .await;
@@ -43,7 +40,7 @@ impl Command for CmdUptime {
     }
 }
 
-fn duration_string(mut sec: u64, precision: usize) -> String {
+pub(super) fn duration_string(mut sec: u64, precision: usize) -> String {
     if sec == 0 {
         return "less than a second".to_string();
     }
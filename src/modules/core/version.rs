@@ -1,9 +1,6 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use crate::sys::Command;
+use crate::{sys::Command, Context};
 
 pub struct CmdVersion;
 
@@ -22,9 +19,9 @@ impl Command for CmdVersion {
     }
 
     async fn run(&self, _args: &[&str], ctx: &Context, msg: &Message) -> bool {
-        let _ = msg
+        let _ = ctx
             .reply(
-                ctx,
+                msg,
                 format!(
                     "Running {} {} (Git {})",
                     env!("CARGO_PKG_NAME"),
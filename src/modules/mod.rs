@@ -2,5 +2,7 @@ mod register;
 
 #[cfg(feature = "modcoords")]
 mod coords;
+#[cfg(feature = "modcoords")]
+pub use coords::{BackupManifest, CoordsArchive, CoordsBackup};
 #[cfg(feature = "modcore")]
 mod core;
@@ -6,9 +6,9 @@ use std::{
 
 use async_recursion::async_recursion;
 use serde::{Deserialize, Serialize};
-use serenity::all::{Context, GuildId, Message, RoleId};
+use serenity::all::{GuildId, Message, RoleId};
 
-use super::Config;
+use super::{Config, Context, GuildClearance, LiveConfig};
 
 static mut CLEARANCES: OnceLock<Clearance> = OnceLock::new();
 
@@ -35,6 +35,38 @@ impl Default for Clearance {
 impl Config for Clearance {
     const NAME: &'static str = "clearance";
     const NOTE: &'static str = "Clearance level presets";
+    const HISTORY: bool = true;
+}
+
+impl LiveConfig for Clearance {
+    const STAGED: bool = true;
+
+    fn current() -> &'static Self {
+        Self::get_self()
+    }
+
+    fn replace(new: Self) {
+        unsafe { CLEARANCES = OnceLock::new() };
+        let _ = unsafe { CLEARANCES.set(new) };
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        for (preset, rules) in self.0.iter() {
+            if !Self::validate(&rules.iter().map(String::as_str).collect::<Vec<_>>(), None) {
+                return Err(format!("Invalid rules in preset {preset}."));
+            }
+
+            if !self.no_cycles(preset, &mut HashSet::new())
+                || GuildClearance::guilds().into_iter().any(|guild| {
+                    !GuildClearance::merged(guild, &self.0).no_cycles(preset, &mut HashSet::new())
+                })
+            {
+                return Err(format!("Preset {preset} is defined circularly."));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Clearance {
@@ -70,6 +102,10 @@ impl Clearance {
         unsafe { CLEARANCES.get() }.unwrap().smart_save();
     }
 
+    pub fn get_self() -> &'static Self {
+        unsafe { CLEARANCES.get() }.unwrap()
+    }
+
     pub fn list_all() -> Vec<&'static String> {
         unsafe { CLEARANCES.get() }.unwrap().0.keys().collect()
     }
@@ -87,8 +123,8 @@ impl Clearance {
         for rule in list.iter_mut() {
             if rule.chars().nth(1) == Some('&') && !rule.contains(':') {
                 if msg.guild_id.is_none() {
-                    let _ = msg
-                        .reply(ctx, "Server ID of role based rules cannot be inferred.")
+                    let _ = ctx
+                        .reply(msg, "Server ID of role based rules cannot be inferred.")
                         .await;
                     return false;
                 }
@@ -154,6 +190,17 @@ impl Clearance {
             }
         }
 
+        // a guild preset may refer back to this preset
+        for guild in GuildClearance::guilds() {
+            let merged = GuildClearance::merged(guild, &new_clearance.0);
+
+            for preset in used_presets.iter() {
+                if !merged.no_cycles(preset, &mut HashSet::new()) {
+                    return false;
+                }
+            }
+        }
+
         true
     }
 
@@ -166,21 +213,75 @@ impl Clearance {
             .unwrap_or(&[])
     }
 
-    #[async_recursion]
+    // a guild's own preset is used before the global one
     pub async fn eval(level: &str, ctx: &Context, msg: &Message) -> Option<bool> {
+        Self::resolve(level, false, ctx, msg).await
+    }
+
+    // only the presets in clearance.jsonc, for checks that guard the whole bot
+    pub async fn eval_global(level: &str, ctx: &Context, msg: &Message) -> Option<bool> {
+        Self::resolve(level, true, ctx, msg).await
+    }
+
+    #[async_recursion]
+    async fn resolve(level: &str, global: bool, ctx: &Context, msg: &Message) -> Option<bool> {
+        if let Some(list) = msg
+            .guild_id
+            .filter(|_| !global)
+            .and_then(|guild| GuildClearance::get(guild, level))
+        {
+            return Self::check(list, global, ctx, msg).await;
+        }
+
         match unsafe { CLEARANCES.get() }.unwrap().0.get(level) {
-            Some(list) => Self::is_allowed(list, ctx, msg).await,
+            Some(list) => Self::check(list, global, ctx, msg).await,
             None => None,
         }
     }
 
     pub async fn is_allowed(allowed_list: &[String], ctx: &Context, msg: &Message) -> Option<bool> {
+        Self::check(allowed_list, false, ctx, msg).await
+    }
+
+    // presets in the rules are read from clearance.jsonc only, guild presets are ignored
+    pub async fn is_allowed_globally(
+        allowed_list: &[String],
+        ctx: &Context,
+        msg: &Message,
+    ) -> Option<bool> {
+        Self::check(allowed_list, true, ctx, msg).await
+    }
+
+    // the global presets a rule list refers to, directly or through other presets
+    pub fn presets_used(allowed_list: &[String], out: &mut HashSet<String>) {
+        for preset in allowed_list
+            .iter()
+            .filter_map(|entry| entry.strip_prefix('?'))
+        {
+            if out.insert(preset.to_string()) {
+                Self::presets_used(Self::get(preset), out);
+            }
+        }
+    }
+
+    async fn check(
+        allowed_list: &[String],
+        global: bool,
+        ctx: &Context,
+        msg: &Message,
+    ) -> Option<bool> {
+        // the local console is a superuser
+        let discord = match ctx.discord() {
+            Some(discord) => discord,
+            None => return Some(true),
+        };
+
         for entry in allowed_list.iter().rev() {
             let entry_allowed = match entry.chars().next().unwrap() {
                 '+' => true,
                 '-' => false,
                 '?' => {
-                    if let Some(b) = Self::eval(&entry[1..], ctx, msg).await {
+                    if let Some(b) = Self::resolve(&entry[1..], global, ctx, msg).await {
                         return Some(b);
                     }
                     continue;
@@ -204,7 +305,7 @@ impl Clearance {
                             if id == guild.get() {
                                 return Some(entry_allowed);
                             }
-                        } else if guild.name(ctx).unwrap() == entry[2..] {
+                        } else if guild.name(discord).unwrap() == entry[2..] {
                             return Some(entry_allowed);
                         }
                     }
@@ -215,7 +316,7 @@ impl Clearance {
                             return Some(entry_allowed);
                         }
                     } else if msg.guild_id.is_some()
-                        && msg.channel_id.name(ctx).await.unwrap_or_default() == entry[2..]
+                        && msg.channel_id.name(discord).await.unwrap_or_default() == entry[2..]
                     {
                         return Some(entry_allowed);
                     }
@@ -227,12 +328,12 @@ impl Clearance {
                     if msg
                         .author
                         .has_role(
-                            ctx,
+                            discord,
                             guild,
                             match role.parse() {
                                 Ok(id) => RoleId::new(id),
                                 Err(_) => guild
-                                    .roles(ctx)
+                                    .roles(discord)
                                     .await
                                     .unwrap_or_default()
                                     .values()
@@ -1,9 +1,6 @@
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
 
-use super::PerCommandConfig;
+use super::{Context, PerCommandConfig};
 
 #[async_trait]
 pub trait Command: Sync + Send {
@@ -16,4 +13,9 @@ pub trait Command: Sync + Send {
     fn percmd(&self) -> PerCommandConfig {
         PerCommandConfig::default()
     }
+
+    // commands that act on the whole bot, guild overrides and presets never apply to them
+    fn global(&self) -> bool {
+        false
+    }
 }
@@ -11,38 +11,45 @@ use std::{
 
 use jsonc_to_json::jsonc_to_json;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use similar::TextDiff;
+use tracing::{debug, info, warn};
+
+use super::History;
 
 static mut CONFIG_HASHES: OnceLock<HashMap<String, u64>> = OnceLock::new();
 
+pub fn config_dir() -> PathBuf {
+    if let Ok(config) = env::var("CONFIG") {
+        PathBuf::from(config)
+    } else {
+        dirs::config_dir().unwrap().join(env!("CARGO_PKG_NAME"))
+    }
+}
+
 pub trait Config: Serialize + DeserializeOwned + Default + Hash {
     const NAME: &'static str;
     const NOTE: &'static str = "";
+    // keep previous versions of the file when it is overwritten
+    const HISTORY: bool = false;
 
     fn path() -> PathBuf {
-        if let Ok(config) = env::var("CONFIG") {
-            PathBuf::from(config)
-        } else {
-            dirs::config_dir().unwrap().join(env!("CARGO_PKG_NAME"))
-        }
-        .join(format!("{}.jsonc", Self::NAME))
+        config_dir().join(format!("{}.jsonc", Self::NAME))
     }
 
-    fn smart_save(&self) {
+    // whether the value has changed since it was last loaded or saved
+    fn is_staged(&self) -> bool {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
 
-        let hashes = unsafe { CONFIG_HASHES.get_mut() }.unwrap();
-        let old = hashes.get_mut(Self::NAME).unwrap();
-        let new = hasher.finish();
-
-        if *old != new {
-            self.save();
-            *old = new;
-        }
+        unsafe { CONFIG_HASHES.get() }
+            .and_then(|hashes| hashes.get(Self::NAME))
+            .is_none_or(|old| *old != hasher.finish())
     }
 
-    fn save(&self) {
-        let ser = format!(
+    // keys are sorted so that the output does not depend on hashmap order
+    fn serialized(&self) -> String {
+        format!(
             "{}{}",
             if Self::NOTE.is_empty() {
                 "".to_string()
@@ -56,13 +63,39 @@ pub trait Config: Serialize + DeserializeOwned + Default + Hash {
                         .join("\n")
                 )
             },
-            serde_json::to_string_pretty(self).unwrap()
-        );
+            serde_json::to_string_pretty(&sort_keys(serde_json::to_value(self).unwrap())).unwrap()
+        )
+    }
+
+    fn smart_save(&self) {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+
+        let hashes = unsafe { CONFIG_HASHES.get_mut() }.unwrap();
+        let old = hashes.get_mut(Self::NAME).unwrap();
+        let new = hasher.finish();
+
+        if *old != new {
+            self.save();
+            *old = new;
+        }
+    }
+
+    fn save(&self) {
+        let ser = self.serialized();
 
         let path = Self::path();
 
         fs::create_dir_all(path.parent().unwrap()).unwrap();
 
+        if Self::HISTORY {
+            if let Ok(old) = fs::read_to_string(&path) {
+                if old != ser {
+                    History::snapshot(Self::NAME, &old);
+                }
+            }
+        }
+
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
@@ -71,6 +104,13 @@ pub trait Config: Serialize + DeserializeOwned + Default + Hash {
             .unwrap();
 
         file.write_all(ser.as_bytes()).unwrap();
+        info!(config = Self::NAME, "saved config");
+    }
+
+    // the file as it is on disk, without creating or replacing it, None if there is no file
+    fn read() -> Option<Result<Self, String>> {
+        let content = fs::read_to_string(Self::path()).ok()?;
+        Some(serde_json::from_str(&jsonc_to_json(&content)).map_err(|e| e.to_string()))
     }
 
     fn load() -> Self {
@@ -80,8 +120,11 @@ pub trait Config: Serialize + DeserializeOwned + Default + Hash {
             let content = fs::read_to_string(&path).unwrap();
             let json = jsonc_to_json(&content);
             match serde_json::from_str(&json) {
-                Ok(val) => return insert_hash(val),
-                Err(_) => {
+                Ok(val) => {
+                    debug!(config = Self::NAME, path = %path.display(), "loaded config");
+                    return insert_hash(val);
+                }
+                Err(e) => {
                     let meta = path
                         .metadata()
                         .unwrap()
@@ -90,8 +133,14 @@ pub trait Config: Serialize + DeserializeOwned + Default + Hash {
                         .duration_since(UNIX_EPOCH)
                         .unwrap()
                         .as_secs();
-                    fs::rename(&path, path.with_file_name(format!("{}-{meta}", Self::NAME)))
-                        .unwrap();
+                    let backup = path.with_file_name(format!("{}-{meta}", Self::NAME));
+                    warn!(
+                        config = Self::NAME,
+                        error = %e,
+                        backup = %backup.display(),
+                        "config could not be parsed, replaced with defaults"
+                    );
+                    fs::rename(&path, backup).unwrap();
                     let out = Self::default();
                     out.save();
                     return insert_hash(out);
@@ -99,12 +148,30 @@ pub trait Config: Serialize + DeserializeOwned + Default + Hash {
             }
         }
 
+        debug!(
+            config = Self::NAME,
+            "config not found, creating with defaults"
+        );
         let out = Self::default();
         out.save();
         insert_hash(out)
     }
 }
 
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    Some(
+        TextDiff::from_lines(old, new)
+            .unified_diff()
+            .context_radius(2)
+            .header(old_label, new_label)
+            .to_string(),
+    )
+}
+
 fn insert_hash<C: Config>(config: C) -> C {
     if unsafe { CONFIG_HASHES.get() }.is_none() {
         let _ = unsafe { CONFIG_HASHES.set(HashMap::new()) };
@@ -118,3 +185,20 @@ fn insert_hash<C: Config>(config: C) -> C {
         .insert(C::NAME.to_string(), hasher.finish());
     config
 }
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
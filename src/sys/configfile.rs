@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use super::{
+    Clearance, CommandHandler, CommitLog, Config, GuildClearance, GuildSwitch, LoggingConfig,
+    MasterOptions, MasterSwitch, StorageConfig,
+};
+
+// a config file on disk, checked and created without loading the config
+pub struct ConfigFile {
+    pub name: &'static str,
+    pub path: PathBuf,
+    read: fn() -> Option<Result<(), String>>,
+    init: fn(),
+}
+
+impl ConfigFile {
+    pub fn of<C: Config>() -> Self {
+        Self {
+            name: C::NAME,
+            path: C::path(),
+            read: || C::read().map(|res| res.map(|_| ())),
+            init: || C::default().save(),
+        }
+    }
+
+    // every config file the bot may read, including those of modules that are not enabled
+    pub fn all() -> Vec<Self> {
+        let mut files = vec![
+            Self::of::<MasterOptions>(),
+            Self::of::<MasterSwitch>(),
+            Self::of::<GuildSwitch>(),
+            Self::of::<Clearance>(),
+            Self::of::<GuildClearance>(),
+            Self::of::<LoggingConfig>(),
+            Self::of::<CommitLog>(),
+            Self::of::<StorageConfig>(),
+            #[cfg(feature = "mongo")]
+            Self::of::<super::MongoConfig>(),
+        ];
+
+        for module in CommandHandler::available() {
+            for config in module.configs() {
+                if files.iter().all(|file| file.name != config.name()) {
+                    files.push(config.file());
+                }
+            }
+        }
+
+        files.sort_by_key(|file| file.name);
+        files
+    }
+
+    // None if the file does not exist, defaults are used in that case
+    pub fn check(&self) -> Option<Result<(), String>> {
+        (self.read)()
+    }
+
+    // writes the defaults if the file does not exist, returns whether it was created
+    pub fn init(&self) -> bool {
+        if self.path.exists() {
+            return false;
+        }
+
+        (self.init)();
+        true
+    }
+}
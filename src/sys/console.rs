@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Http, Message},
+    prelude::TypeMap,
+};
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::{mpsc, RwLock},
+};
+use tracing::{info, warn};
+
+use super::{CommandHandler, Context, Shutdown, MASTER};
+
+// marks the end of the replies to one line on the console socket
+pub const END_OF_REPLY: &str = "\x04";
+
+// runs commands typed locally as a superuser, for when discord is not usable
+pub struct Console;
+
+impl Console {
+    // returns every reply of the command, in order
+    pub async fn run(line: &str, data: Arc<RwLock<TypeMap>>, http: Arc<Http>) -> Vec<String> {
+        // the prefix is optional on the console
+        let prefix = unsafe { MASTER.get() }.unwrap().prefix.as_str();
+        let line = line.trim();
+        let line = line.strip_prefix(prefix).unwrap_or(line);
+
+        let args = match shell_words::split(line) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return Vec::new(),
+            Err(e) => return vec![format!("Could not parse command: {e}")],
+        };
+
+        let _in_flight = match Shutdown::enter() {
+            Some(in_flight) => in_flight,
+            None => return vec!["Shutting down.".to_string()],
+        };
+
+        info!(line, "console command");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = Context::local(data, http, tx);
+
+        let mut msg = Message::default();
+        msg.author.name = "console".to_string();
+        msg.content = line.to_string();
+
+        CommandHandler::run(
+            args.iter().map(String::as_str).collect::<Vec<_>>().as_ref(),
+            &ctx,
+            &msg,
+        )
+        .await;
+        drop(ctx);
+
+        let mut replies = Vec::new();
+        while let Some(reply) = rx.recv().await {
+            replies.push(reply);
+        }
+        replies
+    }
+
+    pub async fn stdin(data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+        let mut lines = BufReader::new(io::stdin()).lines();
+        let mut stdout = io::stdout();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            for reply in Self::run(&line, data.clone(), http.clone()).await {
+                let _ = stdout.write_all(format!("{reply}\n").as_bytes()).await;
+            }
+            let _ = stdout.flush().await;
+        }
+    }
+
+    // each line sent to the socket is run as a command, its replies are followed by END_OF_REPLY
+    #[cfg(unix)]
+    pub async fn socket(path: String, data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+        use std::{fs, os::unix::fs::PermissionsExt};
+        use tokio::net::UnixListener;
+
+        let _ = fs::remove_file(&path);
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!(path, error = %e, "could not bind console socket");
+                return;
+            }
+        };
+
+        // only the user running the bot may connect
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+        info!(path, "console socket started");
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            };
+
+            let data = data.clone();
+            let http = http.clone();
+
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    for reply in Self::run(&line, data.clone(), http.clone()).await {
+                        if write
+                            .write_all(format!("{reply}\n").as_bytes())
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+
+                    if write
+                        .write_all(format!("{END_OF_REPLY}\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+    }
+}
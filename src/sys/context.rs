@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Cache, CacheHttp, CreateAttachment, CreateMessage, EditMessage, Http, Message},
+    prelude::TypeMap,
+};
+use tokio::sync::{mpsc::UnboundedSender, RwLock};
+use tracing::error;
+
+use super::StoreError;
+
+// what a command runs against, either a discord event or the local console
+#[derive(Clone)]
+pub struct Context {
+    pub data: Arc<RwLock<TypeMap>>,
+    pub http: Arc<Http>,
+    source: Source,
+}
+
+#[derive(Clone)]
+enum Source {
+    Discord(serenity::all::Context),
+    // replies are sent down the channel instead of to discord
+    Local(UnboundedSender<String>),
+}
+
+impl From<serenity::all::Context> for Context {
+    fn from(ctx: serenity::all::Context) -> Self {
+        Self {
+            data: ctx.data.clone(),
+            http: ctx.http.clone(),
+            source: Source::Discord(ctx),
+        }
+    }
+}
+
+impl Context {
+    pub fn local(
+        data: Arc<RwLock<TypeMap>>,
+        http: Arc<Http>,
+        replies: UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            data,
+            http,
+            source: Source::Local(replies),
+        }
+    }
+
+    pub fn discord(&self) -> Option<&serenity::all::Context> {
+        match &self.source {
+            Source::Discord(ctx) => Some(ctx),
+            Source::Local(_) => None,
+        }
+    }
+
+    // commands from the local console pass every permission check
+    pub fn is_local(&self) -> bool {
+        matches!(self.source, Source::Local(_))
+    }
+
+    pub async fn reply(
+        &self,
+        msg: &Message,
+        content: impl Into<String>,
+    ) -> serenity::Result<Message> {
+        match &self.source {
+            Source::Discord(ctx) => msg.reply(ctx, content).await,
+            Source::Local(replies) => {
+                let _ = replies.send(content.into());
+                Ok(Message::default())
+            }
+        }
+    }
+
+    // the store being down is already logged by its health check
+    pub async fn reply_store_error(
+        &self,
+        msg: &Message,
+        e: &StoreError,
+    ) -> serenity::Result<Message> {
+        if !matches!(e, StoreError::Unavailable(_)) {
+            error!(error = %e, "query failed");
+        }
+        self.reply(msg, e.reply()).await
+    }
+
+    // text attachments are printed in full to the console
+    pub async fn reply_file(
+        &self,
+        msg: &Message,
+        content: impl Into<String>,
+        data: Vec<u8>,
+        filename: impl Into<String>,
+    ) -> serenity::Result<Message> {
+        let filename = filename.into();
+
+        match &self.source {
+            Source::Discord(ctx) => {
+                msg.channel_id
+                    .send_message(
+                        ctx,
+                        CreateMessage::new()
+                            .content(content)
+                            .add_file(CreateAttachment::bytes(data, filename))
+                            .reference_message(msg),
+                    )
+                    .await
+            }
+            Source::Local(replies) => {
+                let _ = replies.send(content.into());
+                let _ = replies.send(match String::from_utf8(data) {
+                    Ok(text) => format!("--- {filename}\n{text}"),
+                    Err(e) => format!("--- {filename} ({} bytes)", e.as_bytes().len()),
+                });
+                Ok(Message::default())
+            }
+        }
+    }
+
+    pub async fn edit(&self, message: &mut Message, builder: EditMessage) -> serenity::Result<()> {
+        match &self.source {
+            Source::Discord(ctx) => message.edit(ctx, builder).await,
+            Source::Local(_) => Ok(()),
+        }
+    }
+}
+
+impl CacheHttp for Context {
+    fn http(&self) -> &Http {
+        &self.http
+    }
+
+    fn cache(&self) -> Option<&Arc<Cache>> {
+        self.discord().map(|ctx| &ctx.cache)
+    }
+}
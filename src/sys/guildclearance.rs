@@ -0,0 +1,174 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::GuildId;
+
+use super::{Clearance, Config, LiveConfig};
+
+static mut GUILD_CLEARANCES: OnceLock<GuildClearance> = OnceLock::new();
+
+// presets defined by a guild, these are looked up before the global presets of the same name
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct GuildClearance(pub HashMap<String, HashMap<String, Vec<String>>>);
+
+impl Hash for GuildClearance {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut guilds = self.0.iter().collect::<Vec<_>>();
+        guilds.sort_by_key(|entry| entry.0);
+
+        for (guild, presets) in guilds {
+            guild.hash(state);
+            let mut presets = presets.iter().collect::<Vec<_>>();
+            presets.sort_by_key(|entry| entry.0);
+            presets.hash(state);
+        }
+    }
+}
+
+impl Config for GuildClearance {
+    const NAME: &'static str = "guildclearance";
+    const NOTE: &'static str = "Per server clearance level presets";
+}
+
+impl LiveConfig for GuildClearance {
+    fn current() -> &'static Self {
+        Self::get_self()
+    }
+
+    fn replace(new: Self) {
+        unsafe { GUILD_CLEARANCES = OnceLock::new() };
+        let _ = unsafe { GUILD_CLEARANCES.set(new) };
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        for (guild, presets) in self.0.iter() {
+            let mut merged = Clearance::get_self().clone();
+            merged.0.extend(presets.clone());
+
+            for (preset, rules) in presets.iter() {
+                if !Clearance::validate(&rules.iter().map(String::as_str).collect::<Vec<_>>(), None)
+                {
+                    return Err(format!(
+                        "Invalid rules in preset {preset} of server {guild}."
+                    ));
+                }
+
+                if !merged.no_cycles(preset, &mut HashSet::new()) {
+                    return Err(format!(
+                        "Preset {preset} of server {guild} is defined circularly."
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GuildClearance {
+    pub fn setup() {
+        let _ = unsafe { GUILD_CLEARANCES.set(Self::load()) };
+    }
+
+    pub fn reload() {
+        unsafe { GUILD_CLEARANCES = OnceLock::new() };
+        Self::setup();
+    }
+
+    pub fn write_to_config() {
+        unsafe { GUILD_CLEARANCES.get() }.unwrap().smart_save();
+    }
+
+    pub fn get_self() -> &'static Self {
+        unsafe { GUILD_CLEARANCES.get() }.unwrap()
+    }
+
+    pub fn get(guild: GuildId, level: &str) -> Option<&'static [String]> {
+        unsafe { GUILD_CLEARANCES.get() }
+            .unwrap()
+            .0
+            .get(&guild.to_string())
+            .and_then(|presets| presets.get(level))
+            .map(Vec::as_slice)
+    }
+
+    pub fn list_all(guild: GuildId) -> Vec<&'static String> {
+        unsafe { GUILD_CLEARANCES.get() }
+            .unwrap()
+            .0
+            .get(&guild.to_string())
+            .map(|presets| presets.keys().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn remove(guild: GuildId, entry: &str) -> bool {
+        let clearances = unsafe { GUILD_CLEARANCES.get_mut() }.unwrap();
+
+        let presets = match clearances.0.get_mut(&guild.to_string()) {
+            Some(presets) => presets,
+            None => return false,
+        };
+
+        let removed = presets.remove(entry).is_some();
+
+        if presets.is_empty() {
+            clearances.0.remove(&guild.to_string());
+        }
+
+        removed
+    }
+
+    pub fn set(guild: GuildId, entry: String, list: &[&str]) -> bool {
+        if !Clearance::validate(list, None) {
+            return false;
+        }
+
+        let mut merged = Self::merged(guild, &Clearance::get_self().0);
+        merged
+            .0
+            .insert(entry.clone(), list.iter().map(|s| s.to_string()).collect());
+
+        if !merged.no_cycles(&entry, &mut HashSet::new()) {
+            return false;
+        }
+
+        unsafe { GUILD_CLEARANCES.get_mut() }
+            .unwrap()
+            .0
+            .entry(guild.to_string())
+            .or_default()
+            .insert(entry, list.iter().map(|s| s.to_string()).collect());
+
+        true
+    }
+
+    // the presets as seen from a guild: global presets, shadowed by the guild's own
+    pub fn merged(guild: GuildId, global: &HashMap<String, Vec<String>>) -> Clearance {
+        let mut merged = global.clone();
+
+        if let Some(presets) = unsafe { GUILD_CLEARANCES.get() }
+            .unwrap()
+            .0
+            .get(&guild.to_string())
+        {
+            merged.extend(presets.clone());
+        }
+
+        Clearance(merged)
+    }
+
+    pub fn guilds() -> Vec<GuildId> {
+        unsafe { GUILD_CLEARANCES.get() }
+            .unwrap()
+            .0
+            .keys()
+            .filter_map(|guild| guild.parse::<u64>().ok())
+            .filter(|id| *id != 0)
+            .map(GuildId::new)
+            .collect()
+    }
+}
@@ -0,0 +1,233 @@
+use std::{collections::HashMap, hash::Hash, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::GuildId;
+
+use super::{Clearance, Config, LiveConfig, MasterSwitch};
+
+static mut GUILD_SWITCH: OnceLock<GuildSwitch> = OnceLock::new();
+
+// overrides on top of the master switch, keyed by guild id then module name
+#[derive(Serialize, Deserialize, Default)]
+pub struct GuildSwitch(pub HashMap<String, HashMap<String, GuildModuleConfig>>);
+
+impl Hash for GuildSwitch {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut guilds = self.0.iter().collect::<Vec<_>>();
+        guilds.sort_by_key(|entry| entry.0);
+
+        for (guild, modules) in guilds {
+            guild.hash(state);
+            let mut modules = modules.iter().collect::<Vec<_>>();
+            modules.sort_by_key(|entry| entry.0);
+            modules.hash(state);
+        }
+    }
+}
+
+impl Config for GuildSwitch {
+    const NAME: &'static str = "guildswitch";
+    const NOTE: &'static str = "Per server overrides for the master switch";
+}
+
+impl LiveConfig for GuildSwitch {
+    fn current() -> &'static Self {
+        Self::get_self()
+    }
+
+    fn replace(new: Self) {
+        unsafe { GUILD_SWITCH = OnceLock::new() };
+        let _ = unsafe { GUILD_SWITCH.set(new) };
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        for (guild, modules) in self.0.iter() {
+            for (module, permod) in modules.iter() {
+                if !Clearance::validate(
+                    &permod
+                        .allowed
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>(),
+                    None,
+                ) {
+                    return Err(format!(
+                        "Invalid rules in module {module} of server {guild}."
+                    ));
+                }
+
+                for (cmd, percmd) in permod.commands.iter() {
+                    if !Clearance::validate(
+                        &percmd
+                            .allowed
+                            .iter()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>(),
+                        None,
+                    ) {
+                        return Err(format!(
+                            "Invalid rules in command {module}.{cmd} of server {guild}."
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GuildModuleConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub commands: HashMap<String, GuildCommandConfig>,
+}
+
+impl Hash for GuildModuleConfig {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.enabled.hash(state);
+        self.allowed.hash(state);
+        let mut command = self.commands.iter().collect::<Vec<_>>();
+        command.sort_by_key(|entry| entry.0);
+        command.hash(state);
+    }
+}
+
+impl GuildModuleConfig {
+    fn is_empty(&self) -> bool {
+        self.enabled.is_none() && self.allowed.is_empty() && self.commands.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Hash)]
+pub struct GuildCommandConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+}
+
+impl GuildCommandConfig {
+    fn is_empty(&self) -> bool {
+        self.enabled.is_none() && self.allowed.is_empty()
+    }
+}
+
+impl GuildSwitch {
+    pub fn setup() {
+        let _ = unsafe { GUILD_SWITCH.set(Self::load()) };
+    }
+
+    pub fn reload() {
+        unsafe { GUILD_SWITCH = OnceLock::new() };
+        Self::setup();
+    }
+
+    pub fn write_to_config() {
+        unsafe { GUILD_SWITCH.get() }.unwrap().smart_save();
+    }
+
+    pub fn get_self() -> &'static Self {
+        unsafe { GUILD_SWITCH.get() }.unwrap()
+    }
+
+    pub fn get(guild: GuildId, module: &str) -> Option<&'static GuildModuleConfig> {
+        unsafe { GUILD_SWITCH.get() }
+            .unwrap()
+            .0
+            .get(&guild.to_string())
+            .and_then(|modules| modules.get(module))
+    }
+
+    // (enabled override, extra rules) for a module or a command in a guild
+    pub fn overlay(
+        guild: GuildId,
+        module: &str,
+        command: Option<&str>,
+    ) -> Option<(Option<bool>, &'static [String])> {
+        let permod = Self::get(guild, module)?;
+
+        match command {
+            Some(cmd) => permod
+                .commands
+                .get(cmd)
+                .map(|percmd| (percmd.enabled, percmd.allowed.as_slice())),
+            None => Some((permod.enabled, permod.allowed.as_slice())),
+        }
+    }
+
+    pub fn list(guild: GuildId) -> Vec<(&'static String, &'static GuildModuleConfig)> {
+        let mut modules = unsafe { GUILD_SWITCH.get() }
+            .unwrap()
+            .0
+            .get(&guild.to_string())
+            .map(|modules| modules.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        modules.sort_by_key(|entry| entry.0);
+        modules
+    }
+
+    // None for value resets the override back to the master switch
+    pub fn switch(
+        guild: GuildId,
+        module: &str,
+        command: Option<&str>,
+        value: Option<bool>,
+    ) -> bool {
+        if !MasterSwitch::has_module(module, command) {
+            return false;
+        }
+
+        Self::modify(guild, module, command, |enabled, _| *enabled = value);
+        true
+    }
+
+    pub fn set_rules(
+        guild: GuildId,
+        module: &str,
+        command: Option<&str>,
+        rules: Vec<String>,
+    ) -> bool {
+        if !MasterSwitch::has_module(module, command) {
+            return false;
+        }
+
+        Self::modify(guild, module, command, |_, allowed| *allowed = rules);
+        true
+    }
+
+    fn modify<F: FnOnce(&mut Option<bool>, &mut Vec<String>)>(
+        guild: GuildId,
+        module: &str,
+        command: Option<&str>,
+        f: F,
+    ) {
+        let switch = unsafe { GUILD_SWITCH.get_mut() }.unwrap();
+        let modules = switch.0.entry(guild.to_string()).or_default();
+        let permod = modules.entry(module.to_string()).or_default();
+
+        match command {
+            Some(cmd) => {
+                let percmd = permod.commands.entry(cmd.to_string()).or_default();
+                f(&mut percmd.enabled, &mut percmd.allowed);
+
+                if percmd.is_empty() {
+                    permod.commands.remove(cmd);
+                }
+            }
+            None => f(&mut permod.enabled, &mut permod.allowed),
+        }
+
+        if permod.is_empty() {
+            modules.remove(module);
+        }
+
+        if modules.is_empty() {
+            switch.0.remove(&guild.to_string());
+        }
+    }
+}
@@ -1,15 +1,20 @@
-use std::{collections::HashMap, fmt::Write, sync::OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    sync::OnceLock,
+};
 
 use async_recursion::async_recursion;
-use serenity::{
-    all::{Context, Message},
-    Client,
-};
+use serenity::{all::Message, Client};
+use tracing::{debug, error, info, warn};
 
-use super::{Config, MasterSwitch, Module, MASTER};
+use super::{
+    Config, ConfigHandle, Context, MasterSwitch, Migrations, Module, Storage, StoreError, MASTER,
+};
 
 static mut CLIENT: OnceLock<Client> = OnceLock::new();
 static mut HANDLER: OnceLock<CommandHandler> = OnceLock::new();
+static mut GLOBAL: OnceLock<HashSet<(String, String)>> = OnceLock::new();
 
 pub struct CommandHandler {
     pub modules: HashMap<String, Box<dyn Module>>,
@@ -36,6 +41,70 @@ impl CommandHandler {
         let _ = unsafe { CLIENT.set(client) };
     }
 
+    pub fn has_client() -> bool {
+        unsafe { CLIENT.get() }.is_some()
+    }
+
+    pub fn get() -> &'static Self {
+        unsafe { HANDLER.get() }.unwrap()
+    }
+
+    // applies pending migrations and creates indexes of the loaded modules, does nothing while they are being loaded
+    pub async fn prepare_storage() -> Result<(), StoreError> {
+        let handler = match unsafe { HANDLER.get() } {
+            Some(handler) => handler,
+            None => return Ok(()),
+        };
+
+        for module in handler.modules.values() {
+            prepare_storage(module.as_ref()).await?;
+        }
+
+        Ok(())
+    }
+
+    // every module compiled in, none of them set up
+    pub fn available() -> Vec<Box<dyn Module>> {
+        let mut handler = Self::new();
+        handler.register();
+        handler.modules.into_values().collect()
+    }
+
+    // asks every module compiled in, so commands of disabled modules are known as well
+    pub fn is_global(module: &str, command: &str) -> bool {
+        unsafe { GLOBAL.get_or_init(Self::global_commands) }
+            .contains(&(module.to_string(), command.to_string()))
+    }
+
+    fn global_commands() -> HashSet<(String, String)> {
+        let mut out = HashSet::new();
+
+        for module in Self::available() {
+            for cmd in module.commands().values().filter(|cmd| cmd.global()) {
+                out.insert((module.name().to_string(), cmd.name().to_string()));
+            }
+        }
+
+        out
+    }
+
+    // every config provided by the loaded modules, sorted by name
+    pub fn configs() -> Vec<Box<dyn ConfigHandle>> {
+        let mut configs = Self::get()
+            .modules
+            .values()
+            .flat_map(|module| module.configs())
+            .collect::<Vec<_>>();
+        configs.sort_by_key(|config| config.name());
+        configs
+    }
+
+    pub fn config(name: &str) -> Option<Box<dyn ConfigHandle>> {
+        Self::configs()
+            .into_iter()
+            .find(|config| config.name() == name)
+    }
+
     pub fn add_module<M: Module + 'static>(&mut self, module: M) {
         self.modules
             .insert(module.name().to_string(), Box::new(module));
@@ -43,10 +112,13 @@ impl CommandHandler {
 
     #[async_recursion]
     pub async fn run(args: &[&str], ctx: &Context, msg: &Message) {
+        debug!(?args, user = %msg.author.id, guild = ?msg.guild_id, "command received");
+
         if !args.is_empty() {
             if args[0] == "help" {
                 if !args.is_empty()
-                    && MasterSwitch::get(args[0]).is_some_and(|permod| !permod.enabled)
+                    && MasterSwitch::has_module(args[0], None)
+                    && !MasterSwitch::is_enabled(args[0], None, msg.guild_id)
                 {
                     return;
                 }
@@ -55,7 +127,10 @@ impl CommandHandler {
                 return;
             }
 
-            if MasterSwitch::get(args[0]).is_some_and(|permod| !permod.enabled) {
+            if MasterSwitch::has_module(args[0], None)
+                && !MasterSwitch::is_enabled(args[0], None, msg.guild_id)
+            {
+                debug!(module = args[0], "module disabled");
                 return;
             }
 
@@ -124,9 +199,9 @@ impl CommandHandler {
                     return;
                 };
 
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "**[Command] {}.{}**\n{}\n\n**Usage**:{}",
                             module.name(),
@@ -158,11 +233,10 @@ impl CommandHandler {
             }
             [module, ..] if handler.modules.contains_key(*module) => {
                 let module = handler.modules.get(*module).unwrap();
-                let switch = MasterSwitch::get(module.name()).unwrap();
 
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!(
                             "**[Module] {}**\n{}{}{}",
                             module.name(),
@@ -173,7 +247,13 @@ impl CommandHandler {
                                 let mut commands = module
                                     .commands()
                                     .keys()
-                                    .filter(|k| switch.commands.get(k.as_str()).unwrap().enabled)
+                                    .filter(|k| {
+                                        MasterSwitch::is_enabled(
+                                            module.name(),
+                                            Some(k.as_str()),
+                                            msg.guild_id,
+                                        )
+                                    })
                                     .map(|label| format!("\\- {}", label))
                                     .collect::<Vec<_>>();
                                 commands.sort();
@@ -208,14 +288,14 @@ impl CommandHandler {
                 .await
             }
             _ => {
-                let _ = msg
+                let _ = ctx
                     .reply(
-                        ctx,
+                        msg,
                         format!("**Available modules**\n{}", {
                             let mut modules = handler
                                 .modules
                                 .keys()
-                                .filter(|k| MasterSwitch::get(k).unwrap().enabled)
+                                .filter(|k| MasterSwitch::is_enabled(k, None, msg.guild_id))
                                 .map(|label| format!("\\- {}", label))
                                 .collect::<Vec<_>>();
                             modules.sort();
@@ -247,6 +327,7 @@ impl CommandHandler {
                     }
 
                     if !permod.enabled {
+                        info!(module = module.name(), "module disabled, not loaded");
                         disabled_modules.push(module.name().to_string());
                         continue;
                     }
@@ -257,11 +338,32 @@ impl CommandHandler {
                 }
             }
 
+            // a module is not loaded on data it does not understand
+            // while the storage is down its commands are refused, this is done once it is back
+            if Storage::try_get().is_some() && Storage::is_healthy() {
+                match prepare_storage(module.as_ref()).await {
+                    Ok(()) => {}
+                    Err(StoreError::Unavailable(e)) => {
+                        warn!(
+                            module = module.name(),
+                            error = e,
+                            "storage unavailable, migrations postponed"
+                        )
+                    }
+                    Err(e) => {
+                        error!(module = module.name(), error = %e, "migration failed, module not loaded");
+                        disabled_modules.push(module.name().to_string());
+                        continue;
+                    }
+                }
+            }
+
             if reload {
                 module.reload().await
             } else {
                 module.setup().await;
             }
+            debug!(module = module.name(), reload, "module loaded");
 
             for (from, to) in module.aliases() {
                 handler.alias.insert(from.to_string(), to.to_string());
@@ -269,7 +371,7 @@ impl CommandHandler {
         }
 
         if switch_modified {
-            switch.save();
+            switch.smart_save();
         }
 
         for disabled in disabled_modules {
@@ -284,3 +386,20 @@ impl CommandHandler {
         Self::load(true).await;
     }
 }
+
+async fn prepare_storage(module: &dyn Module) -> Result<(), StoreError> {
+    let migrations = module.migrations();
+    if !migrations.is_empty() {
+        Migrations::apply(module.name(), &migrations, false).await?;
+    }
+
+    for (collection, names) in module.dropped_indexes() {
+        Storage::drop_indexes(collection, names).await?;
+    }
+
+    for (collection, indexes) in module.indexes() {
+        Storage::ensure_indexes(collection, indexes).await?;
+    }
+
+    Ok(())
+}
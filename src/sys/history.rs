@@ -0,0 +1,109 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{debug, info};
+
+use super::{config_dir, MASTER};
+
+const DEFAULT_LIMIT: usize = 20;
+const DEFAULT_DAYS: u64 = 30;
+
+// previous versions of config files, kept in history/[config]/[unix millis].jsonc
+pub struct History;
+
+impl History {
+    pub fn dir(name: &str) -> PathBuf {
+        config_dir().join("history").join(name)
+    }
+
+    fn valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    // keeps the content of a config file that is about to be overwritten
+    pub fn snapshot(name: &str, content: &str) {
+        let dir = Self::dir(name);
+        if fs::create_dir_all(&dir).is_err() {
+            return;
+        }
+
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let _ = fs::write(dir.join(format!("{millis}.jsonc")), content);
+        debug!(config = name, snapshot = millis, "config snapshot taken");
+
+        Self::prune(name);
+    }
+
+    // (unix millis, path) of every snapshot, newest first
+    pub fn list(name: &str) -> Vec<(u64, PathBuf)> {
+        if !Self::valid_name(name) {
+            return Vec::new();
+        }
+
+        let mut snapshots = match fs::read_dir(Self::dir(name)) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let millis = path.file_name()?.to_str()?.strip_suffix(".jsonc")?;
+                    Some((millis.parse::<u64>().ok()?, path))
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+
+        snapshots.sort_by_key(|entry| Reverse(entry.0));
+        snapshots
+    }
+
+    // writes the nth newest snapshot over the config file, the file being replaced is snapshotted as well
+    // the config should be reloaded afterwards
+    pub fn rollback(name: &str, n: usize, path: PathBuf) -> Result<(), String> {
+        let snapshots = Self::list(name);
+
+        let (_, snapshot) = match n.checked_sub(1).and_then(|i| snapshots.get(i)) {
+            Some(snapshot) => snapshot,
+            None => return Err(format!("No snapshot #{n} for {name}.")),
+        };
+
+        let content = fs::read_to_string(snapshot).map_err(|e| e.to_string())?;
+
+        if let Ok(current) = fs::read_to_string(&path) {
+            if current != content {
+                Self::snapshot(name, &current);
+            }
+        }
+
+        fs::write(path, content).map_err(|e| e.to_string())?;
+        info!(config = name, snapshot = n, "config rolled back");
+        Ok(())
+    }
+
+    // drops snapshots past the count limit, and those older than the age limit
+    fn prune(name: &str) {
+        let (limit, days) = unsafe { MASTER.get() }
+            .map(|master| (master.history_limit, master.history_days))
+            .unwrap_or((DEFAULT_LIMIT, DEFAULT_DAYS));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        for (i, (millis, path)) in Self::list(name).into_iter().enumerate() {
+            if i >= limit || (days != 0 && now.saturating_sub(millis) > days * 86_400_000) {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
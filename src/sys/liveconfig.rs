@@ -0,0 +1,130 @@
+use std::{fs, marker::PhantomData};
+
+use jsonc_to_json::jsonc_to_json;
+
+use super::{unified_diff, Config, ConfigFile};
+
+// a config that can be exported and replaced while running
+pub trait LiveConfig: Config + Send + Sync + 'static {
+    // replaced values are left as uncommitted changes when true, otherwise saved immediately
+    const STAGED: bool = false;
+
+    fn current() -> &'static Self;
+    fn replace(new: Self);
+
+    fn verify(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // hides secrets before the config leaves the bot
+    fn redact(&mut self) {}
+
+    // puts back anything redact has hidden, taken from the current value
+    fn unredact(&mut self, _current: &Self) {}
+
+    // unified diff from the file on disk to the current value, with secrets hidden on both sides
+    fn diff(&self) -> Option<String> {
+        if !self.is_staged() {
+            return None;
+        }
+
+        let old = match fs::read_to_string(Self::path()) {
+            Ok(content) => match serde_json::from_str::<Self>(&jsonc_to_json(&content)) {
+                Ok(mut val) => {
+                    val.redact();
+                    val.serialized()
+                }
+                // the file as it is could hold secrets
+                Err(_) => format!("// {}.jsonc could not be parsed\n", Self::NAME),
+            },
+            Err(_) => String::new(),
+        };
+
+        let mut new = self.copy();
+        new.redact();
+
+        unified_diff(
+            &old,
+            &new.serialized(),
+            &format!("{}.jsonc (saved)", Self::NAME),
+            &format!("{}.jsonc (live)", Self::NAME),
+        )
+    }
+
+    fn copy(&self) -> Self {
+        serde_json::from_value(serde_json::to_value(self).unwrap()).unwrap()
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut new =
+            serde_json::from_str::<Self>(&jsonc_to_json(content)).map_err(|e| e.to_string())?;
+        new.unredact(Self::current());
+        new.verify()?;
+        Ok(new)
+    }
+}
+
+// type erased access to a live config, so modules can list the configs they own
+pub trait ConfigHandle: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn staged(&self) -> bool;
+    fn export(&self) -> String;
+    // diff from the current value to the content, None if they are the same
+    fn check(&self, content: &str) -> Result<Option<String>, String>;
+    fn apply(&self, content: &str) -> Result<(), String>;
+    fn file(&self) -> ConfigFile;
+}
+
+pub struct LiveHandle<C>(PhantomData<fn() -> C>);
+
+impl<C: LiveConfig> LiveHandle<C> {
+    pub fn boxed() -> Box<dyn ConfigHandle> {
+        Box::new(Self(PhantomData))
+    }
+}
+
+impl<C: LiveConfig> ConfigHandle for LiveHandle<C> {
+    fn name(&self) -> &'static str {
+        C::NAME
+    }
+
+    fn staged(&self) -> bool {
+        C::STAGED
+    }
+
+    fn export(&self) -> String {
+        let mut copy = C::current().copy();
+        copy.redact();
+        copy.serialized()
+    }
+
+    fn check(&self, content: &str) -> Result<Option<String>, String> {
+        let new = C::parse(content)?;
+
+        let mut old = C::current().copy();
+        old.redact();
+        let mut new_redacted = new.copy();
+        new_redacted.redact();
+
+        Ok(unified_diff(
+            &old.serialized(),
+            &new_redacted.serialized(),
+            &format!("{}.jsonc (live)", C::NAME),
+            &format!("{}.jsonc (import)", C::NAME),
+        ))
+    }
+
+    fn apply(&self, content: &str) -> Result<(), String> {
+        C::replace(C::parse(content)?);
+
+        if !C::STAGED {
+            C::current().smart_save();
+        }
+
+        Ok(())
+    }
+
+    fn file(&self) -> ConfigFile {
+        ConfigFile::of::<C>()
+    }
+}
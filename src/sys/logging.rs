@@ -0,0 +1,135 @@
+use std::{collections::BTreeMap, io};
+
+use serde::{Deserialize, Serialize};
+use serde_default::DefaultFromSerde;
+use serde_inline_default::serde_inline_default;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use super::Config;
+
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, DefaultFromSerde, Hash)]
+pub struct LoggingConfig {
+    // level for everything not listed in targets
+    #[serde_inline_default("info".to_string())]
+    pub level: String,
+    // level per target, e.g. "merlin::sys": "debug", overridden by the RUST_LOG env
+    #[serde_inline_default(BTreeMap::from([("serenity".to_string(), "warn".to_string())]))]
+    pub targets: BTreeMap<String, String>,
+    #[serde_inline_default(LogFormat::Pretty)]
+    pub format: LogFormat,
+    // also write logs to rotating files, disabled if null
+    #[serde_inline_default(None)]
+    pub file: Option<LogFile>,
+}
+
+#[derive(Serialize, Deserialize, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, Hash)]
+pub struct LogFile {
+    pub directory: String,
+    #[serde_inline_default("merlin.log".to_string())]
+    pub prefix: String,
+    #[serde_inline_default(LogRotation::Daily)]
+    pub rotation: LogRotation,
+}
+
+#[derive(Serialize, Deserialize, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl Config for LoggingConfig {
+    const NAME: &'static str = "logging";
+    const NOTE: &'static str = "Log levels and outputs, levels are trace, debug, info, warn, error or off\nformat is pretty or json, file rotation is minutely, hourly, daily or never";
+}
+
+impl LoggingConfig {
+    // the guard must be held until exit, or buffered lines to the log file are lost
+    pub fn setup() -> Option<WorkerGuard> {
+        let config = Self::load();
+
+        let filter = || {
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+                EnvFilter::new(
+                    [config.level.clone()]
+                        .into_iter()
+                        .chain(
+                            config
+                                .targets
+                                .iter()
+                                .map(|(target, level)| format!("{target}={level}")),
+                        )
+                        .collect::<Vec<_>>()
+                        .join(","),
+                )
+            })
+        };
+
+        // stderr, so the output of cli commands stays clean
+        let stderr = match config.format {
+            LogFormat::Pretty => fmt::layer()
+                .with_writer(io::stderr)
+                .with_filter(filter())
+                .boxed(),
+            LogFormat::Json => fmt::layer()
+                .json()
+                .with_writer(io::stderr)
+                .with_filter(filter())
+                .boxed(),
+        };
+
+        let (file, guard) = match &config.file {
+            Some(file) => {
+                let appender = RollingFileAppender::new(
+                    match file.rotation {
+                        LogRotation::Minutely => Rotation::MINUTELY,
+                        LogRotation::Hourly => Rotation::HOURLY,
+                        LogRotation::Daily => Rotation::DAILY,
+                        LogRotation::Never => Rotation::NEVER,
+                    },
+                    &file.directory,
+                    &file.prefix,
+                );
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+
+                let layer = match config.format {
+                    LogFormat::Pretty => fmt::layer()
+                        .with_ansi(false)
+                        .with_writer(writer)
+                        .with_filter(filter())
+                        .boxed(),
+                    LogFormat::Json => fmt::layer()
+                        .json()
+                        .with_writer(writer)
+                        .with_filter(filter())
+                        .boxed(),
+                };
+
+                (Some(layer), Some(guard))
+            }
+            None => (None, None),
+        };
+
+        let _ = tracing_subscriber::registry()
+            .with(stderr)
+            .with(file)
+            .try_init();
+
+        guard
+    }
+}
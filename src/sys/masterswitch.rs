@@ -1,9 +1,13 @@
-use std::{collections::HashMap, hash::Hash, sync::OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::OnceLock,
+};
 
 use serde::{Deserialize, Serialize};
-use serenity::all::{Context, Message};
+use serenity::all::{GuildId, Message};
 
-use super::{Clearance, Config};
+use super::{Clearance, CommandHandler, Config, Context, GuildSwitch, LiveConfig};
 
 static mut SWITCH: OnceLock<MasterSwitch> = OnceLock::new();
 
@@ -27,6 +31,10 @@ impl MasterSwitch {
         unsafe { SWITCH.get_mut() }.unwrap().0.get_mut(module)
     }
 
+    pub fn get_self() -> &'static Self {
+        unsafe { SWITCH.get() }.unwrap()
+    }
+
     pub fn get_mut_self() -> &'static mut Self {
         unsafe { SWITCH.get_mut() }.unwrap()
     }
@@ -36,6 +44,114 @@ impl MasterSwitch {
             .is_some_and(|got| command.is_none() || got.commands.contains_key(command.unwrap()))
     }
 
+    pub fn is_global(module: &str, command: Option<&str>) -> bool {
+        command.is_some_and(|cmd| CommandHandler::is_global(module, cmd))
+    }
+
+    // whether a module or command is enabled, taking the guild's overrides into account
+    pub fn is_enabled(module: &str, command: Option<&str>, guild: Option<GuildId>) -> bool {
+        let permod = match Self::get(module) {
+            Some(permod) => permod,
+            None => return false,
+        };
+
+        let enabled = match command {
+            Some(cmd) => match permod.commands.get(cmd) {
+                Some(percmd) => percmd.enabled,
+                None => return false,
+            },
+            None => permod.enabled,
+        };
+
+        guild
+            .filter(|_| !Self::is_global(module, command))
+            .and_then(|guild| GuildSwitch::overlay(guild, module, command))
+            .and_then(|(enabled, _)| enabled)
+            .unwrap_or(enabled)
+    }
+
+    // the global rules are checked first, the guild's own rules are appended and so take priority
+    // except for global commands, which only follow the global rules
+    pub async fn is_allowed(
+        module: &str,
+        command: Option<&str>,
+        ctx: &Context,
+        msg: &Message,
+    ) -> bool {
+        let permod = Self::get(module).unwrap();
+
+        let allowed = match command {
+            Some(cmd) => &permod.commands.get(cmd).unwrap().allowed,
+            None => &permod.allowed,
+        };
+
+        let enabled = Self::is_enabled(module, command, msg.guild_id);
+
+        if Self::is_global(module, command) {
+            return Clearance::is_allowed_globally(allowed, ctx, msg)
+                .await
+                .map(|b| enabled && b)
+                .unwrap_or(enabled);
+        }
+
+        let rules = match msg
+            .guild_id
+            .and_then(|guild| GuildSwitch::overlay(guild, module, command))
+        {
+            Some((_, overlay)) if !overlay.is_empty() => [allowed.as_slice(), overlay].concat(),
+            _ => allowed.clone(),
+        };
+
+        Clearance::is_allowed(&rules, ctx, msg)
+            .await
+            .map(|b| enabled && b)
+            .unwrap_or(enabled)
+    }
+
+    // presets the global commands depend on, guilds cannot redefine them
+    pub fn global_presets() -> HashSet<String> {
+        let mut out = HashSet::new();
+
+        for (module, permod) in Self::get_self().0.iter() {
+            let mut global_module = module == "core";
+
+            for (cmd, percmd) in permod.commands.iter() {
+                // the global parts of these are checked with is_allowed_globally
+                if Self::is_global(module, Some(cmd))
+                    || (module == "core"
+                        && matches!(cmd.as_str(), "perms" | "switch" | "clearance"))
+                {
+                    global_module = true;
+                    Clearance::presets_used(&percmd.allowed, &mut out);
+                }
+            }
+
+            // global commands also pass the global rules of their module
+            if global_module {
+                Clearance::presets_used(&permod.allowed, &mut out);
+            }
+        }
+
+        out
+    }
+
+    // only the global rules and presets, so rules granted by a guild cannot be used to change global options
+    pub async fn is_allowed_globally(
+        module: &str,
+        command: &str,
+        ctx: &Context,
+        msg: &Message,
+    ) -> bool {
+        let permod = Self::get(module).unwrap();
+        permod.is_allowed(ctx, msg).await
+            && permod
+                .commands
+                .get(command)
+                .unwrap()
+                .is_allowed(ctx, msg)
+                .await
+    }
+
     pub fn setup() {
         let _ = unsafe { SWITCH.set(Self::load()) };
     }
@@ -80,6 +196,56 @@ impl MasterSwitch {
 impl Config for MasterSwitch {
     const NAME: &'static str = "switch";
     const NOTE: &'static str = "Master switch for each module";
+    const HISTORY: bool = true;
+}
+
+impl LiveConfig for MasterSwitch {
+    const STAGED: bool = true;
+
+    fn current() -> &'static Self {
+        Self::get_self()
+    }
+
+    fn replace(new: Self) {
+        unsafe { SWITCH = OnceLock::new() };
+        let _ = unsafe { SWITCH.set(new) };
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        if let Some(core) = self.0.get("core") {
+            if !core.enabled || core.commands.get("switch").is_some_and(|cmd| !cmd.enabled) {
+                return Err("core and core.switch cannot be disabled.".to_string());
+            }
+        }
+
+        for (module, permod) in self.0.iter() {
+            if !Clearance::validate(
+                &permod
+                    .allowed
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>(),
+                None,
+            ) {
+                return Err(format!("Invalid rules in module {module}."));
+            }
+
+            for (cmd, percmd) in permod.commands.iter() {
+                if !Clearance::validate(
+                    &percmd
+                        .allowed
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>(),
+                    None,
+                ) {
+                    return Err(format!("Invalid rules in command {module}.{cmd}."));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -126,7 +292,7 @@ impl Default for PerCommandConfig {
 
 impl PerModuleConfig {
     pub async fn is_allowed(&self, ctx: &Context, msg: &Message) -> bool {
-        Clearance::is_allowed(&self.allowed, ctx, msg)
+        Clearance::is_allowed_globally(&self.allowed, ctx, msg)
             .await
             .map(|b| self.enabled && b)
             .unwrap_or(self.enabled)
@@ -135,7 +301,7 @@ impl PerModuleConfig {
 
 impl PerCommandConfig {
     pub async fn is_allowed(&self, ctx: &Context, msg: &Message) -> bool {
-        Clearance::is_allowed(&self.allowed, ctx, msg)
+        Clearance::is_allowed_globally(&self.allowed, ctx, msg)
             .await
             .map(|b| self.enabled && b)
             .unwrap_or(self.enabled)
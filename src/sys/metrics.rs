@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+// upper bounds of the latency histogram buckets, in seconds
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static COMMANDS: Mutex<BTreeMap<String, CommandStats>> = Mutex::new(BTreeMap::new());
+static DENIALS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+#[derive(Default)]
+struct CommandStats {
+    count: u64,
+    sum: f64,
+    buckets: [u64; BUCKETS.len()],
+}
+
+// counters in the prometheus text format, keyed by module.command
+pub struct Metrics;
+
+impl Metrics {
+    pub fn record(command: &str, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let mut commands = COMMANDS.lock().unwrap();
+        let stats = commands.entry(command.to_string()).or_default();
+
+        stats.count += 1;
+        stats.sum += secs;
+        for (bucket, le) in stats.buckets.iter_mut().zip(BUCKETS) {
+            if secs <= le {
+                *bucket += 1;
+            }
+        }
+    }
+
+    pub fn deny(command: &str) {
+        *DENIALS
+            .lock()
+            .unwrap()
+            .entry(command.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn render(out: &mut String) {
+        let commands = COMMANDS.lock().unwrap();
+
+        out.push_str("# HELP merlin_command_invocations_total Commands run, by module.command.\n");
+        out.push_str("# TYPE merlin_command_invocations_total counter\n");
+        for (command, stats) in commands.iter() {
+            writeln!(
+                out,
+                "merlin_command_invocations_total{{command=\"{command}\"}} {}",
+                stats.count
+            )
+            .unwrap();
+        }
+
+        out.push_str("# HELP merlin_command_duration_seconds Time taken to run commands.\n");
+        out.push_str("# TYPE merlin_command_duration_seconds histogram\n");
+        for (command, stats) in commands.iter() {
+            for (le, count) in BUCKETS.iter().zip(stats.buckets) {
+                writeln!(
+                    out,
+                    "merlin_command_duration_seconds_bucket{{command=\"{command}\",le=\"{le}\"}} {count}"
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                "merlin_command_duration_seconds_bucket{{command=\"{command}\",le=\"+Inf\"}} {}",
+                stats.count
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "merlin_command_duration_seconds_sum{{command=\"{command}\"}} {}",
+                stats.sum
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "merlin_command_duration_seconds_count{{command=\"{command}\"}} {}",
+                stats.count
+            )
+            .unwrap();
+        }
+
+        drop(commands);
+
+        out.push_str(
+            "# HELP merlin_permission_denials_total Commands refused by permission rules.\n",
+        );
+        out.push_str("# TYPE merlin_permission_denials_total counter\n");
+        for (command, count) in DENIALS.lock().unwrap().iter() {
+            writeln!(
+                out,
+                "merlin_permission_denials_total{{command=\"{command}\"}} {count}"
+            )
+            .unwrap();
+        }
+    }
+}
@@ -1,10 +1,24 @@
 mod clearance;
 mod command;
 mod config;
+mod configfile;
+mod console;
+mod context;
+mod guildclearance;
+mod guildswitch;
 mod handler;
+mod history;
+mod liveconfig;
+mod logging;
 mod masterswitch;
+mod metrics;
 mod module;
 mod options;
+mod permcheck;
+mod permmatrix;
+mod shutdown;
+mod staging;
+mod store;
 
 #[cfg(feature = "mongo")]
 mod mongo;
@@ -12,10 +26,24 @@ mod mongo;
 pub use mongo::*;
 
 pub use command::Command;
-pub use config::Config;
+pub use config::{config_dir, unified_diff, Config};
+pub use configfile::ConfigFile;
+pub use console::*;
+pub use context::Context;
 pub use handler::CommandHandler;
+pub use history::History;
+pub use liveconfig::{ConfigHandle, LiveConfig, LiveHandle};
 pub use module::Module;
 
 pub use clearance::*;
+pub use guildclearance::*;
+pub use guildswitch::*;
+pub use logging::*;
 pub use masterswitch::*;
+pub use metrics::Metrics;
 pub use options::*;
+pub use permcheck::PermCheck;
+pub use permmatrix::*;
+pub use shutdown::*;
+pub use staging::*;
+pub use store::*;
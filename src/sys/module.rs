@@ -1,11 +1,12 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
-use serenity::{
-    all::{Context, Message},
-    async_trait,
-};
+use serenity::{all::Message, async_trait};
+use tracing::info;
 
-use super::{Command, CommandHandler, MasterSwitch, PerCommandConfig, PerModuleConfig};
+use super::{
+    Command, CommandHandler, ConfigHandle, Context, Index, MasterSwitch, Metrics, Migration,
+    PerCommandConfig, PerModuleConfig, Storage, StoreError,
+};
 
 #[async_trait]
 pub trait Module: Sync + Send + 'static {
@@ -20,20 +21,43 @@ pub trait Module: Sync + Send + 'static {
     async fn run(&self, args: &[&str], ctx: &Context, msg: &Message) {
         if !args.is_empty() {
             if let Some(cmd) = self.commands().get(args[0].to_lowercase().as_str()) {
-                let permod = MasterSwitch::get(self.name()).unwrap();
-
-                if !permod.is_allowed(ctx, msg).await
-                    || !permod
-                        .commands
-                        .get(args[0])
-                        .unwrap()
-                        .is_allowed(ctx, msg)
-                        .await
-                {
+                let label = format!("{}.{}", self.name(), cmd.name());
+
+                // the module rules of a guild must not reach commands that act on the whole bot
+                let allowed = if cmd.global() {
+                    MasterSwitch::is_allowed_globally(self.name(), cmd.name(), ctx, msg).await
+                } else {
+                    MasterSwitch::is_allowed(self.name(), None, ctx, msg).await
+                        && MasterSwitch::is_allowed(self.name(), Some(cmd.name()), ctx, msg).await
+                };
+
+                if !allowed {
+                    Metrics::deny(&label);
+                    info!(command = label, user = %msg.author.id, guild = ?msg.guild_id, "permission denied");
+                    return;
+                }
+
+                if self.needs_storage() && !Storage::is_healthy() {
+                    let _ = ctx
+                        .reply(msg, "The database is unavailable, try again later.")
+                        .await;
                     return;
                 }
 
-                if !cmd.run(&args[1..], ctx, msg).await {
+                let start = Instant::now();
+                let success = cmd.run(&args[1..], ctx, msg).await;
+                let elapsed = start.elapsed();
+                Metrics::record(&label, elapsed);
+                info!(
+                    command = label,
+                    user = %msg.author.id,
+                    guild = ?msg.guild_id,
+                    elapsed_ms = elapsed.as_millis() as u64,
+                    success,
+                    "command finished"
+                );
+
+                if !success {
                     CommandHandler::help(&[&[self.name()], args].concat(), ctx, msg).await
                 }
 
@@ -59,6 +83,11 @@ pub trait Module: Sync + Send + 'static {
         CommandHandler::help(&[self.name()], ctx, msg).await
     }
 
+    // commands are refused while the storage is unavailable
+    fn needs_storage(&self) -> bool {
+        false
+    }
+
     async fn setup(&mut self) {}
     async fn reload(&mut self) {}
 
@@ -73,6 +102,31 @@ pub trait Module: Sync + Send + 'static {
         }
     }
 
+    // extra rows for the permission matrix, each row is a label and the rule lists it must pass
+    async fn permission_rows(&self) -> Result<Vec<(String, Vec<Vec<String>>)>, StoreError> {
+        Ok(Vec::new())
+    }
+
+    // configs owned by the module that can be exported and imported
+    fn configs(&self) -> Vec<Box<dyn ConfigHandle>> {
+        Vec::new()
+    }
+
+    // changes to stored documents, applied before the module is set up
+    fn migrations(&self) -> Vec<Migration> {
+        Vec::new()
+    }
+
+    // (collection, indexes) created after the migrations
+    fn indexes(&self) -> Vec<(&'static str, &'static [Index])> {
+        Vec::new()
+    }
+
+    // (collection, index names) no longer declared, dropped before the indexes are created
+    fn dropped_indexes(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        Vec::new()
+    }
+
     fn percmds(&self) -> HashMap<String, PerCommandConfig> {
         let mut out = HashMap::new();
 
@@ -1,63 +1,200 @@
-use std::sync::OnceLock;
+use std::{
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
 
-use mongodb::{bson::Document, options::ClientOptions, Client, Collection, Database};
+use mongodb::{
+    bson::doc,
+    options::{ClientOptions, Tls, TlsOptions},
+    Client, Database,
+};
 use serde::{Deserialize, Serialize};
 use serde_default::DefaultFromSerde;
 use serde_inline_default::serde_inline_default;
 
+use tracing::{error, info, warn};
+
 use crate::Config;
 
-use super::{
-    collections::{COUNTERS_DESER, COUNTERS_SER, DATABASE},
-    Counter,
-};
+use super::collections::DATABASE;
+
+// the longest wait between two connection attempts on startup
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
 
 pub struct Mongo;
 
 impl Mongo {
+    // retries with backoff until the server answers or the retries run out, the bot starts either way
     pub async fn load() {
         let config = MongoConfig::load();
+        let mut backoff = Duration::from_secs(1);
 
-        let client = Client::with_options(
-            ClientOptions::parse(&config.address)
-                .await
-                .unwrap_or_else(|_| panic!("could not resolve mongodb host {}", config.address)),
-        )
-        .unwrap();
+        for attempt in 0..=config.startup_retries {
+            if attempt != 0 {
+                warn!(
+                    attempt,
+                    retries = config.startup_retries,
+                    wait_secs = backoff.as_secs(),
+                    "mongodb unavailable, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
 
-        let db = client.database(&config.db);
+            if Self::connect(&config).await.is_some() && Self::server_info().await.is_some() {
+                info!(db = config.db, "mongodb connected");
+                return;
+            }
+        }
 
-        let counters_deser: Collection<Counter> = db.collection("counters");
-        let counters_ser: Collection<Document> = db.collection("counters");
-
-        unsafe { DATABASE.set(db) }.unwrap();
-        unsafe { COUNTERS_DESER.set(counters_deser) }.unwrap();
-        unsafe { COUNTERS_SER.set(counters_ser) }.unwrap();
+        error!(
+            address = config.address,
+            "mongodb unavailable, starting without it, the connection is retried in the background"
+        );
     }
 
-    pub async fn reload() {
+    pub fn unload() {
         unsafe { DATABASE = OnceLock::new() };
-        unsafe { COUNTERS_DESER = OnceLock::new() };
-        unsafe { COUNTERS_SER = OnceLock::new() };
+    }
+
+    // None until a client could be created
+    pub fn database() -> Option<&'static Database> {
+        unsafe { DATABASE.get() }
+    }
+
+    // creates the client if there is none yet, it reconnects by itself after that
+    async fn connect(config: &MongoConfig) -> Option<&'static Database> {
+        if let Some(db) = Self::database() {
+            return Some(db);
+        }
 
-        Self::load().await;
+        let client = match config
+            .client_options()
+            .await
+            .and_then(|options| Client::with_options(options).map_err(|e| e.to_string()))
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(
+                    address = config.address,
+                    error = e,
+                    "could not create mongodb client"
+                );
+                return None;
+            }
+        };
+
+        let _ = unsafe { DATABASE.set(client.database(&config.db)) };
+        Self::database()
     }
 
-    pub fn database() -> &'static Database {
-        unsafe { DATABASE.get() }.unwrap()
+    // (round trip time, server version)
+    pub async fn server_info() -> Option<(Duration, String)> {
+        let db = match Self::database() {
+            Some(db) => db,
+            None => Self::connect(&MongoConfig::load()).await?,
+        };
+
+        let start = Instant::now();
+        let info = match db.run_command(doc! {"buildInfo": 1}).await {
+            Ok(info) => info,
+            Err(e) => {
+                warn!(error = %e, "mongodb ping failed");
+                return None;
+            }
+        };
+
+        Some((start.elapsed(), info.get_str("version").ok()?.to_string()))
     }
 }
 
 #[serde_inline_default]
 #[derive(Serialize, Deserialize, DefaultFromSerde, Hash)]
-struct MongoConfig {
+pub struct MongoConfig {
     #[serde_inline_default("mongodb://localhost:27017".to_string())]
     pub address: String,
     #[serde_inline_default("merlin".to_string())]
     pub db: String,
+    // override the credentials in the address if set
+    #[serde_inline_default(None)]
+    pub username: Option<String>,
+    #[serde_inline_default(None)]
+    pub password: Option<String>,
+    // database the user is defined in, usually admin
+    #[serde_inline_default(None)]
+    #[serde(rename = "auth-source")]
+    pub auth_source: Option<String>,
+    #[serde_inline_default(false)]
+    pub tls: bool,
+    // uses the system roots if null
+    #[serde_inline_default(None)]
+    #[serde(rename = "tls-ca-file")]
+    pub tls_ca_file: Option<String>,
+    #[serde_inline_default(false)]
+    #[serde(rename = "tls-allow-invalid-certificates")]
+    pub tls_allow_invalid_certificates: bool,
+    #[serde_inline_default(10)]
+    #[serde(rename = "connect-timeout-secs")]
+    pub connect_timeout_secs: u32,
+    // how long a query waits for a server before failing
+    #[serde_inline_default(5)]
+    #[serde(rename = "server-selection-timeout-secs")]
+    pub server_selection_timeout_secs: u32,
+    #[serde_inline_default(10)]
+    #[serde(rename = "max-pool-size")]
+    pub max_pool_size: u32,
+    #[serde_inline_default(0)]
+    #[serde(rename = "min-pool-size")]
+    pub min_pool_size: u32,
+    // connection attempts on startup before starting without mongodb
+    #[serde_inline_default(5)]
+    #[serde(rename = "startup-retries")]
+    pub startup_retries: u32,
 }
 
 impl Config for MongoConfig {
     const NAME: &'static str = "mongodb";
     const NOTE: &'static str = "MongoDB options";
 }
+
+impl MongoConfig {
+    async fn client_options(&self) -> Result<ClientOptions, String> {
+        let mut options = ClientOptions::parse(&self.address)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        options.app_name = Some(env!("CARGO_PKG_NAME").to_string());
+        options.connect_timeout = Some(Duration::from_secs(self.connect_timeout_secs.into()));
+        options.server_selection_timeout = Some(Duration::from_secs(
+            self.server_selection_timeout_secs.into(),
+        ));
+        options.max_pool_size = Some(self.max_pool_size);
+        options.min_pool_size = Some(self.min_pool_size);
+
+        if self.username.is_some() || self.password.is_some() || self.auth_source.is_some() {
+            let mut credential = options.credential.take().unwrap_or_default();
+            if self.username.is_some() {
+                credential.username = self.username.clone();
+            }
+            if self.password.is_some() {
+                credential.password = self.password.clone();
+            }
+            if self.auth_source.is_some() {
+                credential.source = self.auth_source.clone();
+            }
+            options.credential = Some(credential);
+        }
+
+        if self.tls {
+            options.tls = Some(Tls::Enabled(
+                TlsOptions::builder()
+                    .ca_file_path(self.tls_ca_file.as_ref().map(PathBuf::from))
+                    .allow_invalid_certificates(self.tls_allow_invalid_certificates)
+                    .build(),
+            ));
+        }
+
+        Ok(options)
+    }
+}
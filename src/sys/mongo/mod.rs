@@ -1,9 +1,5 @@
 mod collections;
-mod counter;
 mod details;
-mod item;
 
 pub use collections::*;
-pub use counter::Counter;
-pub use details::Mongo;
-pub use item::CollectionItem;
+pub use details::{Mongo, MongoConfig};
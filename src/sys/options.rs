@@ -4,10 +4,13 @@ use serde::{Deserialize, Serialize};
 use serde_default::DefaultFromSerde;
 use serde_inline_default::serde_inline_default;
 
-use super::Config;
+use super::{Config, LiveConfig};
 
 pub static mut MASTER: OnceLock<MasterOptions> = OnceLock::new();
 
+// placeholder for the token in exported configs
+const REDACTED: &str = "REDACTED";
+
 #[serde_inline_default]
 #[derive(Serialize, Deserialize, DefaultFromSerde, Hash)]
 pub struct MasterOptions {
@@ -15,11 +18,59 @@ pub struct MasterOptions {
     pub prefix: String,
     #[serde_inline_default("CHANGE ME".to_string())]
     pub token: String,
+    // number of snapshots kept for each config
+    #[serde_inline_default(20)]
+    #[serde(rename = "history-limit")]
+    pub history_limit: usize,
+    // snapshots older than this are removed, 0 keeps them forever
+    #[serde_inline_default(30)]
+    #[serde(rename = "history-days")]
+    pub history_days: u64,
+    // address for the /healthz and /metrics endpoints, e.g. 127.0.0.1:9100, disabled if null
+    #[serde_inline_default(None)]
+    #[serde(rename = "metrics-address")]
+    pub metrics_address: Option<String>,
+    // unix socket for the local console, disabled if null
+    #[serde_inline_default(None)]
+    #[serde(rename = "console-socket")]
+    pub console_socket: Option<String>,
 }
 
 impl Config for MasterOptions {
     const NAME: &'static str = "master";
     const NOTE: &'static str = "master config for merlin";
+    const HISTORY: bool = true;
+}
+
+impl LiveConfig for MasterOptions {
+    const STAGED: bool = true;
+
+    fn current() -> &'static Self {
+        unsafe { MASTER.get() }.unwrap()
+    }
+
+    fn replace(new: Self) {
+        unsafe { MASTER = OnceLock::new() };
+        let _ = unsafe { MASTER.set(new) };
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        if self.prefix.is_empty() {
+            return Err("Prefix cannot be empty.".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn redact(&mut self) {
+        self.token = REDACTED.to_string();
+    }
+
+    fn unredact(&mut self, current: &Self) {
+        if self.token == REDACTED {
+            self.token.clone_from(&current.token);
+        }
+    }
 }
 
 impl MasterOptions {
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use super::{
+    Clearance, CommandHandler, Config, GuildClearance, GuildSwitch, MasterSwitch, Storage,
+};
+
+// checks the rule lists in the config files on disk, without loading them
+// rules kept by modules in the store, such as coords categories, are read from it as well
+pub struct PermCheck;
+
+impl PermCheck {
+    // every problem found, an empty list means the rules are valid
+    pub async fn run() -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let switch = read::<MasterSwitch>(&mut problems);
+        let guild_switch = read::<GuildSwitch>(&mut problems);
+        let clearance = read::<Clearance>(&mut problems);
+        let guild_clearance = read::<GuildClearance>(&mut problems);
+        let stored = stored(&mut problems).await;
+
+        // location, guild the rules apply in, rules
+        let mut lists: Vec<(String, Option<&str>, &[String])> = Vec::new();
+
+        for (module, permod) in switch.0.iter() {
+            lists.push((
+                format!("{} module {module}", MasterSwitch::NAME),
+                None,
+                &permod.allowed,
+            ));
+
+            for (cmd, percmd) in permod.commands.iter() {
+                lists.push((
+                    format!("{} command {module}.{cmd}", MasterSwitch::NAME),
+                    None,
+                    &percmd.allowed,
+                ));
+            }
+        }
+
+        for (guild, modules) in guild_switch.0.iter() {
+            for (module, permod) in modules.iter() {
+                lists.push((
+                    format!("{} server {guild} module {module}", GuildSwitch::NAME),
+                    Some(guild),
+                    &permod.allowed,
+                ));
+
+                for (cmd, percmd) in permod.commands.iter() {
+                    lists.push((
+                        format!(
+                            "{} server {guild} command {module}.{cmd}",
+                            GuildSwitch::NAME
+                        ),
+                        Some(guild),
+                        &percmd.allowed,
+                    ));
+                }
+            }
+        }
+
+        for (preset, rules) in clearance.0.iter() {
+            lists.push((format!("{} preset {preset}", Clearance::NAME), None, rules));
+        }
+
+        for (guild, presets) in guild_clearance.0.iter() {
+            for (preset, rules) in presets.iter() {
+                lists.push((
+                    format!("{} server {guild} preset {preset}", GuildClearance::NAME),
+                    Some(guild),
+                    rules,
+                ));
+            }
+        }
+
+        // a row also lists the rules of the rows it is nested in, those are only reported once
+        let mut seen = HashSet::new();
+        for (location, layers) in stored.iter() {
+            for rules in layers.iter() {
+                if seen.insert(rules) {
+                    lists.push((location.clone(), None, rules));
+                }
+            }
+        }
+
+        let merged = |guild: Option<&str>| {
+            let mut merged = clearance.clone();
+            if let Some(presets) = guild.and_then(|guild| guild_clearance.0.get(guild)) {
+                merged.0.extend(presets.clone());
+            }
+            merged
+        };
+
+        for (location, guild, rules) in lists.iter() {
+            if !Clearance::validate(&rules.iter().map(String::as_str).collect::<Vec<_>>(), None) {
+                problems.push(format!("{location}: invalid rules {rules:?}"));
+                continue;
+            }
+
+            let presets = merged(*guild);
+            for preset in rules.iter().filter_map(|rule| rule.strip_prefix('?')) {
+                if !presets.0.contains_key(preset) {
+                    problems.push(format!("{location}: unknown preset {preset}"));
+                }
+            }
+        }
+
+        // cycles are checked as seen from each server, as a server preset may refer back to a global one
+        for guild in [None]
+            .into_iter()
+            .chain(guild_clearance.0.keys().map(|guild| Some(guild.as_str())))
+        {
+            let presets = merged(guild);
+            let mut names = presets.0.keys().collect::<Vec<_>>();
+            names.sort();
+
+            for preset in names {
+                if !presets.no_cycles(preset, &mut HashSet::new()) {
+                    problems.push(match guild {
+                        Some(guild) => {
+                            format!("preset {preset} is defined circularly in server {guild}")
+                        }
+                        None => format!("preset {preset} is defined circularly"),
+                    });
+                }
+            }
+        }
+
+        problems.sort();
+        problems.dedup();
+        problems
+    }
+}
+
+// the permission rows of every module, labelled as module and row
+async fn stored(problems: &mut Vec<String>) -> Vec<(String, Vec<Vec<String>>)> {
+    match Storage::open_existing().await {
+        Ok(true) => {}
+        Ok(false) => return Vec::new(),
+        Err(e) => {
+            problems.push(format!("storage: {e}"));
+            return Vec::new();
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut modules = CommandHandler::available();
+    modules.sort_by_key(|module| module.name().to_string());
+
+    for module in modules {
+        match module.permission_rows().await {
+            Ok(module_rows) => rows.extend(
+                module_rows
+                    .into_iter()
+                    .map(|(label, layers)| (format!("{} {label}", module.name()), layers)),
+            ),
+            Err(e) => problems.push(format!(
+                "{}: could not read stored rules: {e}",
+                module.name()
+            )),
+        }
+    }
+
+    rows
+}
+
+// a missing file is checked as its defaults
+fn read<C: Config>(problems: &mut Vec<String>) -> C {
+    match C::read() {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => {
+            problems.push(format!("{}: could not be parsed: {e}", C::NAME));
+            C::default()
+        }
+        None => C::default(),
+    }
+}
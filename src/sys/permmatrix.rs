@@ -0,0 +1,355 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use serenity::all::GuildId;
+
+use super::{Clearance, CommandHandler, GuildClearance, GuildSwitch, MasterSwitch, StoreError};
+
+// a column of the matrix, every subject is assumed to hold nothing but itself
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Subject {
+    Everyone,
+    Preset(String),
+    Role(String),
+}
+
+impl Subject {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Everyone => "everyone".to_string(),
+            Self::Preset(preset) => format!("?{preset}"),
+            Self::Role(role) => format!("&{role}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Off,
+    Allowed { conditional: bool },
+    Denied { conditional: bool },
+}
+
+impl Cell {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Allowed { conditional: false } => "yes",
+            Self::Allowed { conditional: true } => "yes*",
+            Self::Denied { conditional: false } => "no",
+            Self::Denied { conditional: true } => "no*",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum MatrixFormat {
+    Csv,
+    Markdown,
+}
+
+impl MatrixFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+// label, enabled, guild the row is resolved in, rule lists to pass
+type Scope = (String, bool, Option<GuildId>, Vec<Vec<String>>);
+
+pub struct PermMatrix {
+    pub subjects: Vec<Subject>,
+    pub rows: Vec<(String, Vec<Cell>)>,
+}
+
+impl PermMatrix {
+    // every module.command from the master switch, followed by any extra scopes provided by the loaded modules
+    pub async fn build() -> Result<Self, StoreError> {
+        let mut scopes: Vec<Scope> = Vec::new();
+
+        let mut modules = MasterSwitch::get_self().0.iter().collect::<Vec<_>>();
+        modules.sort_by_key(|entry| entry.0);
+
+        for (module_label, permod) in modules {
+            let mut commands = permod.commands.iter().collect::<Vec<_>>();
+            commands.sort_by_key(|entry| entry.0);
+
+            for (cmd_label, percmd) in commands {
+                scopes.push((
+                    format!("{module_label}.{cmd_label}"),
+                    permod.enabled && percmd.enabled,
+                    None,
+                    vec![permod.allowed.clone(), percmd.allowed.clone()],
+                ));
+            }
+        }
+
+        // commands with server overrides get an extra row for each server
+        let mut guilds = GuildSwitch::get_self().0.iter().collect::<Vec<_>>();
+        guilds.sort_by_key(|entry| entry.0);
+
+        for (guild, overrides) in guilds {
+            let guild_id = guild
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+                .map(GuildId::new);
+
+            let mut overrides = overrides.iter().collect::<Vec<_>>();
+            overrides.sort_by_key(|entry| entry.0);
+
+            for (module_label, overlay) in overrides {
+                let permod = match MasterSwitch::get(module_label) {
+                    Some(permod) => permod,
+                    None => continue,
+                };
+
+                let mut commands = permod.commands.iter().collect::<Vec<_>>();
+                commands.sort_by_key(|entry| entry.0);
+
+                for (cmd_label, percmd) in commands {
+                    // the bot ignores server overrides and presets for these, so the global row is all there is
+                    if MasterSwitch::is_global(module_label, Some(cmd_label)) {
+                        continue;
+                    }
+
+                    let cmd_overlay = overlay.commands.get(cmd_label);
+
+                    if overlay.enabled.is_none()
+                        && overlay.allowed.is_empty()
+                        && cmd_overlay.is_none()
+                    {
+                        continue;
+                    }
+
+                    scopes.push((
+                        format!("{module_label}.{cmd_label} (server {guild})"),
+                        overlay.enabled.unwrap_or(permod.enabled)
+                            && cmd_overlay
+                                .and_then(|cmd| cmd.enabled)
+                                .unwrap_or(percmd.enabled),
+                        guild_id,
+                        vec![
+                            [permod.allowed.as_slice(), overlay.allowed.as_slice()].concat(),
+                            [
+                                percmd.allowed.as_slice(),
+                                cmd_overlay
+                                    .map(|cmd| cmd.allowed.as_slice())
+                                    .unwrap_or_default(),
+                            ]
+                            .concat(),
+                        ],
+                    ));
+                }
+            }
+        }
+
+        let mut modules = CommandHandler::get().modules.values().collect::<Vec<_>>();
+        modules.sort_by_key(|module| module.name().to_string());
+
+        for module in modules {
+            for (label, layers) in module.permission_rows().await? {
+                scopes.push((format!("{}:{label}", module.name()), true, None, layers));
+            }
+        }
+
+        let mut roles = BTreeSet::new();
+        for rules in scopes
+            .iter()
+            .flat_map(|(_, _, _, layers)| layers.iter().map(Vec::as_slice))
+            .chain(
+                Clearance::list_all()
+                    .into_iter()
+                    .map(|preset| Clearance::get(preset)),
+            )
+            .chain(
+                GuildClearance::get_self()
+                    .0
+                    .values()
+                    .flat_map(|presets| presets.values().map(Vec::as_slice)),
+            )
+        {
+            for rule in rules.iter() {
+                if let Some(role) = rule.get(1..).and_then(|rule| rule.strip_prefix('&')) {
+                    roles.insert(role.to_string());
+                }
+            }
+        }
+
+        let presets = Clearance::list_all()
+            .into_iter()
+            .chain(
+                GuildClearance::get_self()
+                    .0
+                    .values()
+                    .flat_map(|presets| presets.keys()),
+            )
+            .collect::<BTreeSet<_>>();
+
+        let subjects = [Subject::Everyone]
+            .into_iter()
+            .chain(presets.into_iter().map(|p| Subject::Preset(p.to_string())))
+            .chain(roles.into_iter().map(Subject::Role))
+            .collect::<Vec<_>>();
+
+        let rows = scopes
+            .into_iter()
+            .map(|(label, enabled, guild, layers)| {
+                let cells = subjects
+                    .iter()
+                    .map(|subject| {
+                        if !enabled {
+                            return Cell::Off;
+                        }
+
+                        let mut allowed = true;
+                        let mut conditional = false;
+
+                        for layer in layers.iter() {
+                            let (res, cond) = resolve(layer, subject, guild, &mut BTreeSet::new());
+                            allowed &= res.unwrap_or(true);
+                            conditional |= cond;
+                        }
+
+                        if allowed {
+                            Cell::Allowed { conditional }
+                        } else {
+                            Cell::Denied { conditional }
+                        }
+                    })
+                    .collect();
+                (label, cells)
+            })
+            .collect();
+
+        Ok(Self { subjects, rows })
+    }
+
+    pub fn render(&self, format: MatrixFormat) -> String {
+        match format {
+            MatrixFormat::Csv => {
+                let mut out = ["scope".to_string()]
+                    .into_iter()
+                    .chain(self.subjects.iter().map(Subject::label))
+                    .map(|field| csv_escape(&field))
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                for (label, cells) in self.rows.iter() {
+                    write!(
+                        out,
+                        "\n{}",
+                        [csv_escape(label)]
+                            .into_iter()
+                            .chain(cells.iter().map(|cell| cell.label().to_string()))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    )
+                    .unwrap();
+                }
+
+                out.push('\n');
+                out
+            }
+            MatrixFormat::Markdown => {
+                let mut out = format!(
+                    "| scope | {} |\n|---|{}",
+                    self.subjects
+                        .iter()
+                        .map(|subject| format!("`{}`", subject.label()))
+                        .collect::<Vec<_>>()
+                        .join(" | "),
+                    "---|".repeat(self.subjects.len())
+                );
+
+                for (label, cells) in self.rows.iter() {
+                    write!(
+                        out,
+                        "\n| `{label}` | {} |",
+                        cells
+                            .iter()
+                            .map(Cell::label)
+                            .collect::<Vec<_>>()
+                            .join(" | ")
+                    )
+                    .unwrap();
+                }
+
+                out.push_str("\n\n`*` the result also depends on user, server, channel or dm rules.\n`off` the module or command is disabled.\n");
+                out
+            }
+        }
+    }
+}
+
+// walks the rules the same way as Clearance::is_allowed, rules that depend on the message are skipped
+// returns the result, and whether any skipped rule could have overridden it
+fn resolve(
+    rules: &[String],
+    subject: &Subject,
+    guild: Option<GuildId>,
+    visited: &mut BTreeSet<String>,
+) -> (Option<bool>, bool) {
+    let mut conditional = false;
+
+    for entry in rules.iter().rev() {
+        let allowed = match entry.chars().next() {
+            Some('+') => true,
+            Some('-') => false,
+            Some('?') => {
+                let preset = &entry[1..];
+
+                if subject == &Subject::Preset(preset.to_string()) {
+                    return (Some(true), conditional);
+                }
+
+                if !visited.insert(preset.to_string()) {
+                    continue;
+                }
+
+                let rules = guild
+                    .and_then(|guild| GuildClearance::get(guild, preset))
+                    .unwrap_or_else(|| Clearance::get(preset));
+                let (res, cond) = resolve(rules, subject, guild, visited);
+                conditional |= cond;
+
+                if res.is_some() {
+                    return (res, conditional);
+                }
+
+                continue;
+            }
+            _ => continue,
+        };
+
+        match entry.get(1..).unwrap_or_default() {
+            "everyone" | "everywhere" => return (Some(allowed), conditional),
+            role if role.starts_with('&') => {
+                if subject == &Subject::Role(role[1..].to_string()) {
+                    return (Some(allowed), conditional);
+                }
+            }
+            _ => conditional = true,
+        }
+    }
+
+    (None, conditional)
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
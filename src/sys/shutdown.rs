@@ -0,0 +1,93 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serenity::all::ShardManager;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+use super::Staging;
+
+static ACCEPTING: AtomicBool = AtomicBool::new(true);
+static RESTART: AtomicBool = AtomicBool::new(false);
+static DONE: AtomicBool = AtomicBool::new(false);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+// how long to wait for running commands before shutting down anyway
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// held for as long as a command is running
+pub struct InFlight;
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct Shutdown;
+
+impl Shutdown {
+    // None once shutdown has started, new commands should be ignored
+    pub fn enter() -> Option<InFlight> {
+        if !ACCEPTING.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        Some(InFlight)
+    }
+
+    pub fn is_shutting_down() -> bool {
+        !ACCEPTING.load(Ordering::SeqCst)
+    }
+
+    pub fn restart_requested() -> bool {
+        RESTART.load(Ordering::SeqCst)
+    }
+
+    // resolves once shutdown has finished, the client may never return if no shard was connected
+    pub async fn stopped() {
+        while !DONE.load(Ordering::SeqCst) {
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    // stops accepting commands, waits for running ones, then writes uncommitted changes and stops the shards
+    // should be spawned as its own task, the command that started it is waited for as well
+    pub async fn begin(restart: bool, shard_manager: Option<Arc<ShardManager>>) {
+        if ACCEPTING.swap(false, Ordering::SeqCst) {
+            RESTART.store(restart, Ordering::SeqCst);
+        } else {
+            return;
+        }
+
+        info!(restart, "shutdown started, waiting for running commands");
+
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+        while IN_FLIGHT.load(Ordering::SeqCst) != 0 && Instant::now() < deadline {
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        let remaining = IN_FLIGHT.load(Ordering::SeqCst);
+        if remaining != 0 {
+            warn!(remaining, "shutting down with commands still running");
+        }
+
+        let committed = Staging::commit("shutdown", "uncommitted changes at shutdown");
+        if !committed.is_empty() {
+            info!(configs = ?committed, "saved uncommitted changes");
+        }
+
+        match shard_manager {
+            Some(shard_manager) => shard_manager.shutdown_all().await,
+            None => std::process::exit(0),
+        }
+
+        DONE.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{Clearance, Config, LiveConfig, MasterOptions, MasterSwitch, MASTER};
+
+// only the most recent entries are kept in the log
+const COMMIT_LOG_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, Default, Hash)]
+pub struct CommitLog(pub Vec<CommitEntry>);
+
+impl Config for CommitLog {
+    const NAME: &'static str = "commits";
+    const NOTE: &'static str = "Log of committed config changes";
+}
+
+#[derive(Serialize, Deserialize, Clone, Hash)]
+pub struct CommitEntry {
+    pub time: u64,
+    pub author: String,
+    pub message: String,
+    pub configs: Vec<String>,
+}
+
+// changes made by commands to global configs stay in memory until committed
+// per server configs are not staged, they are saved as soon as they are changed
+pub struct Staging;
+
+impl Staging {
+    // (config name, unified diff) for every config with uncommitted changes
+    pub fn diff() -> Vec<(&'static str, String)> {
+        [
+            (MasterSwitch::NAME, MasterSwitch::get_self().diff()),
+            (MasterOptions::NAME, unsafe { MASTER.get() }.unwrap().diff()),
+            (Clearance::NAME, Clearance::get_self().diff()),
+        ]
+        .into_iter()
+        .filter_map(|(name, diff)| Some((name, diff?)))
+        .collect()
+    }
+
+    pub fn staged() -> Vec<&'static str> {
+        [
+            (MasterSwitch::NAME, MasterSwitch::get_self().is_staged()),
+            (
+                MasterOptions::NAME,
+                unsafe { MASTER.get() }.unwrap().is_staged(),
+            ),
+            (Clearance::NAME, Clearance::get_self().is_staged()),
+        ]
+        .into_iter()
+        .filter_map(|(name, staged)| staged.then_some(name))
+        .collect()
+    }
+
+    // writes every staged config to disk, returns the names of configs written
+    pub fn commit(author: &str, message: &str) -> Vec<&'static str> {
+        let staged = Self::staged();
+
+        if staged.is_empty() {
+            return staged;
+        }
+
+        MasterSwitch::write_to_config();
+        MasterOptions::write_to_config();
+        Clearance::write_to_config();
+
+        let mut log = CommitLog::load();
+        log.0.push(CommitEntry {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            author: author.to_string(),
+            message: message.to_string(),
+            configs: staged.iter().map(|name| name.to_string()).collect(),
+        });
+
+        if log.0.len() > COMMIT_LOG_LIMIT {
+            log.0.drain(..log.0.len() - COMMIT_LOG_LIMIT);
+        }
+
+        log.save();
+
+        info!(author, message, configs = ?staged, "committed config changes");
+
+        staged
+    }
+}
@@ -0,0 +1,123 @@
+// CollectionItem is from https://github.com/gmornin/services/blob/master/src/traits/collection_item.rs
+
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use serenity::async_trait;
+
+use super::{Filter, Sort, Storage, StoreError};
+
+// typed access to a collection of the current store
+pub struct Collection<T> {
+    name: &'static str,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Collection<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _item: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub async fn find(&self, filter: &Filter) -> Result<Vec<T>, StoreError> {
+        self.find_sorted(filter, Sort::Natural).await
+    }
+
+    pub async fn find_sorted(&self, filter: &Filter, sort: Sort) -> Result<Vec<T>, StoreError> {
+        track(Storage::get().find(self.name, filter, sort).await)?
+            .into_iter()
+            .map(|doc| Ok(serde_json::from_value(doc)?))
+            .collect()
+    }
+
+    pub async fn find_one(&self, filter: &Filter) -> Result<Option<T>, StoreError> {
+        match track(Storage::get().find_one(self.name, filter).await)? {
+            Some(doc) => Ok(Some(serde_json::from_value(doc)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn count_by(&self, field: &str) -> Result<Vec<(Value, u64)>, StoreError> {
+        track(Storage::get().count_by(self.name, field).await)
+    }
+
+    pub async fn insert_one(&self, id: i64, item: &T) -> Result<(), StoreError> {
+        track(
+            Storage::get()
+                .insert(self.name, id, serde_json::to_value(item)?)
+                .await,
+        )
+    }
+
+    pub async fn replace_one(&self, id: i64, item: &T) -> Result<bool, StoreError> {
+        track(
+            Storage::get()
+                .replace(self.name, id, serde_json::to_value(item)?)
+                .await,
+        )
+    }
+
+    pub async fn upsert_one(&self, id: i64, item: &T) -> Result<(), StoreError> {
+        track(
+            Storage::get()
+                .upsert(self.name, id, serde_json::to_value(item)?)
+                .await,
+        )
+    }
+
+    pub async fn delete_one(&self, id: i64) -> Result<bool, StoreError> {
+        track(Storage::get().delete(self.name, id).await)
+    }
+}
+
+// a store that cannot be reached is marked as such until the next successful ping
+pub(super) fn track<T>(res: Result<T, StoreError>) -> Result<T, StoreError> {
+    if let Err(StoreError::Unavailable(_)) = res {
+        Storage::set_healthy(false);
+    }
+    res
+}
+
+#[async_trait]
+pub trait CollectionItem<D>
+where
+    Self: Sized + Clone + Send + Serialize + DeserializeOwned + Unpin + Sync,
+    D: Into<i64> + Send + 'static,
+{
+    /// update if exist, create or else
+    async fn save_generic(&self, collection: &Collection<Self>) -> Result<(), StoreError> {
+        collection.upsert_one(self.id().into(), self).await
+    }
+
+    /// replaces item
+    async fn save_replace(&self, collection: &Collection<Self>) -> Result<(), StoreError> {
+        collection.replace_one(self.id().into(), self).await?;
+        Ok(())
+    }
+
+    /// creates item
+    async fn save_create(&self, collection: &Collection<Self>) -> Result<(), StoreError> {
+        collection.insert_one(self.id().into(), self).await
+    }
+
+    /// deletes self
+    async fn delete(&self, collection: &Collection<Self>) -> Result<(), StoreError> {
+        collection.delete_one(self.id().into()).await?;
+        Ok(())
+    }
+
+    async fn find_by_id(id: D, collection: &Collection<Self>) -> Result<Option<Self>, StoreError> {
+        collection
+            .find_one(Filter::new().eq("_id", id.into()))
+            .await
+    }
+
+    fn id(&self) -> D;
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_default::DefaultFromSerde;
+use serde_inline_default::serde_inline_default;
+
+use crate::{config_dir, Config};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Mongo,
+    Sqlite,
+    Memory,
+}
+
+impl StorageBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mongo => "mongo",
+            Self::Sqlite => "sqlite",
+            Self::Memory => "memory",
+        }
+    }
+}
+
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, DefaultFromSerde, Hash)]
+pub struct StorageConfig {
+    #[serde_inline_default(StorageBackend::Mongo)]
+    pub backend: StorageBackend,
+    // relative to the config directory
+    #[serde_inline_default("merlin.db".to_string())]
+    #[serde(rename = "sqlite-path")]
+    pub sqlite_path: String,
+    // seconds between checks that the store can be reached
+    #[serde_inline_default(10)]
+    #[serde(rename = "health-check-secs")]
+    pub health_check_secs: u64,
+}
+
+impl Config for StorageConfig {
+    const NAME: &'static str = "storage";
+    const NOTE: &'static str = "Where module data is stored, \"mongo\", \"sqlite\" or \"memory\"\nMongoDB is configured in mongodb.jsonc";
+}
+
+impl StorageConfig {
+    pub fn sqlite_path(&self) -> PathBuf {
+        config_dir().join(&self.sqlite_path)
+    }
+}
@@ -0,0 +1,19 @@
+use super::{collection::track, Storage, StoreError};
+
+pub struct Counter;
+
+impl Counter {
+    pub async fn bump_get(id: &str) -> Result<i64, StoreError> {
+        track(Storage::get().next_id(id).await)
+    }
+
+    // the next id handed out will be at least `next`, for entries inserted with their own ids
+    pub async fn raise(id: &str, next: i64) -> Result<(), StoreError> {
+        track(Storage::get().raise_counter(id, next).await)
+    }
+
+    // the next id without taking it
+    pub async fn peek(id: &str) -> Result<i64, StoreError> {
+        track(Storage::get().peek_counter(id).await)
+    }
+}
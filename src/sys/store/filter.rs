@@ -0,0 +1,190 @@
+use regex::Regex;
+use serde_json::Value;
+
+use super::StoreError;
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    Eq(Value),
+    // unanchored, like mongodb's $regex
+    Regex(String),
+    // the field is an array containing every value, like mongodb's $all
+    All(Vec<String>),
+    // a number between the two, inclusive, like mongodb's $gte and $lte
+    Range(i64, i64),
+    // the field is an [x, z] point within the circle or box, like mongodb's $geoWithin
+    Circle { center: (i64, i64), radius: u64 },
+    Box { min: (i64, i64), max: (i64, i64) },
+}
+
+impl Condition {
+    // the smallest box holding every matching point, for backends that narrow down by range
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        match self {
+            Self::Circle {
+                center: (x, z),
+                radius,
+            } => {
+                let r = i64::try_from(*radius).unwrap_or(i64::MAX);
+                Some((
+                    (x.saturating_sub(r), z.saturating_sub(r)),
+                    (x.saturating_add(r), z.saturating_add(r)),
+                ))
+            }
+            Self::Box { min, max } => Some((*min, *max)),
+            _ => None,
+        }
+    }
+}
+
+// conditions on top level fields, all of which must match
+#[derive(Clone, Debug, Default)]
+pub struct Filter(Vec<(String, Condition)>);
+
+#[derive(Clone, Copy)]
+pub enum Sort {
+    // insertion order
+    Natural,
+    // newest first
+    Reverse,
+    Ascending(&'static str),
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn eq(&mut self, field: &str, value: impl Into<Value>) -> &mut Self {
+        self.set(field, Condition::Eq(value.into()))
+    }
+
+    pub fn regex(&mut self, field: &str, pattern: impl Into<String>) -> &mut Self {
+        self.set(field, Condition::Regex(pattern.into()))
+    }
+
+    pub fn all(&mut self, field: &str, values: Vec<String>) -> &mut Self {
+        self.set(field, Condition::All(values))
+    }
+
+    pub fn between(&mut self, field: &str, min: i64, max: i64) -> &mut Self {
+        self.set(field, Condition::Range(min, max))
+    }
+
+    // the edge is included
+    pub fn within_radius(&mut self, field: &str, center: (i64, i64), radius: u64) -> &mut Self {
+        self.set(field, Condition::Circle { center, radius })
+    }
+
+    // corners are in any order
+    pub fn within_box(&mut self, field: &str, a: (i64, i64), b: (i64, i64)) -> &mut Self {
+        self.set(
+            field,
+            Condition::Box {
+                min: (a.0.min(b.0), a.1.min(b.1)),
+                max: (a.0.max(b.0), a.1.max(b.1)),
+            },
+        )
+    }
+
+    // a later condition on the same field replaces the earlier one
+    pub fn set(&mut self, field: &str, condition: Condition) -> &mut Self {
+        match self.0.iter_mut().find(|(f, _)| f == field) {
+            Some((_, existing)) => *existing = condition,
+            None => self.0.push((field.to_string(), condition)),
+        }
+        self
+    }
+
+    pub fn conditions(&self) -> &[(String, Condition)] {
+        &self.0
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Condition> {
+        self.0.iter().find(|(f, _)| f == field).map(|(_, c)| c)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // for backends without a query language, the patterns are compiled once for the whole scan
+    pub fn matcher(&self) -> Result<Matcher<'_>, StoreError> {
+        let regexes = self
+            .0
+            .iter()
+            .map(|(_, condition)| match condition {
+                Condition::Regex(pattern) => Regex::new(pattern).map(Some),
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Matcher {
+            filter: self,
+            regexes,
+        })
+    }
+}
+
+// a filter with its patterns compiled, one per condition
+pub struct Matcher<'a> {
+    filter: &'a Filter,
+    regexes: Vec<Option<Regex>>,
+}
+
+impl Matcher<'_> {
+    // matches the same documents mongodb would
+    pub fn matches(&self, doc: &Value) -> bool {
+        for ((field, condition), regex) in self.filter.0.iter().zip(self.regexes.iter()) {
+            let value = doc.get(field);
+
+            let matched = match condition {
+                Condition::Eq(expected) => value == Some(expected),
+                Condition::Regex(_) => match (value.and_then(Value::as_str), regex) {
+                    (Some(s), Some(regex)) => regex.is_match(s),
+                    _ => false,
+                },
+                Condition::All(expected) => match value.and_then(Value::as_array) {
+                    Some(values) => {
+                        !expected.is_empty()
+                            && expected
+                                .iter()
+                                .all(|e| values.iter().any(|v| v.as_str() == Some(e)))
+                    }
+                    None => false,
+                },
+                Condition::Range(min, max) => value
+                    .and_then(Value::as_i64)
+                    .is_some_and(|value| (*min..=*max).contains(&value)),
+                Condition::Circle {
+                    center: (x, z),
+                    radius,
+                } => match value.and_then(point) {
+                    Some((px, pz)) => {
+                        let dx = i128::from(px) - i128::from(*x);
+                        let dz = i128::from(pz) - i128::from(*z);
+                        dx * dx + dz * dz <= i128::from(*radius).saturating_pow(2)
+                    }
+                    None => false,
+                },
+                Condition::Box { min, max } => match value.and_then(point) {
+                    Some((x, z)) => (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&z),
+                    None => false,
+                },
+            };
+
+            if !matched {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn point(value: &Value) -> Option<(i64, i64)> {
+    match value.as_array()?.as_slice() {
+        [x, z] => Some((x.as_i64()?, z.as_i64()?)),
+        _ => None,
+    }
+}
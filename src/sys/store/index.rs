@@ -0,0 +1,47 @@
+use serde_json::Value;
+
+// an index over top level fields, declared by the module owning the collection
+#[derive(Clone, Copy)]
+pub struct Index {
+    pub name: &'static str,
+    pub fields: &'static [&'static str],
+    // no two documents may have the same values for all the fields
+    pub unique: bool,
+    // the first field holds [x, z] points between -bound and bound, for radius and box queries
+    pub geo: Option<i64>,
+}
+
+impl Index {
+    pub const fn new(name: &'static str, fields: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            fields,
+            unique: false,
+            geo: None,
+        }
+    }
+
+    pub const fn unique(name: &'static str, fields: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            fields,
+            unique: true,
+            geo: None,
+        }
+    }
+
+    // a 2d index in mongodb, points outside the bound cannot be written once it exists
+    pub const fn geo(name: &'static str, fields: &'static [&'static str], bound: i64) -> Self {
+        Self {
+            name,
+            fields,
+            unique: false,
+            geo: Some(bound),
+        }
+    }
+
+    // the indexed values of a document, for backends that check uniqueness themselves
+    pub fn key<'a>(&self, doc: &'a Value) -> Vec<Option<&'a Value>> {
+        self.fields.iter().map(|field| doc.get(field)).collect()
+    }
+}
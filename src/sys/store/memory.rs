@@ -0,0 +1,288 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use serenity::async_trait;
+
+use super::{transaction::gone, Filter, Index, Sort, Store, StoreError, Write};
+
+type Collections = HashMap<String, BTreeMap<i64, Value>>;
+
+// nothing is written to disk, everything is gone when the bot stops
+#[derive(Default)]
+pub struct MemoryStore {
+    collections: Mutex<Collections>,
+    counters: Mutex<HashMap<String, i64>>,
+    // only unique indexes are kept, lookups are full scans anyway
+    indexes: Mutex<HashMap<String, Vec<Index>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // fails if another document has the same key in a unique index
+    fn check_unique(
+        &self,
+        collection: &str,
+        docs: &BTreeMap<i64, Value>,
+        id: i64,
+        doc: &Value,
+    ) -> Result<(), StoreError> {
+        for index in self
+            .indexes
+            .lock()
+            .unwrap()
+            .get(collection)
+            .into_iter()
+            .flatten()
+        {
+            let key = index.key(doc);
+
+            if docs
+                .iter()
+                .any(|(other, existing)| *other != id && index.key(existing) == key)
+            {
+                return Err(StoreError::Duplicate(index.name.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn insert_into(
+        &self,
+        collections: &mut Collections,
+        collection: &str,
+        id: i64,
+        doc: Value,
+    ) -> Result<(), StoreError> {
+        let docs = collections.entry(collection.to_string()).or_default();
+
+        if docs.contains_key(&id) {
+            return Err(StoreError::Duplicate("_id".to_string()));
+        }
+
+        self.check_unique(collection, docs, id, &doc)?;
+        docs.insert(id, doc);
+        Ok(())
+    }
+
+    fn replace_in(
+        &self,
+        collections: &mut Collections,
+        collection: &str,
+        id: i64,
+        doc: Value,
+    ) -> Result<bool, StoreError> {
+        let docs = match collections.get_mut(collection) {
+            Some(docs) if docs.contains_key(&id) => docs,
+            _ => return Ok(false),
+        };
+
+        self.check_unique(collection, docs, id, &doc)?;
+        docs.insert(id, doc);
+        Ok(true)
+    }
+
+    fn upsert_into(
+        &self,
+        collections: &mut Collections,
+        collection: &str,
+        id: i64,
+        doc: Value,
+    ) -> Result<(), StoreError> {
+        let docs = collections.entry(collection.to_string()).or_default();
+
+        self.check_unique(collection, docs, id, &doc)?;
+        docs.insert(id, doc);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn find(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<Value>, StoreError> {
+        let collections = self.collections.lock().unwrap();
+        let matcher = filter.matcher()?;
+        let mut out = Vec::new();
+
+        for doc in collections
+            .get(collection)
+            .into_iter()
+            .flat_map(BTreeMap::values)
+        {
+            if matcher.matches(doc) {
+                out.push(doc.clone());
+            }
+        }
+
+        match sort {
+            Sort::Natural => {}
+            Sort::Reverse => out.reverse(),
+            // stable, so equal values stay in id order
+            Sort::Ascending(field) => out.sort_by(|a, b| compare(a.get(field), b.get(field))),
+        }
+
+        Ok(out)
+    }
+
+    async fn insert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        self.insert_into(&mut self.collections.lock().unwrap(), collection, id, doc)
+    }
+
+    async fn replace(&self, collection: &str, id: i64, doc: Value) -> Result<bool, StoreError> {
+        self.replace_in(&mut self.collections.lock().unwrap(), collection, id, doc)
+    }
+
+    async fn upsert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        self.upsert_into(&mut self.collections.lock().unwrap(), collection, id, doc)
+    }
+
+    async fn delete(&self, collection: &str, id: i64) -> Result<bool, StoreError> {
+        Ok(self
+            .collections
+            .lock()
+            .unwrap()
+            .get_mut(collection)
+            .and_then(|docs| docs.remove(&id))
+            .is_some())
+    }
+
+    // the writes are applied to a copy, which replaces the collections once all of them succeed
+    async fn commit(&self, writes: Vec<Write>) -> Result<(), StoreError> {
+        let mut collections = self.collections.lock().unwrap();
+        let mut staged = collections.clone();
+
+        for write in writes {
+            match write {
+                Write::Insert {
+                    collection,
+                    id,
+                    doc,
+                } => self.insert_into(&mut staged, collection, id, doc)?,
+                Write::Replace {
+                    collection,
+                    id,
+                    doc,
+                } => {
+                    if !self.replace_in(&mut staged, collection, id, doc)? {
+                        return Err(gone(collection, id));
+                    }
+                }
+                Write::Upsert {
+                    collection,
+                    id,
+                    doc,
+                } => self.upsert_into(&mut staged, collection, id, doc)?,
+                Write::Delete { collection, id } => {
+                    if let Some(docs) = staged.get_mut(collection) {
+                        docs.remove(&id);
+                    }
+                }
+            }
+        }
+
+        *collections = staged;
+        Ok(())
+    }
+
+    async fn next_id(&self, counter: &str) -> Result<i64, StoreError> {
+        let mut counters = self.counters.lock().unwrap();
+        let count = counters.entry(counter.to_string()).or_insert(1);
+        let id = *count;
+        *count += 1;
+        Ok(id)
+    }
+
+    async fn raise_counter(&self, counter: &str, next: i64) -> Result<(), StoreError> {
+        let mut counters = self.counters.lock().unwrap();
+        let count = counters.entry(counter.to_string()).or_insert(1);
+        *count = (*count).max(next);
+        Ok(())
+    }
+
+    async fn peek_counter(&self, counter: &str) -> Result<i64, StoreError> {
+        Ok(*self.counters.lock().unwrap().get(counter).unwrap_or(&1))
+    }
+
+    async fn create_index(&self, collection: &str, index: &Index) -> Result<(), StoreError> {
+        if !index.unique {
+            return Ok(());
+        }
+
+        let collections = self.collections.lock().unwrap();
+        let mut indexes = self.indexes.lock().unwrap();
+        let indexes = indexes.entry(collection.to_string()).or_default();
+
+        if indexes.iter().any(|existing| existing.name == index.name) {
+            return Ok(());
+        }
+
+        // same as mongodb, an index cannot be created over existing duplicates
+        let mut keys = Vec::new();
+        for doc in collections
+            .get(collection)
+            .into_iter()
+            .flat_map(BTreeMap::values)
+        {
+            let key = index.key(doc);
+            if keys.contains(&key) {
+                return Err(StoreError::Duplicate(index.name.to_string()));
+            }
+            keys.push(key);
+        }
+
+        indexes.push(*index);
+        Ok(())
+    }
+
+    async fn drop_index(&self, collection: &str, name: &str) -> Result<(), StoreError> {
+        if let Some(indexes) = self.indexes.lock().unwrap().get_mut(collection) {
+            indexes.retain(|index| index.name != name);
+        }
+        Ok(())
+    }
+
+    async fn ping(&self) -> Option<(Duration, String)> {
+        let start = Instant::now();
+        drop(self.collections.lock().unwrap());
+        Some((start.elapsed(), env!("CARGO_PKG_VERSION").to_string()))
+    }
+}
+
+// same order as sqlite: missing and null first, then numbers, then strings
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn rank(value: Option<&Value>) -> u8 {
+        match value {
+            None | Some(Value::Null) => 0,
+            Some(Value::Bool(_) | Value::Number(_)) => 1,
+            Some(Value::String(_)) => 2,
+            Some(_) => 3,
+        }
+    }
+
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::{Counter, Filter, Index, Sort, Storage, StoreError};
+
+const COLLECTION: &str = "migrations";
+
+// a change to the documents of a collection, applied once per database
+pub struct Migration {
+    // steps run in ascending order, starting from 1
+    pub version: u32,
+    pub description: &'static str,
+    pub collection: &'static str,
+    // the new document, or None to leave it as is
+    pub migrate: fn(&Value) -> Option<Value>,
+}
+
+// what applying a step did, or would do on a dry run
+pub struct MigrationReport {
+    pub version: u32,
+    pub description: &'static str,
+    pub changed: usize,
+}
+
+// the version of each module is kept in the migrations collection as {module, version}
+pub struct Migrations;
+
+impl Migrations {
+    pub async fn version(module: &str) -> Result<u32, StoreError> {
+        Ok(Self::record(module)
+            .await?
+            .and_then(|record| record.get("version").and_then(Value::as_u64))
+            .unwrap_or_default() as u32)
+    }
+
+    // applies the steps newer than the recorded version, the version is saved after every step
+    // on a dry run nothing is written, later steps see the documents as earlier steps would leave them
+    pub async fn apply(
+        module: &str,
+        steps: &[Migration],
+        dry_run: bool,
+    ) -> Result<Vec<MigrationReport>, StoreError> {
+        let store = Storage::get();
+        let current = Self::version(module).await?;
+
+        let mut pending = steps
+            .iter()
+            .filter(|step| step.version > current)
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|step| step.version);
+
+        let mut reports = Vec::new();
+        let mut documents: HashMap<&str, Vec<Value>> = HashMap::new();
+
+        for step in pending {
+            if !documents.contains_key(step.collection) {
+                documents.insert(
+                    step.collection,
+                    store
+                        .find(step.collection, &Filter::new(), Sort::Natural)
+                        .await?,
+                );
+            }
+
+            let mut changed = 0;
+
+            for doc in documents.get_mut(step.collection).unwrap().iter_mut() {
+                let new = match (step.migrate)(doc) {
+                    Some(new) if new != *doc => new,
+                    _ => continue,
+                };
+
+                if !dry_run {
+                    let id = new.get("_id").and_then(Value::as_i64).ok_or_else(|| {
+                        StoreError::Other(format!(
+                            "migration {} of {module} removed the _id of a document",
+                            step.version
+                        ))
+                    })?;
+                    store.replace(step.collection, id, new.clone()).await?;
+                }
+
+                *doc = new;
+                changed += 1;
+            }
+
+            if !dry_run {
+                Self::set_version(module, step.version).await?;
+                info!(
+                    module,
+                    version = step.version,
+                    description = step.description,
+                    changed,
+                    "migration applied"
+                );
+            }
+
+            reports.push(MigrationReport {
+                version: step.version,
+                description: step.description,
+                changed,
+            });
+        }
+
+        Ok(reports)
+    }
+
+    async fn record(module: &str) -> Result<Option<Value>, StoreError> {
+        Storage::get()
+            .find_one(COLLECTION, Filter::new().eq("module", module))
+            .await
+    }
+
+    // for restoring documents written at an older version
+    pub async fn set_version(module: &str, version: u32) -> Result<(), StoreError> {
+        let store = Storage::get();
+        store
+            .create_index(COLLECTION, &Index::unique("module", &["module"]))
+            .await?;
+
+        let id = match Self::record(module).await? {
+            Some(record) => record
+                .get("_id")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            None => Counter::bump_get(COLLECTION).await?,
+        };
+
+        store
+            .upsert(
+                COLLECTION,
+                id,
+                json!({"_id": id, "module": module, "version": version}),
+            )
+            .await
+    }
+}
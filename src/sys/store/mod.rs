@@ -0,0 +1,27 @@
+mod collection;
+mod config;
+mod counter;
+mod filter;
+mod index;
+mod memory;
+mod migration;
+#[cfg(feature = "mongo")]
+mod mongo;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod storage;
+mod transaction;
+
+pub use collection::{Collection, CollectionItem};
+pub use config::{StorageBackend, StorageConfig};
+pub use counter::Counter;
+pub use filter::{Condition, Filter, Matcher, Sort};
+pub use index::Index;
+pub use memory::MemoryStore;
+pub use migration::{Migration, MigrationReport, Migrations};
+#[cfg(feature = "mongo")]
+pub use mongo::MongoStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+pub use storage::{Storage, Store, StoreError};
+pub use transaction::{Journal, Transaction, Write};
@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use mongodb::{
+    bson::{self, doc, Bson, Document},
+    error::{ErrorKind, WriteFailure},
+    options::{IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+use regex::Regex;
+use serde_json::Value;
+use serenity::{async_trait, futures::TryStreamExt};
+
+use super::{
+    transaction::{commit_in_order, gone},
+    Condition, Filter, Index, Sort, Store, StoreError, Write,
+};
+use crate::Mongo;
+
+pub struct MongoStore;
+
+// duplicate key errors name the index, e.g. "E11000 duplicate key error collection: merlin.coords-coords index: name dup key: ..."
+impl From<mongodb::error::Error> for StoreError {
+    fn from(e: mongodb::error::Error) -> Self {
+        let message = match e.kind.as_ref() {
+            ErrorKind::Write(WriteFailure::WriteError(write)) if write.code == 11000 => {
+                &write.message
+            }
+            ErrorKind::Command(command) if command.code == 11000 => &command.message,
+            ErrorKind::ServerSelection { .. }
+            | ErrorKind::Io(_)
+            | ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::DnsResolve { .. } => return Self::Unavailable(e.to_string()),
+            _ => return Self::Other(e.to_string()),
+        };
+
+        match Regex::new(r"index: (\S+)")
+            .unwrap()
+            .captures(message)
+            .map(|captures| captures[1].to_string())
+        {
+            Some(index) if index == "_id_" => Self::Duplicate("_id".to_string()),
+            Some(index) => Self::Duplicate(index),
+            None => Self::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<bson::ser::Error> for StoreError {
+    fn from(e: bson::ser::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<bson::de::Error> for StoreError {
+    fn from(e: bson::de::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+fn collection(name: &str) -> Result<Collection<Document>, StoreError> {
+    match Mongo::database() {
+        Some(db) => Ok(db.collection(name)),
+        None => Err(StoreError::Unavailable(
+            "no connection to mongodb".to_string(),
+        )),
+    }
+}
+
+fn to_document(filter: &Filter) -> Result<Document, StoreError> {
+    let mut out = Document::new();
+
+    for (field, condition) in filter.conditions() {
+        let value = match condition {
+            Condition::Eq(value) => bson::to_bson(value)?,
+            Condition::Regex(pattern) => Bson::Document(doc! {"$regex": pattern}),
+            Condition::All(values) => Bson::Document(doc! {"$all": values}),
+            Condition::Range(min, max) => Bson::Document(doc! {"$gte": min, "$lte": max}),
+            Condition::Circle {
+                center: (x, z),
+                radius,
+            } => Bson::Document(doc! {"$geoWithin": {"$center": [[x, z], *radius as f64]}}),
+            Condition::Box { min, max } => {
+                Bson::Document(doc! {"$geoWithin": {"$box": [[min.0, min.1], [max.0, max.1]]}})
+            }
+        };
+        out.insert(field, value);
+    }
+
+    Ok(out)
+}
+
+#[async_trait]
+impl Store for MongoStore {
+    fn name(&self) -> &'static str {
+        "mongo"
+    }
+
+    async fn find(
+        &self,
+        collection_name: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<Value>, StoreError> {
+        let collection = collection(collection_name)?;
+        let find = collection.find(to_document(filter)?);
+
+        let find = match sort {
+            Sort::Natural => find,
+            Sort::Reverse => find.sort(doc! {"$natural": -1}),
+            Sort::Ascending(field) => find.sort(doc! {field: 1}),
+        };
+
+        find.await?
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .map(|doc| Ok(bson::from_document(doc)?))
+            .collect()
+    }
+
+    async fn find_one(
+        &self,
+        collection_name: &str,
+        filter: &Filter,
+    ) -> Result<Option<Value>, StoreError> {
+        match collection(collection_name)?
+            .find_one(to_document(filter)?)
+            .await?
+        {
+            Some(doc) => Ok(Some(bson::from_document(doc)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn count_by(
+        &self,
+        collection_name: &str,
+        field: &str,
+    ) -> Result<Vec<(Value, u64)>, StoreError> {
+        let mut cursor = collection(collection_name)?
+            .aggregate([doc! {"$group": {"_id": format!("${field}"), "count": {"$sum": 1}}}])
+            .await?;
+        let mut out = Vec::new();
+
+        while let Some(doc) = cursor.try_next().await? {
+            let count = match doc.get("count") {
+                Some(Bson::Int32(n)) => *n as u64,
+                Some(Bson::Int64(n)) => *n as u64,
+                _ => 0,
+            };
+            let value = doc.get("_id").cloned().unwrap_or(Bson::Null);
+            out.push((bson::from_bson(value)?, count));
+        }
+
+        Ok(out)
+    }
+
+    async fn insert(&self, collection_name: &str, _id: i64, doc: Value) -> Result<(), StoreError> {
+        collection(collection_name)?
+            .insert_one(bson::to_document(&doc)?)
+            .await?;
+        Ok(())
+    }
+
+    async fn replace(
+        &self,
+        collection_name: &str,
+        id: i64,
+        doc: Value,
+    ) -> Result<bool, StoreError> {
+        Ok(collection(collection_name)?
+            .replace_one(doc! {"_id": id}, bson::to_document(&doc)?)
+            .await?
+            .matched_count
+            != 0)
+    }
+
+    async fn upsert(&self, collection_name: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        collection(collection_name)?
+            .replace_one(doc! {"_id": id}, bson::to_document(&doc)?)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, collection_name: &str, id: i64) -> Result<bool, StoreError> {
+        Ok(collection(collection_name)?
+            .delete_one(doc! {"_id": id})
+            .await?
+            .deleted_count
+            != 0)
+    }
+
+    // transactions need a replica set, a standalone server gets the writes one by one instead
+    async fn commit(&self, writes: Vec<Write>) -> Result<(), StoreError> {
+        let db = match Mongo::database() {
+            Some(db) => db,
+            None => {
+                return Err(StoreError::Unavailable(
+                    "no connection to mongodb".to_string(),
+                ))
+            }
+        };
+
+        let mut session = db.client().start_session().await?;
+        if let Err(e) = session.start_transaction().await {
+            return match e.kind.as_ref() {
+                ErrorKind::Transaction { .. } => commit_in_order(self, writes).await,
+                _ => Err(e.into()),
+            };
+        }
+
+        // the transaction is aborted when the session is dropped before the commit
+        for write in writes {
+            let collection = collection(write.collection())?;
+
+            match write {
+                Write::Insert { doc, .. } => {
+                    collection
+                        .insert_one(bson::to_document(&doc)?)
+                        .session(&mut session)
+                        .await?;
+                }
+                Write::Replace {
+                    collection: name,
+                    id,
+                    doc,
+                } => {
+                    if collection
+                        .replace_one(doc! {"_id": id}, bson::to_document(&doc)?)
+                        .session(&mut session)
+                        .await?
+                        .matched_count
+                        == 0
+                    {
+                        return Err(gone(name, id));
+                    }
+                }
+                Write::Upsert { id, doc, .. } => {
+                    collection
+                        .replace_one(doc! {"_id": id}, bson::to_document(&doc)?)
+                        .upsert(true)
+                        .session(&mut session)
+                        .await?;
+                }
+                Write::Delete { id, .. } => {
+                    collection
+                        .delete_one(doc! {"_id": id})
+                        .session(&mut session)
+                        .await?;
+                }
+            }
+        }
+
+        session.commit_transaction().await?;
+        Ok(())
+    }
+
+    // the counter document holds the next id to hand out
+    // a single upserting update, so concurrent callers never get the same id
+    async fn next_id(&self, counter: &str) -> Result<i64, StoreError> {
+        let updated = collection("counters")?
+            .find_one_and_update(doc! {"_id": counter}, doc! {"$inc": {"count": 1_i64}})
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?;
+
+        // older counters were written as 32 bit integers
+        let id = match updated.as_ref().and_then(|doc| doc.get("count")) {
+            Some(Bson::Int32(count)) => *count as i64 - 1,
+            Some(Bson::Int64(count)) => *count - 1,
+            _ => {
+                return Err(StoreError::Other(format!(
+                    "counter {counter} is not an integer"
+                )))
+            }
+        };
+
+        // a counter created by the upsert starts at 0, which is not a valid id
+        if id == 0 {
+            return self.next_id(counter).await;
+        }
+
+        Ok(id)
+    }
+
+    async fn raise_counter(&self, counter: &str, next: i64) -> Result<(), StoreError> {
+        collection("counters")?
+            .update_one(doc! {"_id": counter}, doc! {"$max": {"count": next}})
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn peek_counter(&self, counter: &str) -> Result<i64, StoreError> {
+        let found = collection("counters")?
+            .find_one(doc! {"_id": counter})
+            .await?;
+
+        Ok(match found.as_ref().and_then(|doc| doc.get("count")) {
+            Some(Bson::Int32(count)) => *count as i64,
+            Some(Bson::Int64(count)) => *count,
+            _ => 1,
+        }
+        .max(1))
+    }
+
+    async fn create_index(&self, collection_name: &str, index: &Index) -> Result<(), StoreError> {
+        let mut keys = Document::new();
+        for (i, field) in index.fields.iter().enumerate() {
+            match index.geo {
+                Some(_) if i == 0 => keys.insert(*field, "2d"),
+                _ => keys.insert(*field, 1),
+            };
+        }
+
+        collection(collection_name)?
+            .create_index(
+                IndexModel::builder()
+                    .keys(keys)
+                    .options(
+                        IndexOptions::builder()
+                            .name(index.name.to_string())
+                            .unique(index.unique)
+                            .min(index.geo.map(|bound| -bound as f64))
+                            .max(index.geo.map(|bound| bound as f64))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn drop_index(&self, collection_name: &str, name: &str) -> Result<(), StoreError> {
+        let e = match collection(collection_name)?.drop_index(name).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        // 26 is a missing collection, 27 a missing index
+        match e.kind.as_ref() {
+            ErrorKind::Command(command) if matches!(command.code, 26 | 27) => Ok(()),
+            _ => Err(e.into()),
+        }
+    }
+
+    async fn ping(&self) -> Option<(Duration, String)> {
+        Mongo::server_info().await
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rusqlite::{
+    params, params_from_iter, types::Value as SqlValue, Connection, ErrorCode, OptionalExtension,
+};
+use serde_json::Value;
+use serenity::async_trait;
+use tracing::warn;
+
+use super::{transaction::gone, Condition, Filter, Index, Sort, Store, StoreError, Write};
+
+// every collection is a table of (id, json document), filters are evaluated on the documents
+pub struct SqliteStore(Arc<Mutex<Connection>>);
+
+// "UNIQUE constraint failed: index 'coords-coords.name'" for indexes, or "... coords-coords.id" for ids
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        let message = e.to_string();
+
+        match message.strip_prefix("UNIQUE constraint failed: ") {
+            Some(failed) if e.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => {
+                match failed.strip_prefix("index '") {
+                    Some(index) => Self::Duplicate(
+                        index
+                            .trim_end_matches('\'')
+                            .rsplit('.')
+                            .next()
+                            .unwrap_or_default()
+                            .to_string(),
+                    ),
+                    None => Self::Duplicate("_id".to_string()),
+                }
+            }
+            _ => Self::Other(message),
+        }
+    }
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| StoreError::Other(e.to_string()))?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS counters (name TEXT PRIMARY KEY, count INTEGER NOT NULL);",
+        )?;
+
+        Ok(Self(Arc::new(Mutex::new(conn))))
+    }
+
+    // how sqlite runs a find, one line per step, for checking that it is answered by an index
+    pub async fn query_plan(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<String>, StoreError> {
+        let collection = collection.to_string();
+        let filter = filter.clone();
+
+        self.run(move |conn| {
+            let Some((query, values)) = select(conn, &collection, &filter, sort)? else {
+                return Ok(Vec::new());
+            };
+
+            let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {query}"))?;
+            let plan = stmt
+                .query_map(params_from_iter(values), |row| row.get::<_, String>(3))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(plan)
+        })
+        .await
+    }
+
+    // queries are blocking, so they are run off the async workers
+    async fn run<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, StoreError> + Send + 'static,
+    {
+        let conn = self.0.clone();
+        tokio::task::spawn_blocking(move || f(&conn.lock().unwrap()))
+            .await
+            .map_err(|e| StoreError::Other(e.to_string()))?
+    }
+}
+
+// the query for a find and its parameters, None if nothing can match
+// documents still go through the matcher of the filter, the clauses only narrow down what is read
+fn select(
+    conn: &Connection,
+    collection: &str,
+    filter: &Filter,
+    sort: Sort,
+) -> Result<Option<(String, Vec<SqlValue>)>, StoreError> {
+    let table = table(conn, collection)?;
+    let mut values = Vec::new();
+
+    // lookups by id do not need to read the whole table
+    let mut clauses = match filter.get("_id") {
+        Some(Condition::Eq(id)) => match id.as_i64() {
+            Some(id) => vec![format!("id = {id}")],
+            None => return Ok(None),
+        },
+        _ => Vec::new(),
+    };
+
+    for (field, condition) in filter.conditions() {
+        let field = field.replace('\'', "''");
+
+        // the same expression as the indexes, so the fields compared for equality are looked up in them
+        // booleans, floats and arrays are left to the matcher, json_extract does not return them as they are
+        if let Condition::Eq(value) = condition {
+            let value = match value {
+                Value::String(s) => Some(SqlValue::Text(s.clone())),
+                Value::Number(n) => n.as_i64().map(SqlValue::Integer),
+                _ => None,
+            };
+
+            if let Some(value) = value.filter(|_| field != "_id") {
+                clauses.push(format!("json_extract(doc, '$.{field}') = ?"));
+                values.push(value);
+            }
+        }
+
+        // and points are narrowed down to a box, which a geo index answers after its equality fields
+        if let Some((min, max)) = condition.bounds() {
+            clauses.push(format!(
+                "json_extract(doc, '$.{field}[0]') BETWEEN {} AND {} AND json_extract(doc, '$.{field}[1]') BETWEEN {} AND {}",
+                min.0, max.0, min.1, max.1
+            ));
+        }
+    }
+
+    let clause = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", clauses.join(" AND "))
+    };
+
+    let order = match sort {
+        Sort::Natural => "id".to_string(),
+        Sort::Reverse => "id DESC".to_string(),
+        Sort::Ascending("_id") => "id".to_string(),
+        Sort::Ascending(field) => {
+            format!("json_extract(doc, '$.{}'), id", field.replace('\'', "''"))
+        }
+    };
+
+    Ok(Some((
+        format!("SELECT doc FROM {table}{clause} ORDER BY {order}"),
+        values,
+    )))
+}
+
+// creates the table on first use, returns the quoted table name
+fn table(conn: &Connection, collection: &str) -> Result<String, StoreError> {
+    let table = format!("\"{}\"", collection.replace('"', "\"\""));
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {table} (id INTEGER PRIMARY KEY, doc TEXT NOT NULL);"
+    ))?;
+    Ok(table)
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn find(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<Value>, StoreError> {
+        let collection = collection.to_string();
+        let filter = filter.clone();
+
+        self.run(move |conn| {
+            let Some((query, values)) = select(conn, &collection, &filter, sort)? else {
+                return Ok(Vec::new());
+            };
+
+            let mut stmt = conn.prepare(&query)?;
+            let mut rows = stmt.query(params_from_iter(values))?;
+            let matcher = filter.matcher()?;
+            let mut out = Vec::new();
+
+            while let Some(row) = rows.next()? {
+                let doc = serde_json::from_str::<Value>(&row.get::<_, String>(0)?)?;
+                if matcher.matches(&doc) {
+                    out.push(doc);
+                }
+            }
+
+            Ok(out)
+        })
+        .await
+    }
+
+    async fn count_by(
+        &self,
+        collection: &str,
+        field: &str,
+    ) -> Result<Vec<(Value, u64)>, StoreError> {
+        let collection = collection.to_string();
+        let field = field.replace('\'', "''");
+
+        self.run(move |conn| {
+            let table = table(conn, &collection)?;
+            let mut stmt = conn.prepare(&format!(
+                "SELECT json_extract(doc, '$.{field}'), COUNT(*) FROM {table} GROUP BY 1"
+            ))?;
+            let counts = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, SqlValue>(0)?, row.get::<_, i64>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(counts
+                .into_iter()
+                .map(|(value, count)| {
+                    let value = match value {
+                        SqlValue::Integer(n) => Value::from(n),
+                        SqlValue::Real(n) => Value::from(n),
+                        SqlValue::Text(s) => Value::from(s),
+                        _ => Value::Null,
+                    };
+                    (value, count as u64)
+                })
+                .collect())
+        })
+        .await
+    }
+
+    async fn insert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        let collection = collection.to_string();
+
+        self.run(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT INTO {} (id, doc) VALUES (?1, ?2)",
+                    table(conn, &collection)?
+                ),
+                params![id, doc.to_string()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn replace(&self, collection: &str, id: i64, doc: Value) -> Result<bool, StoreError> {
+        let collection = collection.to_string();
+
+        self.run(move |conn| {
+            Ok(conn.execute(
+                &format!(
+                    "UPDATE {} SET doc = ?2 WHERE id = ?1",
+                    table(conn, &collection)?
+                ),
+                params![id, doc.to_string()],
+            )? != 0)
+        })
+        .await
+    }
+
+    async fn upsert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        let collection = collection.to_string();
+
+        self.run(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT INTO {} (id, doc) VALUES (?1, ?2) ON CONFLICT(id) DO UPDATE SET doc = excluded.doc",
+                    table(conn, &collection)?
+                ),
+                params![id, doc.to_string()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, collection: &str, id: i64) -> Result<bool, StoreError> {
+        let collection = collection.to_string();
+
+        self.run(move |conn| {
+            Ok(conn.execute(
+                &format!("DELETE FROM {} WHERE id = ?1", table(conn, &collection)?),
+                params![id],
+            )? != 0)
+        })
+        .await
+    }
+
+    // the transaction is rolled back when dropped before the commit
+    async fn commit(&self, writes: Vec<Write>) -> Result<(), StoreError> {
+        self.run(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+
+            for write in writes {
+                let table = table(&tx, write.collection())?;
+
+                match write {
+                    Write::Insert { id, doc, .. } => {
+                        tx.execute(
+                            &format!("INSERT INTO {table} (id, doc) VALUES (?1, ?2)"),
+                            params![id, doc.to_string()],
+                        )?;
+                    }
+                    Write::Replace { collection, id, doc } => {
+                        if tx.execute(
+                            &format!("UPDATE {table} SET doc = ?2 WHERE id = ?1"),
+                            params![id, doc.to_string()],
+                        )? == 0
+                        {
+                            return Err(gone(collection, id));
+                        }
+                    }
+                    Write::Upsert { id, doc, .. } => {
+                        tx.execute(
+                            &format!("INSERT INTO {table} (id, doc) VALUES (?1, ?2) ON CONFLICT(id) DO UPDATE SET doc = excluded.doc"),
+                            params![id, doc.to_string()],
+                        )?;
+                    }
+                    Write::Delete { id, .. } => {
+                        tx.execute(&format!("DELETE FROM {table} WHERE id = ?1"), params![id])?;
+                    }
+                }
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    // the counter row holds the next id to hand out, same as the mongo counters
+    async fn next_id(&self, counter: &str) -> Result<i64, StoreError> {
+        let counter = counter.to_string();
+
+        self.run(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let id = tx
+                .query_row(
+                    "SELECT count FROM counters WHERE name = ?1",
+                    params![counter],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?
+                .unwrap_or(1);
+            tx.execute(
+                "INSERT INTO counters (name, count) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET count = excluded.count",
+                params![counter, id + 1],
+            )?;
+            tx.commit()?;
+            Ok(id)
+        })
+        .await
+    }
+
+    async fn raise_counter(&self, counter: &str, next: i64) -> Result<(), StoreError> {
+        let counter = counter.to_string();
+
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT INTO counters (name, count) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET count = max(count, excluded.count)",
+                params![counter, next],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn peek_counter(&self, counter: &str) -> Result<i64, StoreError> {
+        let counter = counter.to_string();
+
+        self.run(move |conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT count FROM counters WHERE name = ?1",
+                    params![counter],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?
+                .unwrap_or(1))
+        })
+        .await
+    }
+
+    // named "collection.index", json_extract(..) is the same expression used for sorting
+    async fn create_index(&self, collection: &str, index: &Index) -> Result<(), StoreError> {
+        let collection = collection.to_string();
+        let index = *index;
+
+        self.run(move |conn| {
+            let table = table(conn, &collection)?;
+            let name = format!("{collection}.{}", index.name).replace('"', "\"\"");
+            let mut fields = index
+                .fields
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<_>>();
+
+            // the point is split into its coordinates, after the fields compared for equality
+            if index.geo.is_some() {
+                let point = fields.remove(0);
+                fields.push(format!("{point}[0]"));
+                fields.push(format!("{point}[1]"));
+            }
+
+            let columns = fields
+                .iter()
+                .map(|field| format!("json_extract(doc, '$.{}')", field.replace('\'', "''")))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            conn.execute_batch(&format!(
+                "CREATE {}INDEX IF NOT EXISTS \"{name}\" ON {table} ({columns});",
+                if index.unique { "UNIQUE " } else { "" }
+            ))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn drop_index(&self, collection: &str, name: &str) -> Result<(), StoreError> {
+        let name = format!("{collection}.{name}").replace('"', "\"\"");
+
+        self.run(move |conn| {
+            conn.execute_batch(&format!("DROP INDEX IF EXISTS \"{name}\";"))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn ping(&self) -> Option<(Duration, String)> {
+        let start = Instant::now();
+
+        match self
+            .run(|conn| {
+                Ok(conn.query_row("SELECT sqlite_version()", [], |row| row.get::<_, String>(0))?)
+            })
+            .await
+        {
+            Ok(version) => Some((start.elapsed(), version)),
+            Err(e) => {
+                warn!(error = %e, "sqlite ping failed");
+                None
+            }
+        }
+    }
+}
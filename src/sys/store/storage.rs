@@ -0,0 +1,298 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::Duration,
+};
+
+use serde_json::Value;
+use serenity::async_trait;
+use tracing::{error, info, warn};
+
+use super::{
+    transaction::{commit_in_order, Journal},
+    Filter, Index, Sort, StorageConfig, Write,
+};
+use crate::Config;
+
+static mut STORE: OnceLock<Box<dyn Store>> = OnceLock::new();
+// whether the last ping or query reached the store
+static HEALTHY: AtomicBool = AtomicBool::new(false);
+
+// a document store, documents are json objects keyed by an integer `_id`
+#[async_trait]
+pub trait Store: Send + Sync {
+    // as written in storage.jsonc
+    fn name(&self) -> &'static str;
+
+    async fn find(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<Value>, StoreError>;
+
+    async fn find_one(
+        &self,
+        collection: &str,
+        filter: &Filter,
+    ) -> Result<Option<Value>, StoreError> {
+        Ok(self
+            .find(collection, filter, Sort::Natural)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    // the number of documents for each value of a top level field, null for documents without it
+    async fn count_by(
+        &self,
+        collection: &str,
+        field: &str,
+    ) -> Result<Vec<(Value, u64)>, StoreError> {
+        let mut out: Vec<(Value, u64)> = Vec::new();
+
+        for doc in self.find(collection, &Filter::new(), Sort::Natural).await? {
+            let value = doc.get(field).cloned().unwrap_or_default();
+            match out.iter_mut().find(|(existing, _)| *existing == value) {
+                Some((_, count)) => *count += 1,
+                None => out.push((value, 1)),
+            }
+        }
+
+        Ok(out)
+    }
+
+    // fails if a document with the same id exists
+    async fn insert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError>;
+    // returns false if there was no document to replace
+    async fn replace(&self, collection: &str, id: i64, doc: Value) -> Result<bool, StoreError>;
+    async fn upsert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError>;
+    // returns false if there was no document to delete
+    async fn delete(&self, collection: &str, id: i64) -> Result<bool, StoreError>;
+    // every write or none of them
+    async fn commit(&self, writes: Vec<Write>) -> Result<(), StoreError> {
+        commit_in_order(self, writes).await
+    }
+
+    // ids handed out by a counter start from 1
+    async fn next_id(&self, counter: &str) -> Result<i64, StoreError>;
+    // the next id handed out will be at least `next`
+    async fn raise_counter(&self, counter: &str, next: i64) -> Result<(), StoreError>;
+    // the id the counter will hand out next, without taking it
+    async fn peek_counter(&self, counter: &str) -> Result<i64, StoreError>;
+
+    // does nothing if the index already exists
+    async fn create_index(&self, collection: &str, index: &Index) -> Result<(), StoreError>;
+    // does nothing if there is no index with that name
+    async fn drop_index(&self, collection: &str, name: &str) -> Result<(), StoreError>;
+
+    // (round trip time, server version), None if the store does not respond
+    async fn ping(&self) -> Option<(Duration, String)>;
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    // a unique index already has a document with the same value, holds the index name
+    Duplicate(String),
+    // the store could not be reached, the same query may work later
+    Unavailable(String),
+    Other(String),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Duplicate(index) => write!(f, "duplicate value for unique index {index}"),
+            Self::Unavailable(e) | Self::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl StoreError {
+    // for commands that could not finish, only a store that is down is worth telling users about
+    pub fn reply(&self) -> &'static str {
+        match self {
+            Self::Unavailable(_) => "The database is unavailable, try again later.",
+            _ => "Something went wrong while running the command.",
+        }
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<regex::Error> for StoreError {
+    fn from(e: regex::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+pub struct Storage;
+
+impl Storage {
+    pub async fn load() {
+        let config = StorageConfig::load();
+
+        match Self::open(&config).await {
+            Ok(Some(store)) => {
+                info!(backend = store.name(), "storage loaded");
+                HEALTHY.store(store.ping().await.is_some(), Ordering::Relaxed);
+                let _ = unsafe { STORE.set(store) };
+                Journal::recover();
+            }
+            // modules that need storage cannot be compiled without a backend
+            Ok(None) => warn!(
+                backend = config.backend.name(),
+                "storage backend is not compiled in, build with the feature of the same name"
+            ),
+            // commands that need storage are refused, as with an unreachable mongodb
+            Err(e) => error!(
+                backend = config.backend.name(),
+                error = %e,
+                "could not open storage, it stays unavailable"
+            ),
+        }
+    }
+
+    // None if the backend is not compiled in
+    async fn open(config: &StorageConfig) -> Result<Option<Box<dyn Store>>, StoreError> {
+        match config.backend {
+            #[cfg(feature = "mongo")]
+            super::StorageBackend::Mongo => {
+                crate::Mongo::load().await;
+                Ok(Some(Box::new(super::MongoStore)))
+            }
+            #[cfg(feature = "sqlite")]
+            super::StorageBackend::Sqlite => {
+                let path = config.sqlite_path();
+                let store = super::SqliteStore::open(&path).map_err(|e| {
+                    StoreError::Unavailable(format!(
+                        "could not open sqlite database {}: {e}",
+                        path.display()
+                    ))
+                })?;
+                Ok(Some(Box::new(store)))
+            }
+            super::StorageBackend::Memory => Ok(Some(Box::new(super::MemoryStore::new()))),
+            #[allow(unreachable_patterns)]
+            _ => Ok(None),
+        }
+    }
+
+    // the store from storage.jsonc as it is on disk, for checks that only read
+    // journals are left for the bot to recover, false if nothing is kept between runs
+    pub async fn open_existing() -> Result<bool, StoreError> {
+        let Some(Ok(config)) = StorageConfig::read() else {
+            return Ok(false);
+        };
+
+        match config.backend {
+            super::StorageBackend::Memory => return Ok(false),
+            super::StorageBackend::Sqlite if !config.sqlite_path().exists() => return Ok(false),
+            _ => {}
+        }
+
+        match Self::open(&config).await? {
+            Some(store) => {
+                let _ = unsafe { STORE.set(store) };
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub async fn reload() {
+        unsafe { STORE = OnceLock::new() };
+        #[cfg(feature = "mongo")]
+        crate::Mongo::unload();
+        Self::load().await;
+    }
+
+    pub fn get() -> &'static dyn Store {
+        Self::try_get().expect("storage is not loaded")
+    }
+
+    pub fn try_get() -> Option<&'static dyn Store> {
+        unsafe { STORE.get() }.map(Box::as_ref)
+    }
+
+    // creates missing indexes, failures other than the store being down are logged
+    // as lookups still work without them
+    pub async fn ensure_indexes(collection: &str, indexes: &[Index]) -> Result<(), StoreError> {
+        for index in indexes {
+            match Self::get().create_index(collection, index).await {
+                Ok(()) => info!(collection, index = index.name, "index ready"),
+                Err(e @ StoreError::Unavailable(_)) => return Err(e),
+                Err(e) => {
+                    error!(collection, index = index.name, error = %e, "could not create index")
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // indexes replaced by ones with other fields, which would otherwise keep their old constraints
+    pub async fn drop_indexes(collection: &str, names: &[&str]) -> Result<(), StoreError> {
+        for name in names {
+            match Self::get().drop_index(collection, name).await {
+                Ok(()) => {}
+                Err(e @ StoreError::Unavailable(_)) => return Err(e),
+                Err(e) => error!(collection, index = name, error = %e, "could not drop index"),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_healthy() -> bool {
+        HEALTHY.load(Ordering::Relaxed)
+    }
+
+    // only changes are logged
+    pub fn set_healthy(healthy: bool) {
+        match (HEALTHY.swap(healthy, Ordering::Relaxed), healthy) {
+            (true, false) => error!("storage unavailable"),
+            (false, true) => info!("storage available again"),
+            _ => {}
+        }
+    }
+
+    // pings the store in the background so commands can refuse early while it is down
+    pub async fn monitor() {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            StorageConfig::load().health_check_secs.max(1),
+        ));
+
+        loop {
+            interval.tick().await;
+
+            let store = match Self::try_get() {
+                Some(store) => store,
+                None => continue,
+            };
+
+            let reachable = tokio::time::timeout(Duration::from_secs(10), store.ping())
+                .await
+                .is_ok_and(|info| info.is_some());
+
+            // migrations postponed while the store was down are applied before commands are let through
+            if reachable && !Self::is_healthy() {
+                if let Err(e) = crate::CommandHandler::prepare_storage().await {
+                    error!(error = %e, "migrations failed, storage stays unavailable");
+                    continue;
+                }
+            }
+
+            Self::set_healthy(reachable);
+        }
+    }
+}
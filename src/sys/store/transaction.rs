@@ -0,0 +1,386 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+use super::{collection::track, Collection, Filter, Storage, Store, StoreError};
+use crate::config_dir;
+
+// a document write, applied together with the other writes of a transaction
+#[derive(Clone)]
+pub enum Write {
+    Insert {
+        collection: &'static str,
+        id: i64,
+        doc: Value,
+    },
+    // fails if the document is gone
+    Replace {
+        collection: &'static str,
+        id: i64,
+        doc: Value,
+    },
+    Upsert {
+        collection: &'static str,
+        id: i64,
+        doc: Value,
+    },
+    Delete {
+        collection: &'static str,
+        id: i64,
+    },
+}
+
+impl Write {
+    pub fn collection(&self) -> &'static str {
+        match self {
+            Self::Insert { collection, .. }
+            | Self::Replace { collection, .. }
+            | Self::Upsert { collection, .. }
+            | Self::Delete { collection, .. } => collection,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        match self {
+            Self::Insert { id, .. }
+            | Self::Replace { id, .. }
+            | Self::Upsert { id, .. }
+            | Self::Delete { id, .. } => *id,
+        }
+    }
+}
+
+// for stores without transactions, every write is undone if a later one fails
+// another writer can see the documents in between
+pub(super) async fn commit_in_order<S: Store + ?Sized>(
+    store: &S,
+    writes: Vec<Write>,
+) -> Result<(), StoreError> {
+    // (collection, id, document before the write)
+    let mut undo: Vec<(&'static str, i64, Option<Value>)> = Vec::new();
+
+    for write in writes {
+        let (collection, id) = (write.collection(), write.id());
+        let before = match store
+            .find_one(collection, Filter::new().eq("_id", id))
+            .await
+        {
+            Ok(before) => before,
+            Err(e) => {
+                rollback(store, undo).await;
+                return Err(e);
+            }
+        };
+
+        let res = match write {
+            Write::Insert { doc, .. } => store.insert(collection, id, doc).await,
+            Write::Replace { doc, .. } => match store.replace(collection, id, doc).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(gone(collection, id)),
+                Err(e) => Err(e),
+            },
+            Write::Upsert { doc, .. } => store.upsert(collection, id, doc).await,
+            Write::Delete { .. } => store.delete(collection, id).await.map(|_| ()),
+        };
+
+        if let Err(e) = res {
+            rollback(store, undo).await;
+            return Err(e);
+        }
+
+        undo.push((collection, id, before));
+    }
+
+    Ok(())
+}
+
+async fn rollback<S: Store + ?Sized>(store: &S, undo: Vec<(&'static str, i64, Option<Value>)>) {
+    for (collection, id, before) in undo.into_iter().rev() {
+        let res = match before {
+            Some(doc) => store.upsert(collection, id, doc).await,
+            None => store.delete(collection, id).await.map(|_| ()),
+        };
+
+        if let Err(e) = res {
+            error!(collection, id, error = %e, "could not undo write");
+        }
+    }
+}
+
+pub(super) fn gone(collection: &str, id: i64) -> StoreError {
+    StoreError::Other(format!("{collection} {id} no longer exists"))
+}
+
+// document writes and directory moves that happen together or not at all
+// moves are done first and journaled, so they can be undone if the writes fail or the bot stops midway
+#[derive(Default)]
+pub struct Transaction {
+    writes: Vec<Write>,
+    moves: Vec<(PathBuf, PathBuf)>,
+    // removed after the commit if nothing is left in them
+    cleanup: Vec<PathBuf>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<T: Serialize + DeserializeOwned>(
+        &mut self,
+        collection: &Collection<T>,
+        id: i64,
+        item: &T,
+    ) -> Result<&mut Self, StoreError> {
+        self.writes.push(Write::Insert {
+            collection: collection.name(),
+            id,
+            doc: serde_json::to_value(item)?,
+        });
+        Ok(self)
+    }
+
+    pub fn replace<T: Serialize + DeserializeOwned>(
+        &mut self,
+        collection: &Collection<T>,
+        id: i64,
+        item: &T,
+    ) -> Result<&mut Self, StoreError> {
+        self.writes.push(Write::Replace {
+            collection: collection.name(),
+            id,
+            doc: serde_json::to_value(item)?,
+        });
+        Ok(self)
+    }
+
+    pub fn upsert<T: Serialize + DeserializeOwned>(
+        &mut self,
+        collection: &Collection<T>,
+        id: i64,
+        item: &T,
+    ) -> Result<&mut Self, StoreError> {
+        self.writes.push(Write::Upsert {
+            collection: collection.name(),
+            id,
+            doc: serde_json::to_value(item)?,
+        });
+        Ok(self)
+    }
+
+    pub fn delete<T: Serialize + DeserializeOwned>(
+        &mut self,
+        collection: &Collection<T>,
+        id: i64,
+    ) -> &mut Self {
+        self.writes.push(Write::Delete {
+            collection: collection.name(),
+            id,
+        });
+        self
+    }
+
+    // the parent of `to` is created if needed
+    pub fn rename(&mut self, from: PathBuf, to: PathBuf) -> &mut Self {
+        self.moves.push((from, to));
+        self
+    }
+
+    pub fn remove_dir_if_empty(&mut self, dir: PathBuf) -> &mut Self {
+        self.cleanup.push(dir);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty() && self.moves.is_empty()
+    }
+
+    pub async fn commit(self) -> Result<(), StoreError> {
+        let journal = Journal::begin(&self.moves)?;
+
+        for (done, (from, to)) in self.moves.iter().enumerate() {
+            let res = match to.parent() {
+                Some(parent) => tokio::fs::create_dir_all(parent).await,
+                None => Ok(()),
+            };
+
+            if let Err(e) = match res {
+                Ok(()) => tokio::fs::rename(from, to).await,
+                Err(e) => Err(e),
+            } {
+                journal.rollback(&self.moves[..done]);
+                return Err(StoreError::Other(format!(
+                    "could not move {} to {}: {e}",
+                    from.display(),
+                    to.display()
+                )));
+            }
+        }
+
+        if let Err(e) = track(Storage::get().commit(self.writes).await) {
+            journal.rollback(&self.moves);
+            return Err(e);
+        }
+
+        journal.committed(&self.moves);
+        journal.finish();
+
+        for dir in self.cleanup {
+            // fails if the directory is not empty, which is fine
+            let _ = tokio::fs::remove_dir(dir).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalFile {
+    moves: Vec<(PathBuf, PathBuf)>,
+    // the writes went through, so the moves are kept
+    #[serde(default)]
+    committed: bool,
+}
+
+// moves of a transaction in progress, kept as a file in the journal directory until it is committed
+// a journal left behind by a stopped bot is rolled back on the next start
+pub struct Journal(Option<PathBuf>);
+
+impl Journal {
+    fn dir() -> PathBuf {
+        config_dir().join("journal")
+    }
+
+    fn begin(moves: &[(PathBuf, PathBuf)]) -> Result<Self, StoreError> {
+        if moves.is_empty() {
+            return Ok(Self(None));
+        }
+
+        let dir = Self::dir();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = dir.join(format!("{nanos}-{}.json", std::process::id()));
+
+        fs::create_dir_all(&dir)
+            .and_then(|()| {
+                fs::write(
+                    &path,
+                    serde_json::to_vec(&JournalFile {
+                        moves: moves.to_vec(),
+                        committed: false,
+                    })?,
+                )
+            })
+            .map_err(|e| StoreError::Other(format!("could not write journal: {e}")))?;
+
+        Ok(Self(Some(path)))
+    }
+
+    // moves back what was moved, newest first
+    fn rollback(self, moved: &[(PathBuf, PathBuf)]) {
+        for (from, to) in moved.iter().rev() {
+            undo_move(from, to);
+        }
+        self.finish();
+    }
+
+    // replaced in one rename, so a stop midway leaves either the old or the new journal
+    fn committed(&self, moves: &[(PathBuf, PathBuf)]) {
+        let Some(path) = &self.0 else {
+            return;
+        };
+
+        let temp = path.with_extension("tmp");
+        let res = serde_json::to_vec(&JournalFile {
+            moves: moves.to_vec(),
+            committed: true,
+        })
+        .map_err(std::io::Error::from)
+        .and_then(|content| fs::write(&temp, content))
+        .and_then(|()| fs::rename(&temp, path));
+
+        if let Err(e) = res {
+            warn!(path = %path.display(), error = %e, "could not mark journal as committed");
+        }
+    }
+
+    fn finish(self) {
+        if let Some(path) = self.0 {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!(path = %path.display(), error = %e, "could not remove journal");
+            }
+        }
+    }
+
+    // a move is only undone if it happened, the journal does not record how far it got
+    // a journal marked as committed is only removed, its writes and moves are both done
+    pub fn recover() {
+        let entries = match fs::read_dir(Self::dir()) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            // a marker that was not renamed into place, the journal it was meant to replace is still there
+            if path.extension().is_some_and(|ext| ext == "tmp") {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+
+            let journal = match fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_json::from_slice::<JournalFile>(&content).map_err(|e| e.to_string())
+                }) {
+                Ok(journal) => journal,
+                Err(e) => {
+                    error!(path = %path.display(), error = e, "could not read journal");
+                    continue;
+                }
+            };
+
+            if journal.committed {
+                info!(path = %path.display(), "removing journal of committed transaction");
+                Self(Some(path)).finish();
+                continue;
+            }
+
+            warn!(path = %path.display(), moves = journal.moves.len(), "rolling back unfinished transaction");
+            for (from, to) in journal.moves.iter().rev() {
+                if to.exists() && !from.exists() {
+                    undo_move(from, to);
+                }
+            }
+
+            Self(Some(path)).finish();
+        }
+    }
+}
+
+// the parent of `from` may have been removed as empty after the move
+fn undo_move(from: &Path, to: &Path) {
+    let res = match from.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    };
+
+    match res.and_then(|()| fs::rename(to, from)) {
+        Ok(()) => info!(from = %to.display(), to = %from.display(), "move undone"),
+        Err(e) => error!(
+            from = %to.display(),
+            to = %from.display(),
+            error = %e,
+            "could not undo move"
+        ),
+    }
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "modcoords")]
+
+mod common;
+
+use std::fs;
+
+use common::{config_dir, run, start};
+use merlin::{CommandHandler, Storage};
+
+#[tokio::test]
+async fn backup_restores_into_a_fresh_database() {
+    let dir = config_dir("backup");
+    let attachments = dir.join("attachments");
+    fs::write(
+        dir.join("coords.jsonc"),
+        format!(
+            r#"{{ "default-attachment-path": "{}" }}"#,
+            attachments.display()
+        ),
+    )
+    .unwrap();
+    start(&dir).await;
+
+    assert_eq!(run("cogadd base").await, "Category **base** created!");
+    for line in [
+        "coordadd home ow 100 -200 base",
+        "coordadd portal nether 12 -25 base",
+        "coordadd gold nether 40 80",
+    ] {
+        assert_eq!(run(line).await, "Entry added successfully.");
+    }
+    run("coordedit home newdesc=spawn").await;
+    fs::create_dir_all(attachments.join("2")).unwrap();
+    fs::write(attachments.join("2").join("sign.txt"), "portal").unwrap();
+    run("coordrm portal").await;
+
+    fs::create_dir_all(attachments.join("3")).unwrap();
+    fs::write(attachments.join("3").join("map.png"), "gold").unwrap();
+
+    let reply = run("coords backup attachments").await;
+    assert!(
+        reply.ends_with("created with 1 categories and 2 coords, including attachments."),
+        "{reply}"
+    );
+    let name = reply.split('`').nth(1).unwrap().to_string();
+    assert!(dir.join("backups").join(&name).exists());
+
+    assert!(run(&format!("coords restore backup {name}"))
+        .await
+        .contains("is not empty"));
+    assert_eq!(
+        run("coords restore backup ../storage.jsonc").await,
+        "Expected the file name of a backup."
+    );
+
+    // a new empty database, the attachments are gone with the old host
+    // and the new one keeps them elsewhere, trashed ones go there too instead of where the archive says
+    let moved = dir.join("moved");
+    fs::write(
+        dir.join("coords.jsonc"),
+        format!(r#"{{ "default-attachment-path": "{}" }}"#, moved.display()),
+    )
+    .unwrap();
+    assert_eq!(run("core reload force").await, "Config reloaded.");
+    Storage::reload().await;
+    CommandHandler::prepare_storage().await.unwrap();
+    fs::remove_dir_all(&attachments).unwrap();
+    assert_eq!(run("find *").await, "No maching results found.");
+
+    assert_eq!(
+        run(&format!("coords restore backup {name}")).await,
+        format!("Restored 1 categories and 2 coords from `{name}`.")
+    );
+
+    assert!(run("find home")
+        .await
+        .contains("**[base.unspecified] 1: home**"));
+    assert!(run("coordhistory home")
+        .await
+        .starts_with("**home** has 1 revision."));
+    assert!(run("trash").await.contains("1. coord **portal**"));
+    assert_eq!(
+        fs::read_to_string(moved.join("3").join("map.png")).unwrap(),
+        "gold"
+    );
+    assert!(moved.join("2").join("sign.txt").exists());
+    assert!(!attachments.exists());
+
+    // counters continue after the restored ids
+    assert_eq!(
+        run("coordadd mine ow 5000 5000").await,
+        "Entry added successfully."
+    );
+    assert!(run("find mine").await.contains("4: mine"));
+    assert_eq!(run("restore 1").await, "Restored coord **portal**.");
+    assert_eq!(
+        fs::read_to_string(moved.join("2").join("sign.txt")).unwrap(),
+        "portal"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
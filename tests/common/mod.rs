@@ -0,0 +1,48 @@
+// shared by the integration tests that drive the bot through the console
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use merlin::{
+    Clearance, CommandHandler, Console, GuildClearance, GuildSwitch, MasterOptions, MasterSwitch,
+    Storage,
+};
+use serenity::{all::Http, prelude::TypeMap};
+use tokio::sync::RwLock;
+
+// runs a line on the console and joins the replies
+pub async fn run(line: &str) -> String {
+    Console::run(
+        line,
+        Arc::new(RwLock::new(TypeMap::new())),
+        Arc::new(Http::new("")),
+    )
+    .await
+    .join("\n")
+}
+
+// an empty config directory for one test binary, configs can be written to it before `start`
+pub fn config_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("merlin-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// loads the bot from the directory with the memory backend, the bot is global state so once per binary
+pub async fn start(dir: &Path) {
+    fs::write(dir.join("storage.jsonc"), r#"{ "backend": "memory" }"#).unwrap();
+    env::set_var("CONFIG", dir);
+
+    MasterOptions::setup();
+    MasterSwitch::setup();
+    GuildSwitch::setup();
+    Clearance::setup();
+    GuildClearance::setup();
+    Storage::load().await;
+    CommandHandler::load(false).await;
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::fs;
+
+use common::{config_dir, start};
+use merlin::{LiveHandle, MasterOptions, Staging};
+
+#[tokio::test]
+async fn diff_hides_token() {
+    let dir = config_dir("config");
+    fs::write(
+        dir.join("master.jsonc"),
+        r#"{ "prefix": ".", "token": "saved-secret" }"#,
+    )
+    .unwrap();
+    start(&dir).await;
+
+    // an import with a real token instead of the placeholder
+    LiveHandle::<MasterOptions>::boxed()
+        .apply(r#"{ "prefix": "!", "token": "new-secret" }"#)
+        .unwrap();
+
+    let diff = Staging::diff()
+        .into_iter()
+        .map(|(_, diff)| diff)
+        .collect::<String>();
+    assert!(diff.contains("\"!\""), "{diff}");
+    assert!(!diff.contains("secret"), "{diff}");
+
+    // the saved file is not shown when it cannot be read as a config
+    fs::write(dir.join("master.jsonc"), "token: saved-secret").unwrap();
+    let diff = Staging::diff()
+        .into_iter()
+        .map(|(_, diff)| diff)
+        .collect::<String>();
+    assert!(diff.contains("could not be parsed"), "{diff}");
+    assert!(!diff.contains("secret"), "{diff}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,263 @@
+#![cfg(feature = "modcoords")]
+
+mod common;
+
+use std::fs;
+
+use common::{config_dir, run, start};
+use merlin::{CommandHandler, Storage};
+
+// the bot is global state, so the whole flow is a single test against a fresh config directory
+#[tokio::test]
+async fn coords_flow() {
+    let dir = config_dir("test");
+    start(&dir).await;
+
+    assert_eq!(Storage::get().name(), "memory");
+
+    assert_eq!(run("cogadd base").await, "Category **base** created!");
+    assert_eq!(
+        run("cogadd base.farms").await,
+        "Subcategory **base.farms** created!"
+    );
+
+    // a numeric category would be read as the z of a coordadd with a height
+    assert_eq!(
+        run("cogadd 2024").await,
+        "Could not create new category because name cannot be an integer."
+    );
+
+    for line in [
+        "coordadd home ow 100 -200 base",
+        "coordadd portal nether 12 -25 base",
+        "coordadd gold nether 40 80 base.farms",
+    ] {
+        assert_eq!(run(line).await, "Entry added successfully.");
+    }
+
+    assert!(run("coordadd home end 0 0 base")
+        .await
+        .contains("already exists"));
+
+    let found = run("find *").await;
+    assert!(found.starts_with("Showing 3 results."), "{found}");
+
+    let near = run("find * dim=nether near=10,-20,10").await;
+    assert!(near.contains("portal") && !near.contains("gold"), "{near}");
+    let boxed = run("find * box=50,100,0,0").await;
+    assert!(
+        boxed.contains("gold") && !boxed.contains("portal"),
+        "{boxed}"
+    );
+    // the portal is at 96,-200 in the overworld
+    let linked = run("find * dim=ow near=100,-200,10 linked").await;
+    assert!(linked.starts_with("Showing 2 results."), "{linked}");
+    assert!(
+        linked.contains("at ||12, -25|| in the nether, ||96, -200|| in the overworld"),
+        "{linked}"
+    );
+    assert_eq!(
+        run("find * dim=end near=0,0,10 linked").await,
+        "Linked search requires near= and dim= to be ow or nether."
+    );
+    assert_eq!(
+        run("coordadd far ow 30000001 0").await,
+        "Entry was not added because coordinates are outside the world border."
+    );
+
+    // attachments move along with the category path
+    let (old, new) = (dir.join("old"), dir.join("new"));
+    assert_eq!(
+        run(&format!("cogedit base path={}", old.display())).await,
+        "Category details updated."
+    );
+    fs::create_dir_all(old.join("1")).unwrap();
+    fs::write(old.join("1").join("map.png"), "home").unwrap();
+    assert_eq!(
+        run(&format!("cogedit base path={}", new.display())).await,
+        "Category details updated."
+    );
+    assert!(new.join("1").join("map.png").exists());
+    assert!(!old.exists());
+    // newest first
+    assert!(found.find("**gold**").unwrap() < found.find("**home**").unwrap());
+
+    let found = run("find home").await;
+    assert!(found.contains("**[base.unspecified] 1: home**"), "{found}");
+    assert!(found.contains("x=||100|| z=||-200|| in the overworld"));
+
+    let found = run("find base dim=nether").await;
+    assert!(found.starts_with("Showing 2 results."), "{found}");
+    assert!(!found.contains("**home**"));
+
+    assert_eq!(
+        run("coordedit home newdesc=spawn newtags=main").await,
+        "1 entry updated."
+    );
+    let found = run("find home").await;
+    assert!(found.contains("spawn\nTags: main"), "{found}");
+
+    assert_eq!(
+        run("coordedit home newpos=500,600 newdim=ow").await,
+        "1 entry updated."
+    );
+    let history = run("coords history home").await;
+    assert!(
+        history.starts_with("**home** has 2 revisions."),
+        "{history}"
+    );
+    // newest first, with the old and new values
+    assert!(
+        history.find("**Revision 2**").unwrap() < history.find("**Revision 1**").unwrap(),
+        "{history}"
+    );
+    assert!(
+        history.contains("\\- x: 100 → 500\n\\- z: -200 → 600"),
+        "{history}"
+    );
+    assert!(history.contains("\\- tags: *(empty)* → main"), "{history}");
+
+    assert_eq!(
+        run("coords revert home 2").await,
+        "**home** reverted to before revision 2."
+    );
+    let found = run("find home").await;
+    assert!(found.contains("x=||100|| z=||-200||"), "{found}");
+    assert!(found.contains("spawn\nTags: main"), "{found}");
+    assert!(run("coordhistory 1")
+        .await
+        .starts_with("**home** has 3 revisions."));
+    assert_eq!(run("coordrevert home 9").await, "No such revision.");
+    assert_eq!(
+        run("coords history portal").await,
+        "**portal** has never been edited."
+    );
+
+    assert_eq!(
+        run("coordrm portal").await,
+        "1 entry removed. See `trash` to restore."
+    );
+    let found = run("find *").await;
+    assert!(found.starts_with("Showing 2 results."), "{found}");
+    assert!(!found.contains("**portal**"));
+
+    let trash = run("trash").await;
+    assert!(
+        trash.starts_with("Showing 1 of 1 trashed entries."),
+        "{trash}"
+    );
+    assert!(trash.contains("\n1. coord **portal** removed"), "{trash}");
+
+    // a removed entry comes back with its id and its name stays taken
+    assert_eq!(run("restore 1").await, "Restored coord **portal**.");
+    assert!(run("find portal").await.contains("2: portal"));
+    assert_eq!(run("trash").await, "The trash is empty.");
+    assert_eq!(run("restore 1").await, "No such entry in the trash.");
+    assert_eq!(
+        run("coordrm portal").await,
+        "1 entry removed. See `trash` to restore."
+    );
+
+    // a coord cannot come back before its category
+    assert_eq!(
+        run("coordrm gold").await,
+        "1 entry removed. See `trash` to restore."
+    );
+    assert_eq!(
+        run("cogrm base.farms").await,
+        "Category deleted. See `trash` to restore."
+    );
+    assert!(!run("cog base").await.contains("farms"));
+    assert_eq!(
+        run("restore 3").await,
+        "The category of this entry no longer exists, restore it first."
+    );
+    assert!(run("trash kind=subcategory")
+        .await
+        .contains("\n4. subcategory **farms** removed"));
+    assert_eq!(run("restore 4").await, "Restored subcategory **farms**.");
+    assert_eq!(run("restore 3").await, "Restored coord **gold**.");
+    assert!(run("find base.farms").await.contains("3: gold"));
+
+    // ids are not reused after a removal
+    assert_eq!(
+        run("coordadd mine ow 5 5 base").await,
+        "Entry added successfully."
+    );
+    assert!(run("find mine").await.contains(": mine**"));
+    assert!(run("find 4").await.contains("4: mine"));
+
+    // three numbers after the dimension are x, y and z
+    assert_eq!(
+        run("coordadd stash ow 5000 -12 5000 base").await,
+        "Entry added successfully."
+    );
+    assert!(run("find stash")
+        .await
+        .contains("x=||5000|| y=||-12|| z=||5000|| in the overworld"));
+    assert!(run("find * y=-20,0").await.contains("5: stash"));
+    assert!(run("find * dim=ow near=5000,60,5000,50")
+        .await
+        .starts_with("No maching results found."));
+    assert!(run("find * dim=ow near=5000,0,5000,50")
+        .await
+        .contains("5: stash"));
+    // the entry itself is not in the way of its new position
+    assert_eq!(
+        run("coordedit stash newpos=5000,64,5000 newdim=ow").await,
+        "1 entry updated."
+    );
+    assert!(run("find * y=60,70").await.contains("5: stash"));
+    assert_eq!(
+        run("coordedit stash newpos=5000,5000 newdim=ow").await,
+        "1 entry updated."
+    );
+    assert!(!run("find stash").await.contains("y=||"));
+
+    // names are only unique within a world
+    assert_eq!(
+        run("cogadd base world=other").await,
+        "Category **base** created!"
+    );
+    assert_eq!(
+        run("coordadd home ow 100 -200 base world=other").await,
+        "Entry added successfully."
+    );
+    assert!(run("find * world=other").await.contains(": home**"));
+    assert!(run("find mine world=other")
+        .await
+        .starts_with("No maching results found."));
+    assert_eq!(
+        run("find * world=a.b").await,
+        "World name contains illegal characters."
+    );
+
+    assert_eq!(run("find * world=clear").await, "World name is reserved.");
+    assert_eq!(
+        run("worlds other").await,
+        "Entries in this channel are now in **other**."
+    );
+    assert!(run("find mine")
+        .await
+        .starts_with("No maching results found."));
+    assert!(run("worlds")
+        .await
+        .contains("\\- other: 1 entries, 1 categories"));
+    assert_eq!(
+        run("worlds clear").await,
+        "This channel no longer has its own world, entries here are in **default**."
+    );
+    assert!(run("find mine").await.contains(": mine**"));
+
+    // imported worlds must be ones world= can select
+    let config = CommandHandler::config("coords").unwrap();
+    assert!(config
+        .check(r#"{ "default-world": "Other World" }"#)
+        .is_err());
+    assert!(config
+        .check(r#"{ "channel-worlds": { "1": "clear" } }"#)
+        .is_err());
+    assert!(config.check(r#"{ "default-world": "other" }"#).is_ok());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
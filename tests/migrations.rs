@@ -0,0 +1,84 @@
+use std::{env, fs};
+
+use merlin::{Filter, Migration, Migrations, Storage};
+use serde_json::{json, Value};
+
+fn steps() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 2,
+            description: "rename size to area",
+            collection: "items",
+            migrate: |doc| {
+                let mut doc = doc.clone();
+                let size = doc.as_object_mut()?.remove("size")?;
+                doc["area"] = size;
+                Some(doc)
+            },
+        },
+        Migration {
+            version: 1,
+            description: "add size",
+            collection: "items",
+            migrate: |doc| {
+                let mut doc = doc.clone();
+                doc.as_object_mut()?.entry("size").or_insert(json!(1));
+                Some(doc)
+            },
+        },
+    ]
+}
+
+async fn item(id: i64) -> Value {
+    Storage::get()
+        .find_one("items", Filter::new().eq("_id", id))
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn migrations_apply_once_in_order() {
+    let dir = env::temp_dir().join(format!("merlin-migrations-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("storage.jsonc"), r#"{ "backend": "memory" }"#).unwrap();
+    env::set_var("CONFIG", &dir);
+    Storage::load().await;
+
+    let store = Storage::get();
+    store
+        .insert("items", 1, json!({"_id": 1, "name": "old"}))
+        .await
+        .unwrap();
+    store
+        .insert("items", 2, json!({"_id": 2, "name": "sized", "size": 5}))
+        .await
+        .unwrap();
+
+    // later steps see what earlier ones would do, but nothing is written
+    let reports = Migrations::apply("test", &steps(), true).await.unwrap();
+    assert_eq!(
+        reports
+            .iter()
+            .map(|report| (report.version, report.changed))
+            .collect::<Vec<_>>(),
+        vec![(1, 1), (2, 2)]
+    );
+    assert_eq!(Migrations::version("test").await.unwrap(), 0);
+    assert_eq!(item(1).await, json!({"_id": 1, "name": "old"}));
+
+    let reports = Migrations::apply("test", &steps(), false).await.unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(Migrations::version("test").await.unwrap(), 2);
+    assert_eq!(item(1).await, json!({"_id": 1, "name": "old", "area": 1}));
+    assert_eq!(item(2).await, json!({"_id": 2, "name": "sized", "area": 5}));
+
+    assert!(Migrations::apply("test", &steps(), false)
+        .await
+        .unwrap()
+        .is_empty());
+    // versions are kept per module
+    assert_eq!(Migrations::version("other").await.unwrap(), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
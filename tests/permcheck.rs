@@ -0,0 +1,60 @@
+#![cfg(all(feature = "modcoords", feature = "sqlite"))]
+
+mod common;
+
+use std::{env, fs};
+
+use common::{config_dir, run};
+use merlin::{
+    Clearance, CommandHandler, Filter, GuildClearance, GuildSwitch, MasterOptions, MasterSwitch,
+    PermCheck, Storage,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn stored_rules() {
+    let dir = config_dir("permcheck");
+    fs::write(dir.join("storage.jsonc"), r#"{ "backend": "sqlite" }"#).unwrap();
+    fs::write(
+        dir.join("clearance.jsonc"),
+        r#"{ "admin": ["-everyone"], "coordmod": ["-everyone"], "coorduser": ["+everyone"] }"#,
+    )
+    .unwrap();
+    env::set_var("CONFIG", &dir);
+
+    MasterOptions::setup();
+    MasterSwitch::setup();
+    GuildSwitch::setup();
+    Clearance::setup();
+    GuildClearance::setup();
+    Storage::load().await;
+    CommandHandler::load(false).await;
+
+    run("cogadd base").await;
+    run("cogadd base.farms").await;
+    assert_eq!(PermCheck::run().await, Vec::<String>::new());
+
+    // written past the commands, the rows are checked as they are in the store
+    let store = Storage::get();
+    let mut doc = store
+        .find_one("coords-cogs", Filter::new().eq("name", "base"))
+        .await
+        .unwrap()
+        .unwrap();
+    doc["allowed"] = json!(["?missing"]);
+    for subcog in doc["subcategories"].as_object_mut().unwrap().values_mut() {
+        subcog["allowed"] = json!(["everyone"]);
+    }
+    let id = doc["_id"].as_i64().unwrap();
+    store.replace("coords-cogs", id, doc).await.unwrap();
+
+    assert_eq!(
+        PermCheck::run().await,
+        vec![
+            "coords base.farms: invalid rules [\"everyone\"]".to_string(),
+            "coords base: unknown preset missing".to_string(),
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::fs;
+
+use common::{config_dir, start};
+use merlin::{GuildSwitch, LiveConfig, MasterSwitch, PermMatrix};
+use serenity::all::GuildId;
+
+#[tokio::test]
+async fn guild_overrides() {
+    let dir = config_dir("permissions");
+    start(&dir).await;
+
+    let guild = GuildId::new(1);
+
+    GuildSwitch::switch(guild, "core", Some("ping"), Some(false));
+    assert!(!MasterSwitch::is_enabled("core", Some("ping"), Some(guild)));
+    assert!(MasterSwitch::is_enabled("core", Some("ping"), None));
+
+    // commands that act on the whole bot only follow the global switch
+    MasterSwitch::switch("core", Some("restart"), false);
+    GuildSwitch::switch(guild, "core", Some("restart"), Some(true));
+    assert!(!MasterSwitch::is_enabled(
+        "core",
+        Some("restart"),
+        Some(guild)
+    ));
+    assert!(MasterSwitch::is_global("core", Some("shutdown")));
+    assert!(!MasterSwitch::is_global("coords", Some("shutdown")));
+    assert!(MasterSwitch::is_global("core", Some("permmatrix")));
+    assert!(MasterSwitch::is_global("coords", Some("backup")));
+    assert!(!MasterSwitch::is_global("coords", Some("find")));
+
+    // the matrix only has server rows for commands that follow the server overrides
+    let matrix = PermMatrix::build().await.unwrap();
+    let labels = matrix
+        .rows
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .collect::<Vec<_>>();
+    assert!(labels.contains(&"core.ping (server 1)"));
+    assert!(labels.contains(&"core.restart"));
+    assert!(!labels.contains(&"core.restart (server 1)"));
+    assert!(!labels.contains(&"core.shutdown (server 1)"));
+
+    // guilds cannot redefine the presets those commands are guarded by
+    let presets = MasterSwitch::global_presets();
+    assert!(presets.contains("admin"));
+    assert!(!presets.contains("coordmod"));
+
+    // imported rules are checked like the ones set through perms
+    let mut switch = MasterSwitch::get_self().copy();
+    switch
+        .0
+        .get_mut("core")
+        .unwrap()
+        .commands
+        .get_mut("ping")
+        .unwrap()
+        .allowed = vec!["admin".to_string()];
+    assert!(switch.verify().is_err());
+    assert!(MasterSwitch::get_self().copy().verify().is_ok());
+    assert!(GuildSwitch::parse(r#"{ "1": { "core": { "allowed": [""] } } }"#).is_err());
+    assert!(GuildSwitch::parse(r#"{ "1": { "core": { "allowed": ["+everyone"] } } }"#).is_ok());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,470 @@
+use std::time::Duration;
+
+use merlin::{Filter, Index, MemoryStore, Sort, Store, StoreError, Write};
+use serde_json::{json, Value};
+use serenity::async_trait;
+
+const INDEXES: &[Index] = &[
+    Index::unique("name", &["name"]),
+    Index::new("tags", &["tags"]),
+];
+
+// the same checks for every backend that runs without a server
+async fn uniqueness(store: &dyn Store) {
+    for index in INDEXES {
+        store.create_index("items", index).await.unwrap();
+    }
+    // creating an existing index again does nothing
+    store.create_index("items", &INDEXES[0]).await.unwrap();
+
+    let first = store.next_id("items").await.unwrap();
+    let second = store.next_id("items").await.unwrap();
+    assert_eq!((first, second), (1, 2));
+
+    store
+        .insert("items", first, json!({"_id": first, "name": "home"}))
+        .await
+        .unwrap();
+    store
+        .insert("items", second, json!({"_id": second, "name": "farm"}))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        store
+            .insert("items", 3, json!({"_id": 3, "name": "home"}))
+            .await,
+        Err(StoreError::Duplicate(index)) if index == "name"
+    ));
+    assert!(matches!(
+        store
+            .insert("items", first, json!({"_id": first, "name": "mine"}))
+            .await,
+        Err(StoreError::Duplicate(index)) if index == "_id"
+    ));
+    assert!(matches!(
+        store
+            .replace("items", second, json!({"_id": second, "name": "home"}))
+            .await,
+        Err(StoreError::Duplicate(index)) if index == "name"
+    ));
+
+    // a document keeps its own name
+    assert!(store
+        .replace(
+            "items",
+            first,
+            json!({"_id": first, "name": "home", "tags": ["a"]})
+        )
+        .await
+        .unwrap());
+
+    let found = store
+        .find("items", &Filter::new(), Sort::Ascending("name"))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0]["name"], "farm");
+
+    store.raise_counter("items", 10).await.unwrap();
+    store.raise_counter("items", 5).await.unwrap();
+    assert_eq!(store.next_id("items").await.unwrap(), 10);
+
+    // a dropped index no longer applies, dropping it again does nothing
+    store.drop_index("items", "name").await.unwrap();
+    store.drop_index("items", "name").await.unwrap();
+    store
+        .create_index("items", &Index::unique("world-name", &["world", "name"]))
+        .await
+        .unwrap();
+
+    store
+        .insert(
+            "items",
+            20,
+            json!({"_id": 20, "world": "other", "name": "home"}),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        store
+            .insert("items", 21, json!({"_id": 21, "world": "other", "name": "home"}))
+            .await,
+        Err(StoreError::Duplicate(index)) if index == "world-name"
+    ));
+}
+
+// a failed write undoes the writes before it
+async fn atomic(store: &dyn Store) {
+    store.create_index("items", &INDEXES[0]).await.unwrap();
+    store
+        .insert("items", 1, json!({"_id": 1, "name": "home"}))
+        .await
+        .unwrap();
+    store
+        .insert("items", 2, json!({"_id": 2, "name": "farm"}))
+        .await
+        .unwrap();
+
+    let failing = [
+        // the name is taken by the document deleted before it
+        (
+            Write::Delete {
+                collection: "items",
+                id: 2,
+            },
+            Write::Insert {
+                collection: "items",
+                id: 3,
+                doc: json!({"_id": 3, "name": "home"}),
+            },
+        ),
+        (
+            Write::Replace {
+                collection: "items",
+                id: 1,
+                doc: json!({"_id": 1, "name": "base"}),
+            },
+            Write::Replace {
+                collection: "items",
+                id: 4,
+                doc: json!({"_id": 4, "name": "mine"}),
+            },
+        ),
+    ];
+
+    for (first, second) in failing {
+        assert!(store.commit(vec![first, second]).await.is_err());
+
+        let found = store
+            .find("items", &Filter::new(), Sort::Ascending("name"))
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            (&found[0]["name"], &found[1]["name"]),
+            (&json!("farm"), &json!("home"))
+        );
+    }
+
+    store
+        .commit(vec![
+            Write::Upsert {
+                collection: "items",
+                id: 1,
+                doc: json!({"_id": 1, "name": "base"}),
+            },
+            Write::Delete {
+                collection: "items",
+                id: 2,
+            },
+            Write::Insert {
+                collection: "items",
+                id: 3,
+                doc: json!({"_id": 3, "name": "farm"}),
+            },
+        ])
+        .await
+        .unwrap();
+
+    let found = store
+        .find("items", &Filter::new(), Sort::Ascending("name"))
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!((&found[0]["_id"], &found[1]["_id"]), (&json!(1), &json!(3)));
+}
+
+// points on and just outside the edge of the shapes
+async fn geo(store: &dyn Store) {
+    store
+        .create_index("places", &Index::geo("pos", &["pos", "dim"], 1000))
+        .await
+        .unwrap();
+
+    for (id, x, z, dim) in [
+        (1, 0, 0, "ow"),
+        (2, 3, 4, "ow"),
+        (3, 4, 4, "ow"),
+        (4, 3, 4, "nether"),
+        (5, -10, 20, "ow"),
+    ] {
+        store
+            .insert("places", id, json!({"_id": id, "pos": [x, z], "dim": dim}))
+            .await
+            .unwrap();
+    }
+
+    let ids = |found: Vec<Value>| {
+        found
+            .iter()
+            .map(|doc| doc["_id"].as_i64().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let found = store
+        .find(
+            "places",
+            Filter::new()
+                .within_radius("pos", (0, 0), 5)
+                .eq("dim", "ow"),
+            Sort::Natural,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids(found), [1, 2]);
+
+    // corners in any order
+    let found = store
+        .find(
+            "places",
+            Filter::new().within_box("pos", (4, 20), (-10, 4)),
+            Sort::Natural,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids(found), [2, 3, 4, 5]);
+
+    let found = store
+        .find(
+            "places",
+            Filter::new()
+                .within_box("pos", (4, 20), (-10, 4))
+                .between("_id", 3, 4),
+            Sort::Natural,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids(found), [3, 4]);
+}
+
+async fn counts(store: &dyn Store) {
+    for (id, world) in [
+        (1, json!("a")),
+        (2, json!("b")),
+        (3, json!("a")),
+        (4, Value::Null),
+    ] {
+        let mut doc = json!({"_id": id});
+        if !world.is_null() {
+            doc["world"] = world;
+        }
+        store.insert("worlds", id, doc).await.unwrap();
+    }
+
+    let mut counts = store.count_by("worlds", "world").await.unwrap();
+    counts.sort_by_key(|(value, _)| value.to_string());
+    assert_eq!(counts, [(json!("a"), 2), (json!("b"), 1), (Value::Null, 1)]);
+}
+
+// leaves commit to the default, like a mongodb server without transactions
+struct InOrder(MemoryStore);
+
+#[async_trait]
+impl Store for InOrder {
+    fn name(&self) -> &'static str {
+        "in-order"
+    }
+
+    async fn find(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        sort: Sort,
+    ) -> Result<Vec<Value>, StoreError> {
+        self.0.find(collection, filter, sort).await
+    }
+
+    async fn insert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        self.0.insert(collection, id, doc).await
+    }
+
+    async fn replace(&self, collection: &str, id: i64, doc: Value) -> Result<bool, StoreError> {
+        self.0.replace(collection, id, doc).await
+    }
+
+    async fn upsert(&self, collection: &str, id: i64, doc: Value) -> Result<(), StoreError> {
+        self.0.upsert(collection, id, doc).await
+    }
+
+    async fn delete(&self, collection: &str, id: i64) -> Result<bool, StoreError> {
+        self.0.delete(collection, id).await
+    }
+
+    async fn next_id(&self, counter: &str) -> Result<i64, StoreError> {
+        self.0.next_id(counter).await
+    }
+
+    async fn raise_counter(&self, counter: &str, next: i64) -> Result<(), StoreError> {
+        self.0.raise_counter(counter, next).await
+    }
+
+    async fn peek_counter(&self, counter: &str) -> Result<i64, StoreError> {
+        self.0.peek_counter(counter).await
+    }
+
+    async fn create_index(&self, collection: &str, index: &Index) -> Result<(), StoreError> {
+        self.0.create_index(collection, index).await
+    }
+
+    async fn drop_index(&self, collection: &str, name: &str) -> Result<(), StoreError> {
+        self.0.drop_index(collection, name).await
+    }
+
+    async fn ping(&self) -> Option<(Duration, String)> {
+        self.0.ping().await
+    }
+}
+
+#[tokio::test]
+async fn memory_geo() {
+    geo(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn memory_atomic() {
+    atomic(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn in_order_atomic() {
+    atomic(&InOrder(MemoryStore::new())).await;
+}
+
+#[tokio::test]
+async fn memory_uniqueness() {
+    uniqueness(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn memory_index_over_duplicates() {
+    let store = MemoryStore::new();
+    store
+        .insert("items", 1, json!({"_id": 1, "name": "home"}))
+        .await
+        .unwrap();
+    store
+        .insert("items", 2, json!({"_id": 2, "name": "home"}))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        store.create_index("items", &INDEXES[0]).await,
+        Err(StoreError::Duplicate(_))
+    ));
+}
+
+#[tokio::test]
+async fn memory_counts() {
+    counts(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn memory_regex() {
+    let store = MemoryStore::new();
+    for (id, name) in [(1, "home"), (2, "homestead"), (3, "portal")] {
+        store
+            .insert("items", id, json!({"_id": id, "name": name}))
+            .await
+            .unwrap();
+    }
+
+    let found = store
+        .find("items", Filter::new().regex("name", "^home"), Sort::Natural)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 2);
+
+    // an invalid pattern fails the find instead of matching nothing
+    assert!(store
+        .find("items", Filter::new().regex("name", "("), Sort::Natural)
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_uniqueness() {
+    let path = std::env::temp_dir().join(format!("merlin-store-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    uniqueness(&merlin::SqliteStore::open(&path).unwrap()).await;
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_atomic() {
+    let path = std::env::temp_dir().join(format!("merlin-atomic-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    atomic(&merlin::SqliteStore::open(&path).unwrap()).await;
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_counts() {
+    let path = std::env::temp_dir().join(format!("merlin-counts-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    counts(&merlin::SqliteStore::open(&path).unwrap()).await;
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_geo() {
+    let path = std::env::temp_dir().join(format!("merlin-geo-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    geo(&merlin::SqliteStore::open(&path).unwrap()).await;
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_query_plan() {
+    let path = std::env::temp_dir().join(format!("merlin-plan-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let store = merlin::SqliteStore::open(&path).unwrap();
+
+    geo(&store).await;
+    store
+        .create_index("places", &Index::new("dim", &["dim"]))
+        .await
+        .unwrap();
+
+    let uses = |plan: Vec<String>, index: &str| {
+        let index = format!("USING INDEX places.{index} (");
+        assert!(plan.iter().any(|step| step.contains(&index)), "{plan:?}");
+    };
+
+    // the equality field comes first in the geo index, then the coordinates
+    uses(
+        store
+            .query_plan(
+                "places",
+                Filter::new()
+                    .within_radius("pos", (0, 0), 5)
+                    .eq("dim", "ow"),
+                Sort::Natural,
+            )
+            .await
+            .unwrap(),
+        "pos",
+    );
+    uses(
+        store
+            .query_plan("places", Filter::new().eq("dim", "nether"), Sort::Natural)
+            .await
+            .unwrap(),
+        "dim",
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,71 @@
+use std::{env, fs};
+
+use merlin::{Collection, Filter, Journal, Storage, Transaction};
+use serde_json::{json, Value};
+
+const ITEMS: Collection<Value> = Collection::new("items");
+
+// the checks share the config directory, so they run in one test
+#[tokio::test]
+async fn moves_follow_the_writes() {
+    let dir = env::temp_dir().join(format!("merlin-transaction-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("from").join("1")).unwrap();
+    fs::write(dir.join("storage.jsonc"), r#"{ "backend": "memory" }"#).unwrap();
+    env::set_var("CONFIG", &dir);
+
+    Storage::load().await;
+
+    // the replace fails, so the insert and the move are undone
+    let mut tx = Transaction::new();
+    tx.insert(&ITEMS, 1, &json!({"_id": 1})).unwrap();
+    tx.rename(dir.join("from").join("1"), dir.join("to").join("1"));
+    tx.replace(&ITEMS, 2, &json!({"_id": 2})).unwrap();
+    assert!(tx.commit().await.is_err());
+
+    assert!(ITEMS.find(&Filter::new()).await.unwrap().is_empty());
+    assert!(dir.join("from").join("1").exists());
+    assert!(!dir.join("to").join("1").exists());
+    assert_eq!(fs::read_dir(dir.join("journal")).unwrap().count(), 0);
+
+    let mut tx = Transaction::new();
+    tx.insert(&ITEMS, 1, &json!({"_id": 1})).unwrap();
+    tx.rename(dir.join("from").join("1"), dir.join("to").join("1"));
+    tx.remove_dir_if_empty(dir.join("from"));
+    tx.commit().await.unwrap();
+
+    assert_eq!(ITEMS.find(&Filter::new()).await.unwrap().len(), 1);
+    assert!(dir.join("to").join("1").exists());
+    assert!(!dir.join("from").exists());
+
+    // a journal left by a bot that stopped before committing
+    fs::write(
+        dir.join("journal").join("stopped.json"),
+        json!({"moves": [[dir.join("from").join("1"), dir.join("to").join("1")]]}).to_string(),
+    )
+    .unwrap();
+    Journal::recover();
+
+    assert!(dir.join("from").join("1").exists());
+    assert!(!dir.join("to").join("1").exists());
+    assert_eq!(fs::read_dir(dir.join("journal")).unwrap().count(), 0);
+
+    // one that stopped after committing keeps its moves
+    fs::rename(dir.join("from").join("1"), dir.join("to").join("1")).unwrap();
+    fs::write(
+        dir.join("journal").join("committed.json"),
+        json!({
+            "moves": [[dir.join("from").join("1"), dir.join("to").join("1")]],
+            "committed": true
+        })
+        .to_string(),
+    )
+    .unwrap();
+    Journal::recover();
+
+    assert!(!dir.join("from").join("1").exists());
+    assert!(dir.join("to").join("1").exists());
+    assert_eq!(fs::read_dir(dir.join("journal")).unwrap().count(), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}